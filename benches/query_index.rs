@@ -0,0 +1,77 @@
+//! compares running queries by scanning the whole log against running them through the index.
+//! Run with `cargo bench --bench query_index`. Set `LOGVIZ_BENCH_EVENTS` to change the log size
+
+use std::env;
+use std::time::{Duration, Instant};
+use time::macros::datetime;
+use log_viz::ds_events::abstract_object::AbstractObject;
+use log_viz::ds_events::event::{Event, EventLevel};
+use log_viz::ds_events::index::EventIndex;
+use log_viz::ds_events::schema::LogSchema;
+use log_viz::dsl::filters::EventSequenceQuery;
+use log_viz::dsl::parser::parse_event_query;
+
+const DEFAULT_EVENT_COUNT: usize = 500_000;
+const RUNS: u32 = 5;
+
+const QUERIES: &[&str] = &[
+    "find PaxosSlotEntry eq(slotNum, 4242)",
+    "find PaxosSlotEntry and { eq(slotStatus, \"CHOSEN\"); server(3) }",
+    "find any or { eq(sequenceNum, 17); eq(slotNum, 17) }",
+    "find PaxosRequest eq(clientId, \"client3\")",
+    "find any not(server(0))",
+];
+
+/// a log shaped like a paxos run across five servers
+fn synthetic_log(event_count: usize) -> Vec<Event> {
+    (0..event_count)
+        .map(|id| {
+            let obj = match id % 5 {
+                0 => AbstractObject::new("PaxosRequest")
+                    .with_prop("sequenceNum", (id / 5 % 1000) as i64)
+                    .with_prop("clientId", format!("client{}", id % 7)),
+                1 => AbstractObject::new("Heartbeat")
+                    .with_prop("ballot", (id / 100) as i64),
+                _ => AbstractObject::new("PaxosSlotEntry")
+                    .with_prop("slotNum", (id / 3 % 10_000) as i64)
+                    .with_prop("slotStatus", if id % 3 == 0 { "CHOSEN" } else { "ACCEPTED" })
+                    .with_prop("ballot", (id / 100) as i64),
+            };
+            Event::new(id, EventLevel::Info, datetime!(2024-03-05 22:59:25), format!("server{}", id % 5), obj)
+        })
+        .collect()
+}
+
+/// the average time each run of `f` took
+fn time_runs<T>(mut f: impl FnMut() -> T) -> (Duration, T) {
+    let mut result = f();
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        result = f();
+    }
+
+    (start.elapsed() / RUNS, result)
+}
+
+fn main() {
+    let event_count = env::var("LOGVIZ_BENCH_EVENTS").ok()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(DEFAULT_EVENT_COUNT);
+
+    let events = synthetic_log(event_count);
+    let schema = LogSchema::infer(&events);
+    let build_start = Instant::now();
+    let index = EventIndex::build(&events, &schema);
+    println!("{} events, index built in {:?}\n", event_count, build_start.elapsed());
+
+    for query_text in QUERIES {
+        let (_, query) = parse_event_query(query_text).expect("Benchmark queries should parse");
+        let (scan_time, scanned) = time_runs(|| query.eval(&events).expect("Benchmark queries should evaluate"));
+        let (indexed_time, indexed) = time_runs(|| query.eval_indexed(&events, &index).expect("Benchmark queries should evaluate"));
+        assert_eq!(scanned, indexed, "indexed results differ for {}", query_text);
+
+        println!("{}", query_text);
+        println!("    {} matches, scan {:?}, indexed {:?} ({:.1}x)\n", scanned.len(), scan_time, indexed_time,
+                 scan_time.as_secs_f64() / indexed_time.as_secs_f64());
+    }
+}
@@ -1,14 +1,46 @@
+mod completion_state;
+pub mod details_state;
+mod diff_state;
 mod filter_state;
+mod filter_view_state;
+pub mod goto_state;
+pub mod highlight_state;
+pub mod history_state;
 mod messages_state;
 mod navigation_state;
+pub mod picker_state;
+mod projection_state;
+mod query_worker;
+pub mod schema_state;
+pub mod search_state;
 
+use std::collections::BTreeSet;
 use std::error;
-use tui_textarea::TextArea;
+use std::sync::Arc;
+use ratatui::style::Color;
+use tui_textarea::{CursorMove, TextArea};
+use crate::app::completion_state::CompletionState;
+use crate::app::details_state::{DetailQuery, DetailsState};
 use crate::app::filter_state::FilterState;
+use crate::app::filter_view_state::FilterViewState;
+use crate::app::goto_state::{GotoState, GotoTarget};
+use crate::app::diff_state::DiffState;
+use crate::app::highlight_state::{Highlight, HighlightState};
+use crate::app::history_state::HistoryState;
 use crate::app::messages_state::MessagesState;
 use crate::app::navigation_state::NavigationState;
+use crate::app::picker_state::PickerState;
+use crate::app::projection_state::ProjectionState;
+use crate::app::query_worker::{QueryUpdate, QueryWorker};
+use crate::app::schema_state::SchemaState;
+use crate::app::search_state::SearchState;
 use crate::ds_events::event::Event;
-use crate::dsl::query_ast::EventQuery;
+use crate::ds_events::index::EventIndex;
+use crate::ds_events::schema::LogSchema;
+use crate::dsl::diagnostics::QueryDiagnostic;
+use crate::dsl::macros::QueryLibrary;
+use crate::dsl::parser::{parse_highlight, parse_statement};
+use crate::dsl::query_ast::{EventQuery, HighlightRule, PropPath, QueryStatement};
 use crate::ui::event_list::EventListState;
 
 /// Application result type.
@@ -18,21 +50,30 @@ pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 pub enum FocusedWindow {
     EventList,
     QueryEditor,
+    SchemaBrowser,
+    QueryPicker,
+    EventDetails,
 }
 
 impl FocusedWindow {
     pub fn is_event_list(&self) -> bool {
-        match self {
-            FocusedWindow::EventList => true,
-            _ => false
-        }
+        matches!(self, FocusedWindow::EventList)
     }
 
     pub fn is_filter_list(&self) -> bool {
-        match self {
-            FocusedWindow::QueryEditor => true,
-            _ => false,
-        }
+        matches!(self, FocusedWindow::QueryEditor)
+    }
+
+    pub fn is_schema_browser(&self) -> bool {
+        matches!(self, FocusedWindow::SchemaBrowser)
+    }
+
+    pub fn is_query_picker(&self) -> bool {
+        matches!(self, FocusedWindow::QueryPicker)
+    }
+
+    pub fn is_event_details(&self) -> bool {
+        matches!(self, FocusedWindow::EventDetails)
     }
 }
 
@@ -43,20 +84,62 @@ pub struct App<'a> {
     pub running: bool,
     /// the current portion of the screen we have focused
     pub focused_window: FocusedWindow,
-    /// the events to display
-    pub events: Vec<Event>,
+    /// the events to display. Shared with the thread running the current query
+    pub events: Arc<[Event]>,
     /// list state used to control the main event list display
     pub event_list_state: EventListState,
     /// our query text editor + state
     pub query_text_area: TextArea<'a>,
     /// used for filtering the events
     pub filter_state: FilterState,
+    /// used for showing only the events that match
+    pub filter_view_state: FilterViewState,
+    /// list state used to control the event list while it only shows matches
+    pub filter_list_state: EventListState,
     /// used for controlling the messages view
     pub message_state: MessagesState,
     /// the index of the currently selected event, or none if no event is selected
     pub selected_event: Option<usize>,
+    /// the index of the event the selected event is compared with, if one is marked
+    pub marked_event: Option<usize>,
+    /// the comparison of the marked event with the selected one
+    pub diff_state: DiffState,
     /// used for navigating selected events
     pub navigation_state: NavigationState,
+    /// used for browsing the selected event's object
+    pub details_state: DetailsState,
+    /// the queries whose matches are drawn in their own colors
+    pub highlight_state: HighlightState,
+    /// used for searching the text of events
+    pub search_state: SearchState,
+    /// used for jumping to an event by id, time or how far through the log it is
+    pub goto_state: GotoState,
+    /// the previous state of each event, for showing what changed since it
+    pub history_state: HistoryState,
+    /// which fields of each event are shown in the event list, and how they are sorted
+    pub projection_state: ProjectionState,
+    /// the fields each kind of object in the log has
+    pub schema: LogSchema,
+    /// used for finding the events a query could match without scanning the whole log
+    pub index: Arc<EventIndex>,
+    /// used for browsing the schema
+    pub schema_state: SchemaState,
+    /// true if the evaluation of the active query against the selected event is shown
+    pub explain_visible: bool,
+    /// true if what each key does is shown
+    pub help_visible: bool,
+    /// the named queries loaded from the project file
+    pub library: QueryLibrary,
+    /// used for picking a named query
+    pub picker_state: PickerState,
+    /// the completions offered for the word at the query editor's cursor
+    pub completion_state: CompletionState,
+    /// the syntax error from the last query we tried to run, if it didn't parse
+    pub query_error: Option<QueryDiagnostic>,
+    /// the query being run in the background, if any
+    pub query_worker: Option<QueryWorker>,
+    /// the queries of highlight rules being run in the background, labelled with the rule's name
+    pub highlight_workers: Vec<QueryWorker>,
 }
 
 impl<'a> Default for App<'a> {
@@ -68,9 +151,30 @@ impl<'a> Default for App<'a> {
             event_list_state: Default::default(),
             query_text_area: Default::default(),
             filter_state: Default::default(),
+            filter_view_state: Default::default(),
+            filter_list_state: Default::default(),
             message_state: Default::default(),
             selected_event: None,
+            marked_event: None,
+            diff_state: Default::default(),
             navigation_state: Default::default(),
+            details_state: Default::default(),
+            highlight_state: Default::default(),
+            search_state: Default::default(),
+            goto_state: Default::default(),
+            history_state: Default::default(),
+            projection_state: Default::default(),
+            schema: Default::default(),
+            index: Default::default(),
+            schema_state: Default::default(),
+            explain_visible: false,
+            help_visible: false,
+            library: Default::default(),
+            picker_state: Default::default(),
+            completion_state: Default::default(),
+            query_error: None,
+            query_worker: None,
+            highlight_workers: Vec::new(),
         }
     }
 }
@@ -86,31 +190,676 @@ impl<'a> App<'a> {
         };
         
         let event_count = events.len();
+        let schema = LogSchema::infer(&events);
+        let index = EventIndex::build(&events, &schema);
+        let history_state = HistoryState::build(&events, None);
         
         Self {
-            events,
+            events: events.into(),
+            schema,
+            index: Arc::new(index),
+            history_state,
             event_list_state: EventListState::new(event_count, starting_index),
             ..Default::default()
         }
     }
 
+    /// tells apart the things each node logs the state of by the value at `key`, so an event's
+    /// changes are shown against the last event about the same thing
+    pub fn with_state_key(mut self, key: PropPath) -> Self {
+        self.history_state = HistoryState::build(&self.events, Some(key));
+        self
+    }
+
+    /// uses the named queries and highlight rules from a project file. Rules earlier in the file
+    /// take priority over later ones
+    pub fn with_library(mut self, library: QueryLibrary) -> Self {
+        for (line, text) in library.highlights().iter().rev() {
+            let rule = library.expand(text)
+                .map_err(|err| err.to_string())
+                .and_then(|expansion| {
+                    parse_highlight(expansion.text())
+                        .map_err(|diagnostic| expansion.locate(text, diagnostic).to_string())
+                });
+            match rule {
+                Ok(rule) => self.add_highlight(rule),
+                Err(err) => self.message_state.push(format!("error in highlight rule on line {}: {}", line, err)),
+            }
+        }
+        
+        self.library = library;
+        self
+    }
+
     pub fn select_event(&mut self, index: usize) {
         self.selected_event = Some(index);
     }
 
     pub fn clear_selected_event(&mut self) {
-        self.selected_event = None
+        self.selected_event = None;
+        if self.focused_window.is_event_details() {
+            self.focused_window = FocusedWindow::EventList;
+        }
+    }
+    
+    /// marks the event under the cursor to compare the selected event with, or unmarks it if it's
+    /// already marked
+    pub fn toggle_marked_event(&mut self) {
+        let Some(event_idx) = self.event_at_cursor() else {
+            return;
+        };
+        
+        if self.marked_event == Some(event_idx) {
+            self.marked_event = None;
+            self.message_state.push(format!("Unmarked event {}", event_idx));
+        } else {
+            self.marked_event = Some(event_idx);
+            self.message_state.push(format!("Marked event {}. Select another event to compare it with", event_idx));
+        }
+    }
+    
+    /// builds a query from the value under the cursor in the details, then runs it over every
+    /// event or puts it in the query editor
+    pub fn query_from_details(&mut self, query: DetailQuery, run: bool) {
+        let Some(event) = self.selected_event.and_then(|event_idx| self.events.get(event_idx)) else {
+            return;
+        };
+        let Some(row) = self.details_state.selected_row(event.event_obj()) else {
+            return;
+        };
+        
+        let statement = row.query_text(query, event.event_obj().name())
+            .and_then(|text| parse_statement(&text)
+                .map(|statement| (text, statement))
+                .map_err(|diagnostic| diagnostic.message().to_string()));
+        let (text, statement) = match statement {
+            Ok(built) => built,
+            Err(err) => {
+                self.message_state.push(format!("Can't build a query from {}: {}", row.label(), err));
+                return;
+            }
+        };
+        
+        // replace what's in the editor in a way that can be undone
+        self.query_text_area.select_all();
+        self.query_text_area.cut();
+        self.query_text_area.insert_str(&text);
+        self.query_error = None;
+        if run {
+            // the query is about the event's value, not the matches of whatever query is active
+            self.apply_statement(statement, &text, false);
+        } else {
+            self.focused_window = FocusedWindow::QueryEditor;
+        }
+    }
+    
+    /// focuses the details of the selected event, if there is one
+    pub fn focus_event_details(&mut self) {
+        match self.selected_event {
+            Some(event_idx) => {
+                self.details_state.show_event(event_idx);
+                self.focused_window = FocusedWindow::EventDetails;
+            }
+            None => self.message_state.push("No event is selected. Press Enter on an event to see its details"),
+        }
+    }
+
+    /// the index of the event shown in the currently selected row of the event list, if there is one
+    pub fn event_at_cursor(&self) -> Option<usize> {
+        if self.filter_view_state.is_enabled() {
+            return self.filter_view_state.event_at_row(self.filter_list_state.selected_row());
+        }
+        
+        let event_idx = self.projection_state.event_at_row(self.event_list_state.selected_row());
+        (event_idx < self.events.len()).then_some(event_idx)
+    }
+    
+    /// the state of the event list being shown, which depends on if only matches are shown
+    pub fn active_list_state(&mut self) -> &mut EventListState {
+        if self.filter_view_state.is_enabled() {
+            &mut self.filter_list_state
+        } else {
+            &mut self.event_list_state
+        }
+    }
+    
+    /// how many rows the event list being shown has
+    fn shown_row_count(&self) -> usize {
+        if self.filter_view_state.is_enabled() {
+            self.filter_view_state.rows().len()
+        } else {
+            self.events.len()
+        }
+    }
+    
+    /// the event at the given row of the event list being shown
+    fn event_at_shown_row(&self, row: usize) -> Option<usize> {
+        if self.filter_view_state.is_enabled() {
+            self.filter_view_state.event_at_row(row)
+        } else {
+            Some(self.projection_state.event_at_row(row)).filter(|event_idx| *event_idx < self.events.len())
+        }
+    }
+    
+    /// moves the cursor of the event list being shown to the given event, if it's shown
+    fn focus_event(&mut self, event_idx: usize) {
+        if self.filter_view_state.is_enabled() {
+            if let Some(row) = self.filter_view_state.row_of_event(event_idx, &self.projection_state) {
+                self.filter_list_state.focus_event(row);
+            }
+        } else {
+            self.event_list_state.focus_event(self.projection_state.row_of_event(event_idx));
+        }
+    }
+    
+    /// switches between showing every event and only the events that match the active query
+    pub fn toggle_filter_view(&mut self) {
+        if !self.filter_view_state.is_enabled() && !self.filter_state.has_active_filter() && !self.is_query_running() {
+            self.message_state.push("No query is active. Run a query to show only its matches");
+            return;
+        }
+        
+        // stay on the same event in the list being switched to
+        let cursor_event = self.event_at_cursor();
+        self.filter_view_state.set_enabled(!self.filter_view_state.is_enabled());
+        self.refresh_filter_view();
+        self.restore_cursor(cursor_event);
+    }
+    
+    /// shows one more event around each match while only matches are shown
+    pub fn grow_filter_context(&mut self) {
+        self.filter_view_state.grow_context();
+        self.refresh_filter_view();
+        self.message_state.push(format!("Showing {} event(s) around each match", self.filter_view_state.context()));
+    }
+    
+    /// shows one less event around each match while only matches are shown
+    pub fn shrink_filter_context(&mut self) {
+        self.filter_view_state.shrink_context();
+        self.refresh_filter_view();
+        self.message_state.push(format!("Showing {} event(s) around each match", self.filter_view_state.context()));
+    }
+    
+    /// works out which events the filtered list shows again, after the matches or their order changed
+    fn refresh_filter_view(&mut self) {
+        if !self.filter_view_state.is_enabled() {
+            return;
+        }
+        
+        let cursor_event = self.event_at_cursor();
+        let changed = self.filter_view_state.update(self.filter_state.matching_events(), &self.projection_state, self.events.len());
+        if changed {
+            self.restore_cursor(cursor_event);
+        }
+    }
+    
+    /// puts the cursor of the event list being shown back on the given event after its rows
+    /// changed. If the event isn't shown anymore, the cursor goes to the next one that is
+    fn restore_cursor(&mut self, event_idx: Option<usize>) {
+        if self.filter_view_state.is_enabled() {
+            let row = match event_idx {
+                Some(event_idx) => self.filter_view_state.nearest_row(event_idx, &self.projection_state),
+                None => self.filter_list_state.selected_row(),
+            };
+            self.filter_list_state.set_rows(self.filter_view_state.rows().len(), row);
+        } else {
+            let row = event_idx.map_or(self.event_list_state.selected_row(), |event_idx| self.projection_state.row_of_event(event_idx));
+            self.event_list_state.set_rows(self.events.len(), row);
+        }
     }
 
-    pub fn push_new_filter_state(&mut self, event: EventQuery) {
-        self.message_state.push("Successfully updated query");
-        self.filter_state.push_new_filter(event, &self.events);
-        self.navigation_state.load_nav_order(self.filter_state.nav_order());
+    /// runs the statement's query, if it has one, and shows its columns. `text` is what the user
+    /// wrote, which labels the query in the breadcrumb. If `refine` is false the query searches
+    /// every event instead of the matches of the active query
+    pub fn apply_statement(&mut self, statement: QueryStatement, text: &str, refine: bool) {
+        let (query, projection) = statement.into_parts();
+        if let Some(query) = query {
+            let label = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            self.push_new_filter_state(query, label, refine);
+        }
+        
+        if let Some(columns) = projection {
+            self.set_projection(columns);
+        }
+    }
+
+    pub fn toggle_explain(&mut self) {
+        if !self.filter_state.has_active_filter() {
+            self.message_state.push("No query is active. Run a query to explain how it evaluates");
+            return;
+        }
+        
+        self.explain_visible = !self.explain_visible;
+        if self.explain_visible && self.selected_event.is_none() {
+            self.selected_event = self.event_at_cursor();
+        }
+    }
+
+    /// shows the schema browser and focuses it, or hides it if it's already focused
+    pub fn toggle_schema_browser(&mut self) {
+        if self.schema_state.is_visible() && self.focused_window.is_schema_browser() {
+            self.schema_state.set_visible(false);
+            self.focused_window = FocusedWindow::EventList;
+        } else {
+            self.schema_state.set_visible(true);
+            self.focused_window = FocusedWindow::SchemaBrowser;
+        }
+    }
+
+    /// shows the query picker and focuses it, or hides it if it's already focused
+    pub fn toggle_query_picker(&mut self) {
+        if self.picker_state.is_visible() && self.focused_window.is_query_picker() {
+            self.picker_state.set_visible(false);
+            self.focused_window = FocusedWindow::EventList;
+        } else {
+            self.picker_state.set_visible(true);
+            self.focused_window = FocusedWindow::QueryPicker;
+        }
+    }
+
+    /// writes a call to the query under the picker's cursor into the editor, and focuses it
+    pub fn insert_picked_query(&mut self) {
+        let Some(query_macro) = self.library.macros().get(self.picker_state.cursor()) else {
+            return;
+        };
+
+        self.query_text_area.insert_str(format!("{}()", query_macro.name()));
+        if !query_macro.params().is_empty() {
+            // leave the cursor where the arguments go
+            self.query_text_area.move_cursor(CursorMove::Back);
+        }
+        self.picker_state.set_visible(false);
+        self.focused_window = FocusedWindow::QueryEditor;
+    }
+
+    pub fn set_projection(&mut self, columns: Vec<PropPath>) {
+        let cursor_event = self.event_at_cursor();
+        self.projection_state.set_columns(columns, &self.events);
+        self.refresh_filter_view();
+        self.restore_cursor(cursor_event);
+        self.message_state.push("Successfully updated columns");
+    }
+
+    pub fn cycle_sort_column(&mut self) {
+        if !self.projection_state.is_active() {
+            self.message_state.push("No columns are selected. Add a select clause to sort by a column");
+            return;
+        }
+        
+        let cursor_event = self.event_at_cursor();
+        self.projection_state.cycle_sort_column(&self.events);
+        self.refresh_filter_view();
+        self.restore_cursor(cursor_event);
+    }
+
+    pub fn toggle_sort_direction(&mut self) {
+        let cursor_event = self.event_at_cursor();
+        self.projection_state.toggle_sort_direction(&self.events);
+        self.refresh_filter_view();
+        self.restore_cursor(cursor_event);
+    }
+
+    /// starts running the query in the background over the events that match the active query,
+    /// if any and `refine` is true, or else over every event. Matches show up as they are found,
+    /// and the query is pushed on top of the active one once it finishes
+    pub fn push_new_filter_state(&mut self, event: EventQuery, label: String, refine: bool) {
+        if let Some(worker) = self.query_worker.take() {
+            worker.cancel();
+        }
+        
+        // n/N go through the new query's matches instead of the search text
+        self.search_state.stop_navigating();
+        let within = self.filter_state.refined_events().filter(|_| refine).cloned();
+        self.filter_state.start_layer(refine);
+        self.refresh_filter_view();
+        self.reload_nav_order();
+        let searched_count = within.as_ref().map_or(self.events.len(), |within| within.len());
+        self.message_state.push(format!("Running query over {} events. Press Esc to cancel", searched_count));
+        for issue in event.find_issues(&self.schema) {
+            self.message_state.push(format!("warning: {}", issue));
+        }
+        
+        self.query_worker = Some(QueryWorker::spawn(label, event, Arc::clone(&self.events), Arc::clone(&self.index), within));
+    }
+    
+    pub fn is_query_running(&self) -> bool {
+        self.query_worker.is_some()
+    }
+    
+    /// stops the query running in the background, and drops what it has found so far. The query
+    /// it would have refined stays active
+    pub fn cancel_query(&mut self) {
+        let Some(worker) = self.query_worker.take() else {
+            return;
+        };
+        
+        worker.cancel();
+        self.filter_state.abandon_layer();
+        self.reload_nav_order();
+        self.refresh_filter_view();
+        self.message_state.push(format!("Cancelled query after searching {} of {} events", worker.searched(), worker.total()));
+    }
+    
+    /// applies whatever the background query has found since the last check
+    fn poll_query_worker(&mut self) {
+        let Some(worker) = self.query_worker.as_mut() else {
+            return;
+        };
+        
+        let mut new_matches = BTreeSet::new();
+        let mut finished = false;
+        for update in worker.poll() {
+            match update {
+                QueryUpdate::Progress { matches, .. } => {
+                    self.filter_state.extend_matches(&matches);
+                    new_matches.extend(matches);
+                }
+                QueryUpdate::Finished { query, results: Ok(results) } => {
+                    for issue in results.issues() {
+                        self.message_state.push(format!("warning: {}", issue));
+                    }
+                    self.filter_state.push_layer(worker.label(), *query, results);
+                    self.reload_nav_order();
+                    let match_count = self.filter_state.matching_events().len();
+                    self.message_state.push(format!("Successfully updated query: {} matching events", match_count));
+                    self.query_worker = None;
+                    finished = true;
+                    break;
+                }
+                QueryUpdate::Finished { results: Err(err), .. } => {
+                    self.filter_state.abandon_layer();
+                    self.reload_nav_order();
+                    self.message_state.push(format!("error while evaluating query: {}", err));
+                    self.query_worker = None;
+                    finished = true;
+                    break;
+                }
+            }
+        }
+        
+        if finished {
+            self.refresh_filter_view();
+        } else if !new_matches.is_empty() && self.filter_view_state.is_enabled() {
+            let cursor_event = self.event_at_cursor();
+            if self.filter_view_state.add_matches(&new_matches, &self.projection_state, self.events.len()) {
+                self.restore_cursor(cursor_event);
+            }
+        }
+    }
+    
+    /// draws the matches of the rule's query in the rule's color, over every other rule
+    pub fn add_highlight(&mut self, rule: HighlightRule) {
+        let color = match rule.color.parse::<Color>() {
+            Ok(color) => color,
+            Err(_) => {
+                self.message_state.push(format!("error in highlight rule {}: {} isn't a color", rule.name, rule.color));
+                return;
+            }
+        };
+        
+        // a rule with the same name is replaced, so its query is no longer wanted
+        self.highlight_workers.retain(|worker| {
+            let replaced = worker.label() == rule.name;
+            if replaced {
+                worker.cancel();
+            }
+            !replaced
+        });
+        
+        // the rule keeps its priority while its matches are drawn in as they are found
+        self.highlight_state.add(Highlight::new(rule.name.clone(), color, BTreeSet::new()));
+        self.reload_nav_order();
+        self.highlight_workers.push(QueryWorker::spawn(rule.name, rule.query, Arc::clone(&self.events), Arc::clone(&self.index), None));
+    }
+    
+    /// draws whatever the highlight rules' queries have found since the last check
+    fn poll_highlight_workers(&mut self) {
+        let mut any_finished = false;
+        for mut worker in std::mem::take(&mut self.highlight_workers) {
+            let mut finished = false;
+            for update in worker.poll() {
+                match update {
+                    QueryUpdate::Progress { matches, .. } => self.highlight_state.extend_matches(worker.label(), &matches),
+                    QueryUpdate::Finished { results: Ok(results), .. } => {
+                        // queries that can't be searched in pieces only send their matches at the end
+                        self.highlight_state.extend_matches(worker.label(), results.matches());
+                        let match_count = self.highlight_state.highlights().iter()
+                            .find(|highlight| highlight.name() == worker.label())
+                            .map_or(0, |highlight| highlight.matches().len());
+                        self.message_state.push(format!("Highlighting {} events as {}", match_count, worker.label()));
+                        for issue in results.issues() {
+                            self.message_state.push(format!("warning: highlight rule {}: {}", worker.label(), issue));
+                        }
+                        finished = true;
+                    }
+                    QueryUpdate::Finished { results: Err(err), .. } => {
+                        self.highlight_state.remove(worker.label());
+                        self.message_state.push(format!("error while evaluating highlight rule {}: {}", worker.label(), err));
+                        finished = true;
+                    }
+                }
+            }
+            
+            if finished {
+                any_finished = true;
+            } else {
+                self.highlight_workers.push(worker);
+            }
+        }
+        
+        if any_finished {
+            self.reload_nav_order();
+        }
+    }
+    
+    /// makes n/N cycle through the next highlight rule's matches, or the active query's after the
+    /// last rule
+    pub fn cycle_nav_highlight(&mut self) {
+        if self.highlight_state.highlights().is_empty() {
+            self.message_state.push("No highlight rules. Run `highlight name color = query` to add one");
+            return;
+        }
+        
+        self.search_state.stop_navigating();
+        self.highlight_state.cycle_nav_highlight();
+        let message = match self.highlight_state.nav_highlight() {
+            Some(highlight) => format!("n/N now go through the matches of highlight {}", highlight.name()),
+            None => "n/N now go through the matches of the active query".to_string(),
+        };
+        self.message_state.push(message);
+        self.reload_nav_order();
+    }
+    
+    /// removes the highlight rule n/N cycle through
+    pub fn remove_nav_highlight(&mut self) {
+        match self.highlight_state.remove_nav_highlight() {
+            Some(name) => self.message_state.push(format!("Removed highlight {}", name)),
+            None => self.message_state.push("Press h to pick the highlight rule to remove"),
+        }
+        self.reload_nav_order();
+    }
+    
+    /// true if n/N have something to go through
+    pub fn can_navigate(&self) -> bool {
+        self.search_state.is_navigating() || self.highlight_state.nav_highlight().is_some() || self.filter_state.has_active_filter()
+    }
+    
+    /// opens the search prompt, searching from the row the cursor is on
+    pub fn open_search(&mut self) {
+        let origin_row = self.active_list_state().selected_row();
+        self.search_state.load_texts(&self.events);
+        self.search_state.open(origin_row);
+    }
+    
+    /// moves the cursor to the first event from where the search started that has the text typed
+    /// so far, or back to where it started if nothing is typed
+    pub fn update_search(&mut self) {
+        let origin_row = self.search_state.origin_row();
+        if self.search_state.pattern().is_none() {
+            self.search_state.set_found(true);
+            if origin_row < self.shown_row_count() {
+                self.active_list_state().focus_event(origin_row);
+            }
+            return;
+        }
+        
+        let found_row = self.find_search_match(origin_row, true);
+        self.search_state.set_found(found_row.is_some());
+        if let Some(row) = found_row {
+            self.active_list_state().focus_event(row);
+        }
+    }
+    
+    /// closes the search prompt, selecting the event that was found. n/N go through the events
+    /// the text is found in after this
+    pub fn commit_search(&mut self) {
+        let prompt = self.search_state.prompt().unwrap_or_default().to_string();
+        if !self.search_state.commit() {
+            return;
+        }
+        
+        if let Some(event_idx) = self.event_at_cursor() {
+            self.selected_event = Some(event_idx);
+        }
+        self.message_state.push(format!("Searching for {}: n/N go to the next and previous event it's found in", prompt));
+    }
+    
+    /// closes the search prompt, moving the cursor back to where the search started
+    pub fn cancel_search(&mut self) {
+        self.search_state.cancel();
+        let origin_row = self.search_state.origin_row();
+        if origin_row < self.shown_row_count() {
+            self.active_list_state().focus_event(origin_row);
+        }
+    }
+    
+    /// closes the goto prompt, moving the cursor to the event it points at
+    pub fn commit_goto(&mut self) {
+        let Some(text) = self.goto_state.close() else {
+            return;
+        };
+        
+        let target = match GotoTarget::parse(&text) {
+            Ok(target) => target,
+            Err(err) => {
+                self.message_state.push(format!("Can't go to {}: {}", text.trim(), err));
+                return;
+            }
+        };
+        
+        let Some(event_idx) = target.resolve(&self.events) else {
+            self.message_state.push(format!("The log doesn't reach {}", text.trim()));
+            return;
+        };
+        
+        if self.filter_view_state.is_enabled() && self.filter_view_state.row_of_event(event_idx, &self.projection_state).is_none() {
+            self.message_state.push(format!("Event {} isn't shown. Press f to show every event", event_idx));
+            return;
+        }
+        
+        self.focus_event(event_idx);
+        self.selected_event = Some(event_idx);
+    }
+    
+    /// moves to the next or previous event the search text is found in, wrapping around the list
+    fn search_next(&mut self, forward: bool) {
+        let row_count = self.shown_row_count();
+        let cursor_row = self.active_list_state().selected_row();
+        let start = if forward { cursor_row + 1 } else { cursor_row + row_count.saturating_sub(1) };
+        
+        match self.find_search_match(start, forward) {
+            Some(row) => {
+                self.active_list_state().focus_event(row);
+                self.selected_event = self.event_at_cursor();
+            }
+            None => self.message_state.push("The search text isn't found in any shown event"),
+        }
+    }
+    
+    /// the first row, starting at `start` and going forward or backward, whose event has the
+    /// search text. Wraps around the ends of the list
+    fn find_search_match(&self, start: usize, forward: bool) -> Option<usize> {
+        let row_count = self.shown_row_count();
+        (0..row_count)
+            .map(|step| if forward { (start + step) % row_count } else { (start + row_count - step) % row_count })
+            .find(|row| {
+                self.event_at_shown_row(*row)
+                    .is_some_and(|event_idx| self.search_state.is_event_match(&self.events, event_idx))
+            })
+    }
+    
+    /// makes n/N go through the matches of the picked highlight rule, or the active query's
+    fn reload_nav_order(&mut self) {
+        let nav_order = match self.highlight_state.nav_highlight() {
+            Some(highlight) => highlight.matches().iter()
+                .map(|event_idx| vec![*event_idx])
+                .collect(),
+            // the matches of a running query aren't complete yet
+            None if self.is_query_running() => Vec::new(),
+            None => self.filter_state.nav_order(),
+        };
+        self.navigation_state.load_nav_order(nav_order);
+    }
+    
+    /// pops the active query, going back to the one it refined
+    pub fn undo_filter(&mut self) {
+        if self.is_query_running() {
+            self.message_state.push("A query is running. Press Esc to cancel it first");
+            return;
+        }
+        
+        let message = match self.filter_state.undo() {
+            Some(label) => format!("Undid query: {}", label),
+            None => "No query to undo".to_string(),
+        };
+        self.message_state.push(message);
+        self.reload_filter_layer();
+    }
+    
+    /// pushes the last undone query back on top
+    pub fn redo_filter(&mut self) {
+        if self.is_query_running() {
+            self.message_state.push("A query is running. Press Esc to cancel it first");
+            return;
+        }
+        
+        let message = match self.filter_state.redo() {
+            Some(label) => format!("Redid query: {}", label),
+            None => "No query to redo".to_string(),
+        };
+        self.message_state.push(message);
+        self.reload_filter_layer();
+    }
+    
+    /// undoes every query, so every event is shown again
+    pub fn undo_all_filters(&mut self) {
+        if self.is_query_running() {
+            self.message_state.push("A query is running. Press Esc to cancel it first");
+            return;
+        }
+        
+        self.filter_state.undo_all();
+        self.message_state.push("Undid every query");
+        self.reload_filter_layer();
+    }
+    
+    /// follows the query that is now on top of the stack
+    fn reload_filter_layer(&mut self) {
+        self.reload_nav_order();
+        self.refresh_filter_view();
+        if !self.filter_state.has_active_filter() {
+            self.explain_visible = false;
+        }
     }
     
     pub fn nav_next(&mut self) {
+        if self.search_state.is_navigating() {
+            self.search_next(true);
+            return;
+        }
+        
         if let Some(next_idx) = self.navigation_state.next_event() {
-            self.event_list_state.focus_event(next_idx);
+            self.focus_event(next_idx);
             self.selected_event = Some(next_idx);
         } else {
             self.message_state.push("No more results found")
@@ -118,16 +867,42 @@ impl<'a> App<'a> {
     }
     
     pub fn nav_prev(&mut self) {
+        if self.search_state.is_navigating() {
+            self.search_next(false);
+            return;
+        }
+        
         if let Some(prev_idx) = self.navigation_state.prev_event() {
-            self.event_list_state.focus_event(prev_idx);
+            self.focus_event(prev_idx);
             self.selected_event = Some(prev_idx);
         } else {
             self.message_state.push("No more results found")
         }
     }
 
+    pub fn nav_next_in_group(&mut self) {
+        if let Some(next_idx) = self.navigation_state.next_in_group() {
+            self.focus_event(next_idx);
+            self.selected_event = Some(next_idx);
+        } else {
+            self.message_state.push("No more events in this group")
+        }
+    }
+    
+    pub fn nav_prev_in_group(&mut self) {
+        if let Some(prev_idx) = self.navigation_state.prev_in_group() {
+            self.focus_event(prev_idx);
+            self.selected_event = Some(prev_idx);
+        } else {
+            self.message_state.push("No more events in this group")
+        }
+    }
+
     /// Handles the tick event of the terminal.
-    pub fn tick(&self) {}
+    pub fn tick(&mut self) {
+        self.poll_query_worker();
+        self.poll_highlight_workers();
+    }
 
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
@@ -0,0 +1,78 @@
+use tui_textarea::TextArea;
+use crate::ds_events::schema::LogSchema;
+use crate::dsl::completion::{complete, Completions};
+
+#[derive(Debug, Default)]
+pub struct CompletionState {
+    /// the completions offered in the popup, if it's open
+    completions: Option<Completions>,
+    /// the candidate highlighted in the popup
+    selected: usize,
+}
+
+impl CompletionState {
+    pub fn is_active(&self) -> bool {
+        self.completions.is_some()
+    }
+
+    pub fn candidates(&self) -> &[String] {
+        self.completions.as_ref()
+            .map(|completions| completions.candidates())
+            .unwrap_or_default()
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn close(&mut self) {
+        self.completions = None;
+        self.selected = 0;
+    }
+
+    /// works out completions for the word at the cursor, opening the popup if there are any
+    pub fn update(&mut self, text_area: &TextArea, schema: &LogSchema) {
+        let completions = complete(&text_before_cursor(text_area), schema);
+        self.selected = 0;
+        self.completions = if completions.is_empty() {
+            None
+        } else {
+            Some(completions)
+        };
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.candidates().len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// replaces the word at the cursor with the highlighted candidate, and closes the popup
+    pub fn accept(&mut self, text_area: &mut TextArea) {
+        if let Some(completions) = self.completions.take() {
+            if let Some(candidate) = completions.candidates().get(self.selected) {
+                for _ in completions.prefix().chars() {
+                    text_area.delete_char();
+                }
+                text_area.insert_str(candidate);
+            }
+        }
+        self.selected = 0;
+    }
+}
+
+/// all the text in the editor up to the cursor
+fn text_before_cursor(text_area: &TextArea) -> String {
+    let (row, col) = text_area.cursor();
+    let lines = text_area.lines();
+    let mut before = lines[..row].join("\n");
+    if row > 0 {
+        before.push('\n');
+    }
+    before.extend(lines[row].chars().take(col));
+    before
+}
@@ -0,0 +1,478 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::app::search_state::SearchState;
+use crate::ds_events::abstract_object::{AbstractObject, AbstractValue, ValueKind};
+use crate::dsl::query_ast::PropPath;
+
+/// maps and collections with more children than this start collapsed
+const COLLAPSED_CHILD_COUNT: usize = 8;
+
+/// the kinds of queries that can be built from a value in the details
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailQuery {
+    /// events of the same type with the same value at the same path
+    Eq,
+    /// events of the same type that have a value at the same path
+    Has,
+}
+
+/// one line of the event details tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailsRow {
+    /// identifies the value in the event, like `amoCommand.command.key` or `acceptors[0]`
+    key: String,
+    /// the key of the value this one is inside of, if it isn't a field of the event's object
+    parent: Option<String>,
+    /// the path a query can use to look the value up, like `acceptors.0` for a collection item
+    path: PropPath,
+    /// how many levels down the tree the value is
+    depth: usize,
+    /// the field name, map key or collection index the value is under
+    label: String,
+    kind: ValueKind,
+    /// the value itself for numbers and symbols, and a summary of it for everything else
+    summary: String,
+    /// how many values are inside of this one
+    child_count: usize,
+}
+
+impl DetailsRow {
+    /// the row for a value inside of `parent`, or a field of the event's object if there's no
+    /// parent. `segment` extends the parent's path
+    fn new(value: &AbstractValue, parent: Option<&DetailsRow>, label: String, segment: &str) -> Self {
+        let key = match parent {
+            Some(parent) if label.starts_with('[') => format!("{}{}", parent.key, label),
+            Some(parent) => format!("{}.{}", parent.key, label),
+            None => label.clone(),
+        };
+        let mut segments = parent.map_or_else(Vec::new, |parent| parent.path.segments.clone());
+        segments.push(segment.to_string());
+        let path = PropPath { segments };
+        let (summary, child_count) = match value {
+            AbstractValue::Number(_) | AbstractValue::Symbol(_) => (value.to_string(), 0),
+            AbstractValue::Object(object) => (object.name().to_string(), object.props().len()),
+            AbstractValue::Map(map) => (format!("{} entries", map.len()), map.len()),
+            AbstractValue::Collection(items) => (format!("{} items", items.len()), items.len()),
+        };
+
+        Self {
+            key,
+            parent: parent.map(|parent| parent.key.clone()),
+            path,
+            depth: parent.map_or(0, |parent| parent.depth + 1),
+            label,
+            kind: value.kind(),
+            summary,
+            child_count,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn path(&self) -> &PropPath {
+        &self.path
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn kind(&self) -> ValueKind {
+        self.kind
+    }
+
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+
+    pub fn is_expandable(&self) -> bool {
+        self.child_count > 0
+    }
+
+    /// the text of a query that finds other events of the given type like this value
+    pub fn query_text(&self, query: DetailQuery, object_name: &str) -> Result<String, String> {
+        let path = &self.path;
+        match query {
+            DetailQuery::Has => Ok(format!("find {} has({})", object_name, path)),
+            DetailQuery::Eq if self.kind == ValueKind::Number => Ok(format!("find {} eq({}, {})", object_name, path, self.summary)),
+            DetailQuery::Eq if self.kind == ValueKind::Symbol && !self.summary.contains('"') => {
+                Ok(format!("find {} eq({}, \"{}\")", object_name, path, self.summary))
+            }
+            DetailQuery::Eq if self.kind == ValueKind::Symbol => Err(format!("{} has a quote in it, which queries can't compare to", self.summary)),
+            DetailQuery::Eq => Err(format!("only numbers and strings can be compared, but {} is a {}", self.label, self.kind)),
+        }
+    }
+
+    /// the text searches look through
+    fn search_text(&self) -> String {
+        format!("{} {}", self.label, self.summary)
+    }
+}
+
+/// state for browsing the selected event's object as a tree
+#[derive(Debug, Default)]
+pub struct DetailsState {
+    /// the event being shown. The tree starts over when it changes
+    event_idx: Option<usize>,
+    /// the keys of values that were expanded or collapsed from how they start
+    toggled: BTreeSet<String>,
+    /// the row the cursor is on
+    cursor: usize,
+    /// the first row on screen
+    offset: usize,
+    /// how many rows fit on screen, the last time the tree was drawn
+    height: usize,
+    /// used for searching the tree
+    search: SearchState,
+}
+
+impl DetailsState {
+    /// starts over with a fresh tree if the event changed
+    pub fn show_event(&mut self, event_idx: usize) {
+        if self.event_idx == Some(event_idx) {
+            return;
+        }
+
+        *self = Self {
+            event_idx: Some(event_idx),
+            search: std::mem::take(&mut self.search),
+            ..Self::default()
+        };
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn search(&self) -> &SearchState {
+        &self.search
+    }
+
+    pub fn search_mut(&mut self) -> &mut SearchState {
+        &mut self.search
+    }
+
+    /// true if the values inside the row's value are shown
+    pub fn is_expanded(&self, row: &DetailsRow) -> bool {
+        let starts_expanded = row.kind == ValueKind::Object || row.child_count <= COLLAPSED_CHILD_COUNT;
+        starts_expanded != self.toggled.contains(&row.key)
+    }
+
+    /// every row currently shown, with the values inside expanded values under them
+    pub fn rows(&self, object: &AbstractObject) -> Vec<DetailsRow> {
+        let mut rows = Vec::new();
+        self.push_object_rows(object, None, false, &mut rows);
+        rows
+    }
+
+    /// the row the cursor is on
+    pub fn selected_row(&self, object: &AbstractObject) -> Option<DetailsRow> {
+        self.rows(object).into_iter().nth(self.cursor)
+    }
+
+    pub fn next_row(&mut self, object: &AbstractObject) {
+        self.move_cursor(self.cursor + 1, object);
+    }
+
+    pub fn prev_row(&mut self, object: &AbstractObject) {
+        self.move_cursor(self.cursor.saturating_sub(1), object);
+    }
+
+    pub fn next_page(&mut self, object: &AbstractObject) {
+        self.move_cursor(self.cursor + self.height.max(1), object);
+    }
+
+    pub fn prev_page(&mut self, object: &AbstractObject) {
+        self.move_cursor(self.cursor.saturating_sub(self.height.max(1)), object);
+    }
+
+    pub fn go_home(&mut self, object: &AbstractObject) {
+        self.move_cursor(0, object);
+    }
+
+    pub fn go_end(&mut self, object: &AbstractObject) {
+        self.move_cursor(usize::MAX, object);
+    }
+
+    /// shows or hides the values inside the value under the cursor. On a value with nothing
+    /// inside, collapses the value it's in and moves the cursor back to it
+    pub fn toggle_expanded(&mut self, object: &AbstractObject) {
+        let rows = self.rows(object);
+        let Some(row) = rows.get(self.cursor) else {
+            return;
+        };
+
+        if row.is_expandable() {
+            self.toggle(&row.key);
+            return;
+        }
+
+        if let Some(parent) = &row.parent {
+            self.toggle(parent);
+            self.cursor = rows.iter()
+                .position(|row| row.key == *parent)
+                .unwrap_or_default();
+        }
+    }
+
+    /// expands the value under the cursor if it's collapsed
+    pub fn expand(&mut self, object: &AbstractObject) {
+        if let Some(row) = self.selected_row(object).filter(|row| row.is_expandable() && !self.is_expanded(row)) {
+            self.toggle(&row.key);
+        }
+    }
+
+    /// collapses the value under the cursor if it's expanded, otherwise moves to the value it's in
+    pub fn collapse(&mut self, object: &AbstractObject) {
+        let rows = self.rows(object);
+        let Some(row) = rows.get(self.cursor) else {
+            return;
+        };
+
+        if row.is_expandable() && self.is_expanded(row) {
+            self.toggle(&row.key);
+        } else if let Some(parent) = &row.parent {
+            self.cursor = rows.iter()
+                .position(|row| row.key == *parent)
+                .unwrap_or(self.cursor);
+        }
+    }
+
+    /// opens the search prompt, searching from the row the cursor is on
+    pub fn open_search(&mut self) {
+        self.search.open(self.cursor);
+    }
+
+    /// moves the cursor to the first value from where the search started that has the text typed
+    /// so far, expanding the values it's inside of
+    pub fn update_search(&mut self, object: &AbstractObject) {
+        if self.search.pattern().is_none() {
+            self.search.set_found(true);
+            let origin_row = self.search.origin_row();
+            self.move_cursor(origin_row, object);
+            return;
+        }
+
+        // the search starts from the row under the cursor when the prompt was opened
+        let origin_key = self.rows(object).get(self.search.origin_row()).map(|row| row.key.clone());
+        let found = self.find_match(object, origin_key.as_deref(), true, true);
+        self.search.set_found(found);
+    }
+
+    /// closes the search prompt, moving the cursor back to where the search started
+    pub fn cancel_search(&mut self, object: &AbstractObject) {
+        self.search.cancel();
+        self.move_cursor(self.search.origin_row(), object);
+    }
+
+    /// moves to the next or previous value the search text is found in, wrapping around the tree.
+    /// Returns false if it isn't found anywhere
+    pub fn search_next(&mut self, object: &AbstractObject, forward: bool) -> bool {
+        let cursor_key = self.selected_row(object).map(|row| row.key);
+        self.find_match(object, cursor_key.as_deref(), forward, false)
+    }
+
+    /// moves the cursor to the first value after `start_key`, or at it if `inclusive`, that has the
+    /// search text. Looks inside collapsed values too
+    fn find_match(&mut self, object: &AbstractObject, start_key: Option<&str>, forward: bool, inclusive: bool) -> bool {
+        let mut all_rows = Vec::new();
+        self.push_object_rows(object, None, true, &mut all_rows);
+        if all_rows.is_empty() {
+            return false;
+        }
+
+        let start = start_key
+            .and_then(|key| all_rows.iter().position(|row| row.key == key))
+            .unwrap_or_default();
+        let row_count = all_rows.len();
+        let skip = if inclusive { 0 } else { 1 };
+        let found = (skip..row_count + skip)
+            .map(|step| if forward { (start + step) % row_count } else { (start + row_count - step % row_count) % row_count })
+            .find(|idx| self.search.is_match(&all_rows[*idx].search_text()));
+        let Some(found_idx) = found else {
+            return false;
+        };
+
+        self.reveal(&all_rows, found_idx);
+        let found_key = &all_rows[found_idx].key;
+        let cursor = self.rows(object).iter()
+            .position(|row| row.key == *found_key)
+            .unwrap_or_default();
+        self.move_cursor(cursor, object);
+        true
+    }
+
+    /// expands every value the row is inside of
+    fn reveal(&mut self, all_rows: &[DetailsRow], row_idx: usize) {
+        let rows_by_key = all_rows.iter()
+            .map(|row| (row.key.as_str(), row))
+            .collect::<HashMap<_, _>>();
+        let mut parent = all_rows[row_idx].parent.as_deref();
+        while let Some(parent_row) = parent.and_then(|key| rows_by_key.get(key)) {
+            if !self.is_expanded(parent_row) {
+                self.toggle(&parent_row.key);
+            }
+            parent = parent_row.parent.as_deref();
+        }
+    }
+
+    /// fits the tree to the height it is drawn at, keeping the cursor on screen
+    pub fn set_height(&mut self, height: usize, row_count: usize) {
+        self.height = height;
+        self.cursor = self.cursor.min(row_count.saturating_sub(1));
+        self.scroll_to_cursor();
+    }
+
+    fn move_cursor(&mut self, row: usize, object: &AbstractObject) {
+        let row_count = self.rows(object).len();
+        self.cursor = row.min(row_count.saturating_sub(1));
+        self.scroll_to_cursor();
+    }
+
+    /// scrolls as little as possible to put the cursor on screen
+    fn scroll_to_cursor(&mut self) {
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.height > 0 && self.cursor >= self.offset + self.height {
+            self.offset = self.cursor + 1 - self.height;
+        }
+    }
+
+    fn toggle(&mut self, key: &str) {
+        if !self.toggled.remove(key) {
+            self.toggled.insert(key.to_string());
+        }
+    }
+
+    /// adds a row for each field of the object, in name order, with the rows of expanded values
+    /// under them. With `expand_all`, every value is treated as expanded
+    fn push_object_rows(&self, object: &AbstractObject, parent: Option<&DetailsRow>, expand_all: bool, rows: &mut Vec<DetailsRow>) {
+        let mut props = object.props().iter().collect::<Vec<_>>();
+        props.sort_by_key(|(name, _)| *name);
+        for (name, value) in props {
+            let row = DetailsRow::new(value, parent, name.clone(), name);
+            self.push_value_rows(value, row, expand_all, rows);
+        }
+    }
+
+    fn push_value_rows(&self, value: &AbstractValue, row: DetailsRow, expand_all: bool, rows: &mut Vec<DetailsRow>) {
+        let expanded = expand_all || self.is_expanded(&row);
+        rows.push(row.clone());
+        if !expanded {
+            return;
+        }
+
+        match value {
+            AbstractValue::Object(object) => self.push_object_rows(object, Some(&row), expand_all, rows),
+            AbstractValue::Map(map) => {
+                let mut entries = map.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(entry_key, _)| *entry_key);
+                for (entry_key, entry) in entries {
+                    let entry_row = DetailsRow::new(entry, Some(&row), entry_key.clone(), entry_key);
+                    self.push_value_rows(entry, entry_row, expand_all, rows);
+                }
+            }
+            AbstractValue::Collection(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    let item_row = DetailsRow::new(item, Some(&row), format!("[{}]", idx), &idx.to_string());
+                    self.push_value_rows(item, item_row, expand_all, rows);
+                }
+            }
+            AbstractValue::Number(_) | AbstractValue::Symbol(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::details_state::{DetailQuery, DetailsState};
+    use crate::ds_events::abstract_object::{AbstractObject, AbstractValue};
+    use crate::dsl::parser::parse_statement;
+
+    fn labels(state: &DetailsState, object: &AbstractObject) -> Vec<String> {
+        state.rows(object).iter()
+            .map(|row| format!("{}{}", "  ".repeat(row.depth()), row.label()))
+            .collect()
+    }
+
+    #[test]
+    fn expands_collapses_and_searches() {
+        let acceptors = (0..10)
+            .map(|idx| AbstractValue::from(format!("server{}", idx)))
+            .collect::<Vec<_>>();
+        let object = AbstractObject::new("PaxosSlotEntry")
+            .with_prop("slotNum", 3i64)
+            .with_prop("ballot", AbstractObject::new("Ballot").with_prop("roundNum", 1i64))
+            .with_prop("acceptors", acceptors);
+        let mut state = DetailsState::default();
+        state.show_event(0);
+
+        // big collections start collapsed
+        assert_eq!(labels(&state, &object), vec!["acceptors", "ballot", "  roundNum", "slotNum"]);
+        let ballot = state.rows(&object)[1].clone();
+        assert_eq!((ballot.summary(), ballot.path().to_string()), ("Ballot", "ballot".to_string()));
+
+        // collapsing from a field goes back to the object it's in
+        state.next_row(&object);
+        state.next_row(&object);
+        state.collapse(&object);
+        assert_eq!(state.cursor(), 1);
+        state.collapse(&object);
+        assert_eq!(labels(&state, &object), vec!["acceptors", "ballot", "slotNum"]);
+
+        // searching finds values inside of collapsed ones
+        state.open_search();
+        for c in "server7".chars() {
+            state.search_mut().push_char(c);
+        }
+        state.update_search(&object);
+        let found = state.selected_row(&object).expect("The search should find a row");
+        assert_eq!((found.label(), found.path().to_string()), ("[7]", "acceptors.7".to_string()));
+        assert!(state.search_mut().commit());
+        assert!(state.search_next(&object, true));
+        assert_eq!(state.selected_row(&object).map(|row| row.key().to_string()), Some("acceptors[7]".to_string()));
+
+        // a new event starts over
+        state.show_event(1);
+        assert_eq!(state.cursor(), 0);
+        assert_eq!(labels(&state, &object).len(), 4);
+    }
+
+    #[test]
+    fn builds_queries_from_values() {
+        let object = AbstractObject::new("PaxosSlotEntry")
+            .with_prop("slotNum", 3i64)
+            .with_prop("ballot", AbstractObject::new("Ballot").with_prop("serverAddress", "server1"))
+            .with_prop("acceptors", vec![AbstractValue::from("server1")]);
+        let state = DetailsState::default();
+        let rows = state.rows(&object);
+        let query_of = |key: &str, query: DetailQuery| {
+            let row = rows.iter().find(|row| row.key() == key).expect("The row should be shown");
+            row.query_text(query, object.name())
+        };
+
+        let queries = [
+            (query_of("slotNum", DetailQuery::Eq), "find PaxosSlotEntry eq(slotNum, 3)"),
+            (query_of("ballot.serverAddress", DetailQuery::Eq), "find PaxosSlotEntry eq(ballot.serverAddress, \"server1\")"),
+            (query_of("ballot", DetailQuery::Has), "find PaxosSlotEntry has(ballot)"),
+            (query_of("acceptors[0]", DetailQuery::Eq), "find PaxosSlotEntry eq(acceptors.0, \"server1\")"),
+        ];
+        for (query, expected) in queries {
+            let query = query.expect("The query should be built");
+            assert_eq!(query, expected);
+            assert!(parse_statement(&query).is_ok());
+        }
+
+        assert!(query_of("ballot", DetailQuery::Eq).is_err());
+        assert!(query_of("acceptors", DetailQuery::Has).is_ok());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::ds_events::diff::{diff_objects, DiffLine};
+use crate::ds_events::event::Event;
+
+/// the comparison of the marked event with the selected one, kept until either of them changes
+#[derive(Debug, Default)]
+pub struct DiffState {
+    /// the marked and selected events the lines compare, or none if there's nothing to compare
+    compared: Option<(usize, usize)>,
+    lines: Vec<DiffLine>,
+}
+
+impl DiffState {
+    /// compares the marked event with the selected one, unless they were already compared
+    pub fn update(&mut self, events: &[Event], marked_event: Option<usize>, selected_event: Option<usize>) {
+        let compared = match (marked_event, selected_event) {
+            (Some(marked_idx), Some(selected_idx)) if marked_idx != selected_idx => Some((marked_idx, selected_idx)),
+            _ => None,
+        };
+        if compared == self.compared {
+            return;
+        }
+
+        self.lines = match compared {
+            Some((marked_idx, selected_idx)) => diff_objects(events[marked_idx].event_obj(), events[selected_idx].event_obj()),
+            None => Vec::new(),
+        };
+        self.compared = compared;
+    }
+
+    /// the marked and selected events with the lines comparing them, if they're different events
+    pub fn diff(&self) -> Option<(usize, usize, &[DiffLine])> {
+        self.compared.map(|(marked_idx, selected_idx)| (marked_idx, selected_idx, self.lines.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::diff_state::DiffState;
+    use crate::ds_events::fixtures::{log, slot_entry};
+
+    #[test]
+    fn compares_each_pair_once() {
+        let events = log([("server1", slot_entry(1)), ("server1", slot_entry(2)), ("server2", slot_entry(2))]);
+        let mut diff_state = DiffState::default();
+        diff_state.update(&events, Some(0), Some(0));
+        assert!(diff_state.diff().is_none());
+
+        diff_state.update(&events, Some(0), Some(1));
+        let (marked_idx, selected_idx, lines) = diff_state.diff().expect("The events should be compared");
+        assert_eq!((marked_idx, selected_idx), (0, 1));
+        let first_lines = lines.as_ptr();
+
+        // drawing again reuses the lines
+        diff_state.update(&events, Some(0), Some(1));
+        assert_eq!(diff_state.diff().map(|(_, _, lines)| lines.as_ptr()), Some(first_lines));
+
+        diff_state.update(&events, Some(1), Some(2));
+        assert_eq!(diff_state.diff().map(|(marked_idx, selected_idx, _)| (marked_idx, selected_idx)), Some((1, 2)));
+        diff_state.update(&events, None, Some(2));
+        assert!(diff_state.diff().is_none());
+    }
+}
@@ -1,55 +1,203 @@
-use std::collections::{BTreeSet};
-use crate::ds_events::event::Event;
-use crate::dsl::filters::{EventSequenceQuery};
+use std::collections::BTreeSet;
+use crate::dsl::aggregate::QueryTable;
 use crate::dsl::query_ast::EventQuery;
+use crate::dsl::results::QueryResults;
 
+/// the matches shown when no query is active
+static NO_MATCHES: BTreeSet<usize> = BTreeSet::new();
+
+/// one query in the stack, along with what it found
+#[derive(Debug)]
+struct FilterLayer {
+    /// how the query is shown in the breadcrumb
+    label: String,
+    /// the query that made this layer
+    event_filter: EventQuery,
+    /// the set of events that match this layer
+    matching_events: BTreeSet<usize>,
+    /// events that matched together, like the chains of a sequence or the tuples of a join
+    match_groups: Vec<Vec<usize>>,
+    /// the summary produced by the filter, if it is a summarizing query
+    result_table: Option<QueryTable>,
+    /// true if the query searched the matches of the layer below, false if it searched every event
+    refines: bool,
+}
+
+/// a stack of queries, where each one refines the matches of the one below it
 #[derive(Default, Debug)]
 pub struct FilterState {
-    /// Our current event filter
-    event_filter: Option<EventQuery>,
-    /// the set of events that match this state
-    matching_events: BTreeSet<usize>,
+    /// the queries that have been applied, with the active one on top
+    layers: Vec<FilterLayer>,
+    /// the layers that were undone, with the next one to redo on top
+    undone: Vec<FilterLayer>,
+    /// the matches found so far by a query that is still running
+    pending_matches: Option<BTreeSet<usize>>,
+    /// true if the running query refines the top layer, false if it searches every event
+    pending_refines: bool,
 }
 
 impl FilterState {
-    
+
+    fn top(&self) -> Option<&FilterLayer> {
+        self.layers.last()
+    }
+
+    /// the events shown as matching: what a running query has found so far, or the top layer's
     pub fn matching_events(&self) -> &BTreeSet<usize> {
-        &self.matching_events
+        self.pending_matches.as_ref()
+            .or(self.top().map(|layer| &layer.matching_events))
+            .unwrap_or(&NO_MATCHES)
     }
-    
-    pub fn nav_order(&self) -> Vec<usize> {
-        self.matching_events.iter()
-            .copied()
-            .collect::<Vec<_>>()
+
+    pub fn result_table(&self) -> Option<&QueryTable> {
+        self.top().and_then(|layer| layer.result_table.as_ref())
     }
-    
-    pub fn clear_filter(&mut self) {
-        self.matching_events.clear();
-        self.event_filter = None;
-    }
-    
-    pub fn push_new_filter(&mut self, event_query: EventQuery, events: &[Event]) {
-        
-        self.matching_events.clear();
-        match &event_query {
-            EventQuery::Find { queries } => {
-                for query in queries {
-                    match query.eval(events) {
-                        Ok(matches) => {
-                            self.matching_events.extend(matches.into_iter())
-                        }
-                        Err(_) => {
-                            // TODO something this this
-                        }
-                    }
-                }
-            }
+
+    /// the groups of events to navigate through in the top layer. Events that didn't match as
+    /// part of a group are navigated one at a time
+    pub fn nav_order(&self) -> Vec<Vec<usize>> {
+        let Some(top) = self.top() else {
+            return Vec::new();
+        };
+
+        if !top.match_groups.is_empty() {
+            return top.match_groups.clone();
         }
-        
-        self.event_filter = Some(event_query);
+
+        top.matching_events.iter()
+            .map(|event_idx| vec![*event_idx])
+            .collect::<Vec<_>>()
+    }
+
+    /// the events a new query refines, or none if every event is searched
+    pub fn refined_events(&self) -> Option<&BTreeSet<usize>> {
+        self.top().map(|layer| &layer.matching_events)
+    }
+
+    /// starts showing the matches of a new query as they come in. If `refines` is false the
+    /// query searches every event, and starts a new chain of refinements on top of the stack
+    pub fn start_layer(&mut self, refines: bool) {
+        self.pending_matches = Some(BTreeSet::new());
+        self.pending_refines = refines;
+    }
+
+    /// adds events found by a query that is still running. They show as matches, but the query
+    /// isn't pushed until it finishes
+    pub fn extend_matches(&mut self, matches: &BTreeSet<usize>) {
+        self.pending_matches.get_or_insert_with(BTreeSet::new).extend(matches);
+    }
+
+    /// drops the matches of a query that was cancelled or failed, going back to the top layer
+    pub fn abandon_layer(&mut self) {
+        self.pending_matches = None;
+    }
+
+    /// pushes a finished query on top of the stack. Layers that were undone can't be redone after
+    pub fn push_layer<StrT: Into<String>>(&mut self, label: StrT, event_query: EventQuery, results: QueryResults) {
+        let (matches, groups, table) = results.into_parts();
+        self.layers.push(FilterLayer {
+            label: label.into(),
+            event_filter: event_query,
+            matching_events: matches,
+            match_groups: groups,
+            result_table: table,
+            refines: self.pending_refines,
+        });
+        self.undone.clear();
+        self.pending_matches = None;
+    }
+
+    /// pops the top layer so it can be redone. Returns its label, or none if there are no layers
+    pub fn undo(&mut self) -> Option<&str> {
+        let layer = self.layers.pop()?;
+        self.undone.push(layer);
+        self.undone.last().map(|layer| layer.label.as_str())
+    }
+
+    /// pushes the last undone layer back. Returns its label, or none if nothing was undone
+    pub fn redo(&mut self) -> Option<&str> {
+        let layer = self.undone.pop()?;
+        self.layers.push(layer);
+        self.top().map(|layer| layer.label.as_str())
+    }
+
+    /// undoes every layer, so every event is shown again
+    pub fn undo_all(&mut self) {
+        while self.undo().is_some() {}
+    }
+
+    /// the label and match count of each layer in the chain of refinements the top layer is part
+    /// of, from the query that searched every event to the top. Layers under that query come back
+    /// when it's undone
+    pub fn breadcrumb(&self) -> Vec<(&str, usize)> {
+        let chain_start = self.layers.iter()
+            .rposition(|layer| !layer.refines)
+            .unwrap_or(0);
+        self.layers[chain_start..].iter()
+            .map(|layer| (layer.label.as_str(), layer.matching_events.len()))
+            .collect()
     }
-    
+
+    pub fn active_filter(&self) -> Option<&EventQuery> {
+        self.top().map(|layer| &layer.event_filter)
+    }
+
     pub fn has_active_filter(&self) -> bool {
-        self.event_filter.is_some()
+        !self.layers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use crate::app::filter_state::FilterState;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::ds_events::index::EventIndex;
+    use crate::ds_events::schema::LogSchema;
+    use crate::dsl::parser::parse_event_query;
+
+    #[test]
+    fn refines_undoes_and_redoes() {
+        let events = log((0..10i64).map(|id| (format!("server{}", id % 2), slot_entry(id % 3))));
+        let index = EventIndex::build(&events, &LogSchema::infer(&events));
+        let mut filter_state = FilterState::default();
+        for query_text in ["find any eq(slotNum, 1)", "find any server(0)"] {
+            let (_, query) = parse_event_query(query_text).expect("Parsing should succeed");
+            filter_state.start_layer(true);
+            let results = query.run_in_pieces(&events, &index, filter_state.refined_events(), 4, |_, _| true)
+                .expect("Eval should succeed")
+                .expect("The search wasn't stopped");
+            filter_state.push_layer(query_text, query, results);
+        }
+
+        assert_eq!(*filter_state.matching_events(), BTreeSet::from([4]));
+        assert_eq!(filter_state.breadcrumb(), vec![("find any eq(slotNum, 1)", 3), ("find any server(0)", 1)]);
+
+        assert_eq!(filter_state.undo(), Some("find any server(0)"));
+        assert_eq!(*filter_state.matching_events(), BTreeSet::from([1, 4, 7]));
+        assert_eq!(filter_state.nav_order(), vec![vec![1], vec![4], vec![7]]);
+
+        // matches from a running query are shown until it's abandoned
+        filter_state.start_layer(true);
+        filter_state.extend_matches(&BTreeSet::from([7]));
+        assert_eq!(*filter_state.matching_events(), BTreeSet::from([7]));
+        filter_state.abandon_layer();
+
+        assert_eq!(filter_state.redo(), Some("find any server(0)"));
+        assert_eq!(filter_state.redo(), None);
+
+        // a query over every event starts a new chain, and the old one comes back when it's undone
+        let (_, query) = parse_event_query("find any eq(slotNum, 2)").expect("Parsing should succeed");
+        filter_state.start_layer(false);
+        let results = query.run(&events).expect("Eval should succeed");
+        filter_state.push_layer("find any eq(slotNum, 2)", query, results);
+        assert_eq!(*filter_state.matching_events(), BTreeSet::from([2, 5, 8]));
+        assert_eq!(filter_state.breadcrumb(), vec![("find any eq(slotNum, 2)", 3)]);
+        filter_state.undo();
+        assert_eq!(filter_state.breadcrumb().len(), 2);
+
+        filter_state.undo_all();
+        assert!(!filter_state.has_active_filter());
+        assert!(filter_state.matching_events().is_empty());
     }
 }
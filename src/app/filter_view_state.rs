@@ -0,0 +1,186 @@
+use std::collections::BTreeSet;
+use crate::app::projection_state::ProjectionState;
+
+/// the most events that can be shown around each match
+const MAX_CONTEXT: usize = 50;
+
+/// state for showing only the events that match the active query, like `grep`
+#[derive(Debug, Default)]
+pub struct FilterViewState {
+    /// true if only matching events are shown
+    enabled: bool,
+    /// how many events before and after each match are shown with it, like `grep -C`
+    context: usize,
+    /// the event shown at each row, in the same order as the full list
+    rows: Vec<usize>,
+}
+
+impl FilterViewState {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn context(&self) -> usize {
+        self.context
+    }
+
+    pub fn grow_context(&mut self) {
+        self.context = (self.context + 1).min(MAX_CONTEXT);
+    }
+
+    pub fn shrink_context(&mut self) {
+        self.context = self.context.saturating_sub(1);
+    }
+
+    /// the event shown at each row
+    pub fn rows(&self) -> &[usize] {
+        &self.rows
+    }
+
+    /// the event shown at the given row
+    pub fn event_at_row(&self, row: usize) -> Option<usize> {
+        self.rows.get(row).copied()
+    }
+
+    /// the row the given event is shown at, if it is shown
+    pub fn row_of_event(&self, event_idx: usize, projection: &ProjectionState) -> Option<usize> {
+        self.search_rows(event_idx, projection).ok()
+    }
+
+    /// the row the given event is shown at, or if it isn't shown, the row of the first shown event
+    /// after it in the full list. Past the last row if no shown event is after it
+    pub fn nearest_row(&self, event_idx: usize, projection: &ProjectionState) -> usize {
+        match self.search_rows(event_idx, projection) {
+            Ok(row) | Err(row) => row,
+        }
+    }
+
+    /// works out which events to show for the given matches, in the order the full list shows them.
+    /// Returns true if the events shown changed
+    pub fn update(&mut self, matching_events: &BTreeSet<usize>, projection: &ProjectionState, event_count: usize) -> bool {
+        let rows = self.events_around(matching_events, projection, event_count);
+        if rows == self.rows {
+            return false;
+        }
+
+        self.rows = rows;
+        true
+    }
+
+    /// shows the events around matches that were just found, without going over the ones already
+    /// shown. Returns true if the events shown changed
+    pub fn add_matches(&mut self, new_matches: &BTreeSet<usize>, projection: &ProjectionState, event_count: usize) -> bool {
+        let added = self.events_around(new_matches, projection, event_count)
+            .into_iter()
+            .filter(|event_idx| self.row_of_event(*event_idx, projection).is_none())
+            .collect::<Vec<_>>();
+        let Some(first_added) = added.first() else {
+            return false;
+        };
+
+        // queries find matches in log order, so the new rows usually all go after the shown ones
+        // and only the rows after the first new one are moved
+        let split_row = self.nearest_row(*first_added, projection);
+        let later_rows = self.rows.split_off(split_row);
+        self.rows.reserve(later_rows.len() + added.len());
+        let mut later_rows = later_rows.into_iter().peekable();
+        for event_idx in added {
+            let full_row = projection.row_of_event(event_idx);
+            while let Some(later_idx) = later_rows.next_if(|later_idx| projection.row_of_event(*later_idx) < full_row) {
+                self.rows.push(later_idx);
+            }
+            self.rows.push(event_idx);
+        }
+        self.rows.extend(later_rows);
+        true
+    }
+
+    /// the events within the context of the given matches, in the order the full list shows them
+    fn events_around(&self, matching_events: &BTreeSet<usize>, projection: &ProjectionState, event_count: usize) -> Vec<usize> {
+        let mut events = Vec::new();
+        let mut next_unseen = 0;
+        for event_idx in matching_events.range(..event_count) {
+            let first = event_idx.saturating_sub(self.context).max(next_unseen);
+            let last = (event_idx + self.context).min(event_count - 1);
+            events.extend(first..=last);
+            next_unseen = next_unseen.max(last + 1);
+        }
+
+        if !projection.row_order().is_empty() {
+            events.sort_unstable_by_key(|event_idx| projection.row_of_event(*event_idx));
+        }
+        events
+    }
+
+    /// binary searches the shown rows for the given event by where the full list shows it
+    fn search_rows(&self, event_idx: usize, projection: &ProjectionState) -> Result<usize, usize> {
+        let full_row = projection.row_of_event(event_idx);
+        self.rows.binary_search_by_key(&full_row, |shown_idx| projection.row_of_event(*shown_idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use crate::app::filter_view_state::FilterViewState;
+    use crate::app::projection_state::ProjectionState;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::dsl::query_ast::PropPath;
+
+    #[test]
+    fn shows_matches_with_context() {
+        let matches = BTreeSet::from([1, 5, 6, 9]);
+        let mut filter_view = FilterViewState::default();
+        filter_view.update(&matches, &ProjectionState::default(), 10);
+        assert_eq!(filter_view.rows(), &[1, 5, 6, 9]);
+        assert_eq!(filter_view.row_of_event(6, &ProjectionState::default()), Some(2));
+        assert_eq!(filter_view.row_of_event(2, &ProjectionState::default()), None);
+        assert_eq!(filter_view.nearest_row(2, &ProjectionState::default()), 1);
+        assert_eq!(filter_view.nearest_row(6, &ProjectionState::default()), 2);
+
+        filter_view.grow_context();
+        filter_view.update(&matches, &ProjectionState::default(), 10);
+        assert_eq!(filter_view.rows(), &[0, 1, 2, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn keeps_sorted_order() {
+        let events = log([3, 1, 2, 0].map(|slot| ("server1", slot_entry(slot))));
+        let mut projection = ProjectionState::default();
+        projection.set_columns(vec![PropPath { segments: vec!["slotNum".to_string()] }], &events);
+        projection.cycle_sort_column(&events);
+
+        let mut filter_view = FilterViewState::default();
+        filter_view.update(&BTreeSet::from([0, 1, 2]), &projection, events.len());
+        assert_eq!(filter_view.rows(), &[1, 2, 0]);
+        assert_eq!(filter_view.event_at_row(2), Some(0));
+    }
+
+    #[test]
+    fn adds_new_matches_in_place() {
+        let events = log([3, 1, 2, 0, 5, 4].map(|slot| ("server1", slot_entry(slot))));
+        let mut projection = ProjectionState::default();
+        projection.set_columns(vec![PropPath { segments: vec!["slotNum".to_string()] }], &events);
+        projection.cycle_sort_column(&events);
+
+        let mut filter_view = FilterViewState::default();
+        filter_view.update(&BTreeSet::from([2]), &projection, events.len());
+        assert!(filter_view.add_matches(&BTreeSet::from([0, 5]), &projection, events.len()));
+        assert_eq!(filter_view.rows(), &[2, 0, 5]);
+        assert!(!filter_view.add_matches(&BTreeSet::from([0]), &projection, events.len()));
+
+        // the context of new matches doesn't repeat the events already shown
+        filter_view.grow_context();
+        filter_view.update(&BTreeSet::from([2]), &projection, events.len());
+        assert!(filter_view.add_matches(&BTreeSet::from([4]), &projection, events.len()));
+        let mut rebuilt = FilterViewState::default();
+        rebuilt.grow_context();
+        rebuilt.update(&BTreeSet::from([2, 4]), &projection, events.len());
+        assert_eq!(filter_view.rows(), rebuilt.rows());
+        assert_eq!(filter_view.rows(), &[3, 1, 2, 5, 4]);
+    }
+}
@@ -0,0 +1,134 @@
+use time::macros::format_description;
+use time::{PrimitiveDateTime, Time};
+use crate::ds_events::event::{parse_timestamp, Event};
+
+/// a place in the log to jump to
+#[derive(Debug, Clone, PartialEq)]
+pub enum GotoTarget {
+    /// the event with the given id
+    EventId(usize),
+    /// the first event logged at or after the given time of day, on the day the log starts
+    TimeOfDay(Time),
+    /// the first event logged at or after the given timestamp
+    Timestamp(PrimitiveDateTime),
+    /// the event the given percent of the way through the log
+    Percent(f64),
+}
+
+impl GotoTarget {
+    /// parses what was typed into the goto prompt: an event id like `1234`, a timestamp like
+    /// `2024-03-05 22:59:25` or `22:59:25`, or a percentage like `50%`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if let Some(percent) = text.strip_suffix('%') {
+            return match percent.trim().parse::<f64>() {
+                Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(GotoTarget::Percent(percent)),
+                _ => Err(format!("{} isn't a percentage between 0% and 100%", text)),
+            };
+        }
+
+        if text.contains(' ') {
+            return parse_timestamp(text)
+                .map(GotoTarget::Timestamp)
+                .map_err(|err| err.to_string());
+        }
+
+        if text.contains(':') {
+            return Time::parse(text, format_description!("[hour]:[minute]:[second]"))
+                .map(GotoTarget::TimeOfDay)
+                .map_err(|_| format!("{} isn't a time like 22:59:25", text));
+        }
+
+        text.parse::<usize>()
+            .map(GotoTarget::EventId)
+            .map_err(|_| format!("{} isn't an event id, timestamp or percentage", text))
+    }
+
+    /// the index of the event this points at, or none if the log doesn't reach it. Events are
+    /// logged in id and time order, so both are found with a binary search
+    pub fn resolve(&self, events: &[Event]) -> Option<usize> {
+        let first = events.first()?;
+        let at_or_after = |time| events.partition_point(|event| event.time() < time);
+        let event_idx = match self {
+            GotoTarget::EventId(id) => return events.binary_search_by_key(id, |event| event.id()).ok(),
+            GotoTarget::TimeOfDay(time) => at_or_after(first.time().replace_time(*time)),
+            GotoTarget::Timestamp(timestamp) => at_or_after(*timestamp),
+            GotoTarget::Percent(percent) => ((events.len() - 1) as f64 * percent / 100.0).round() as usize,
+        };
+        (event_idx < events.len()).then_some(event_idx)
+    }
+}
+
+/// state for the prompt that jumps to a place in the log
+#[derive(Debug, Default)]
+pub struct GotoState {
+    /// the text typed into the prompt, while it's open
+    prompt: Option<String>,
+}
+
+impl GotoState {
+    pub fn is_prompt_open(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    pub fn prompt(&self) -> Option<&str> {
+        self.prompt.as_deref()
+    }
+
+    pub fn open(&mut self) {
+        self.prompt = Some(String::new());
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.push(c);
+        }
+    }
+
+    pub fn pop_char(&mut self) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.pop();
+        }
+    }
+
+    /// closes the prompt, returning what was typed into it
+    pub fn close(&mut self) -> Option<String> {
+        self.prompt.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{datetime, time};
+    use crate::app::goto_state::GotoTarget;
+    use crate::ds_events::abstract_object::AbstractObject;
+    use crate::ds_events::event::{Event, EventLevel};
+    use crate::ds_events::fixtures::LOGGED_AT;
+
+    #[test]
+    fn parses_and_resolves_targets() {
+        assert_eq!(GotoTarget::parse("12"), Ok(GotoTarget::EventId(12)));
+        assert_eq!(GotoTarget::parse("50%"), Ok(GotoTarget::Percent(50.0)));
+        assert_eq!(GotoTarget::parse("22:59:27"), Ok(GotoTarget::TimeOfDay(time!(22:59:27))));
+        assert_eq!(GotoTarget::parse("2024-03-05 22:59:30"), Ok(GotoTarget::Timestamp(datetime!(2024-03-05 22:59:30))));
+        assert!(GotoTarget::parse("150%").is_err());
+        assert!(GotoTarget::parse("slot 3").is_err());
+
+        // two events per second, starting at 22:59:25
+        let events = (0..10usize)
+            .map(|id| {
+                let time = LOGGED_AT + time::Duration::seconds(id as i64 / 2);
+                Event::new(id, EventLevel::Info, time, "server1", AbstractObject::new("PaxosSlotEntry"))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(GotoTarget::EventId(9).resolve(&events), Some(9));
+        assert_eq!(GotoTarget::EventId(10).resolve(&events), None);
+        assert_eq!(GotoTarget::TimeOfDay(time!(22:59:27)).resolve(&events), Some(4));
+        assert_eq!(GotoTarget::Timestamp(datetime!(2024-03-05 22:59:00)).resolve(&events), Some(0));
+        assert_eq!(GotoTarget::Timestamp(datetime!(2024-03-05 23:00:00)).resolve(&events), None);
+        assert_eq!(GotoTarget::Percent(100.0).resolve(&events), Some(9));
+        assert_eq!(GotoTarget::Percent(50.0).resolve(&events), Some(5));
+        assert_eq!(GotoTarget::Percent(0.0).resolve(&events), Some(0));
+        assert!(GotoTarget::EventId(0).resolve(&[]).is_none());
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeSet;
+use ratatui::style::Color;
+
+/// the matches of one highlight rule, and how they are drawn
+#[derive(Debug)]
+pub struct Highlight {
+    /// the name shown in the legend
+    name: String,
+    color: Color,
+    /// the events the rule's query matched
+    matches: BTreeSet<usize>,
+}
+
+impl Highlight {
+    pub fn new<StrT: Into<String>>(name: StrT, color: Color, matches: BTreeSet<usize>) -> Self {
+        Self {
+            name: name.into(),
+            color,
+            matches,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn matches(&self) -> &BTreeSet<usize> {
+        &self.matches
+    }
+}
+
+/// the highlight rules being drawn, in priority order
+#[derive(Debug, Default)]
+pub struct HighlightState {
+    /// the first rule that matches an event decides its color
+    highlights: Vec<Highlight>,
+    /// the rule whose matches n/N cycle through, or none to cycle through the active query's
+    nav_highlight: Option<usize>,
+}
+
+impl HighlightState {
+    pub fn highlights(&self) -> &[Highlight] {
+        &self.highlights
+    }
+
+    /// adds a rule with the highest priority, replacing any rule with the same name
+    pub fn add(&mut self, highlight: Highlight) {
+        self.keep_nav_highlight(|highlights| {
+            highlights.retain(|existing| existing.name != highlight.name);
+            highlights.insert(0, highlight);
+        });
+    }
+
+    /// draws more events in the color of the named rule, as its query finds them
+    pub fn extend_matches(&mut self, name: &str, matches: &BTreeSet<usize>) {
+        if let Some(highlight) = self.highlights.iter_mut().find(|highlight| highlight.name == name) {
+            highlight.matches.extend(matches);
+        }
+    }
+
+    /// removes the named rule, like when its query failed
+    pub fn remove(&mut self, name: &str) {
+        self.keep_nav_highlight(|highlights| highlights.retain(|existing| existing.name != name));
+    }
+
+    /// changes the rules while n/N keep cycling through the same one, if it's still there
+    fn keep_nav_highlight<ChangeT: FnOnce(&mut Vec<Highlight>)>(&mut self, change: ChangeT) {
+        let nav_name = self.nav_highlight().map(|nav| nav.name.clone());
+        change(&mut self.highlights);
+        self.nav_highlight = nav_name.and_then(|name| self.highlights.iter().position(|existing| existing.name == name));
+    }
+
+    /// removes the rule n/N cycle through, returning its name. n/N go back to the active query
+    pub fn remove_nav_highlight(&mut self) -> Option<String> {
+        let removed = self.highlights.remove(self.nav_highlight.take()?);
+        Some(removed.name)
+    }
+
+    /// the highest priority rule that matches the event
+    pub fn highlight_of(&self, event_idx: usize) -> Option<&Highlight> {
+        self.highlights.iter().find(|highlight| highlight.matches.contains(&event_idx))
+    }
+
+    /// the rule whose matches n/N cycle through, if it isn't the active query
+    pub fn nav_highlight(&self) -> Option<&Highlight> {
+        self.nav_highlight.and_then(|idx| self.highlights.get(idx))
+    }
+
+    pub fn nav_highlight_idx(&self) -> Option<usize> {
+        self.nav_highlight
+    }
+
+    /// makes n/N cycle through the next rule, going back to the active query after the last one
+    pub fn cycle_nav_highlight(&mut self) {
+        self.nav_highlight = match self.nav_highlight {
+            None if !self.highlights.is_empty() => Some(0),
+            Some(idx) if idx + 1 < self.highlights.len() => Some(idx + 1),
+            _ => None,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use ratatui::style::Color;
+    use crate::app::highlight_state::{Highlight, HighlightState};
+
+    #[test]
+    fn newest_rule_wins() {
+        let mut highlights = HighlightState::default();
+        highlights.add(Highlight::new("accepts", Color::Green, BTreeSet::from([1, 2, 3])));
+        highlights.add(Highlight::new("chosen", Color::Yellow, BTreeSet::from([3, 4])));
+        assert_eq!(highlights.highlight_of(3).map(|highlight| highlight.name()), Some("chosen"));
+        assert_eq!(highlights.highlight_of(1).map(|highlight| highlight.name()), Some("accepts"));
+        assert!(highlights.highlight_of(0).is_none());
+
+        // replacing a rule keeps cycling through it
+        highlights.cycle_nav_highlight();
+        highlights.cycle_nav_highlight();
+        assert_eq!(highlights.nav_highlight().map(|highlight| highlight.name()), Some("accepts"));
+        highlights.add(Highlight::new("accepts", Color::Blue, BTreeSet::from([1])));
+        assert_eq!(highlights.nav_highlight().map(|highlight| highlight.color()), Some(Color::Blue));
+        assert_eq!(highlights.highlight_of(3).map(|highlight| highlight.name()), Some("chosen"));
+
+        assert_eq!(highlights.remove_nav_highlight(), Some("accepts".to_string()));
+        assert!(highlights.nav_highlight().is_none());
+        assert_eq!(highlights.highlights().len(), 1);
+    }
+
+    #[test]
+    fn matches_arrive_after_the_rule() {
+        let mut highlights = HighlightState::default();
+        highlights.add(Highlight::new("accepts", Color::Green, BTreeSet::new()));
+        highlights.add(Highlight::new("chosen", Color::Yellow, BTreeSet::new()));
+        highlights.cycle_nav_highlight();
+        highlights.cycle_nav_highlight();
+
+        highlights.extend_matches("accepts", &BTreeSet::from([1, 2]));
+        highlights.extend_matches("accepts", &BTreeSet::from([5]));
+        assert_eq!(highlights.nav_highlight().map(|highlight| highlight.matches().len()), Some(3));
+        assert!(highlights.highlight_of(5).is_some());
+
+        // removing another rule keeps cycling through the same one
+        highlights.remove("chosen");
+        assert_eq!(highlights.nav_highlight().map(|highlight| highlight.name()), Some("accepts"));
+        highlights.remove("accepts");
+        assert!(highlights.nav_highlight().is_none());
+        assert!(highlights.highlights().is_empty());
+    }
+}
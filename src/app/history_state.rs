@@ -0,0 +1,109 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use crate::ds_events::diff::{changed_values, DiffLine};
+use crate::ds_events::event::Event;
+use crate::dsl::query_ast::PropPath;
+
+/// links each event to the last state of the same thing before it: the previous event of the
+/// same type, from the same node, with the same value at the key path. Events without a value at
+/// the key path are linked to the previous event of their type from their node that also has none
+#[derive(Debug, Default)]
+pub struct HistoryState {
+    /// the field that tells apart the things a node logs the state of, like `slotNum`
+    key: Option<PropPath>,
+    /// the index of the event before each event with the same type, node and key
+    previous: Vec<Option<usize>>,
+    /// the values that changed since the previous event, for each event they've been asked for.
+    /// The event list asks for them on every draw, so they're only compared once
+    changes: Vec<OnceCell<Vec<DiffLine>>>,
+    /// true if the changes are listed after each event in the event list
+    show_in_list: bool,
+}
+
+impl HistoryState {
+    /// links every event in the log to the one before it with the same key
+    pub fn build(events: &[Event], key: Option<PropPath>) -> Self {
+        let mut latest = HashMap::<(&str, &str, Option<String>), usize>::new();
+        let previous = events.iter()
+            .enumerate()
+            .map(|(event_idx, event)| {
+                let key_value = key.as_ref()
+                    .and_then(|key| key.lookup_value(event).ok())
+                    .map(|value| value.to_string());
+                latest.insert((event.event_obj().name(), event.originator(), key_value), event_idx)
+            })
+            .collect();
+
+        Self {
+            key,
+            previous,
+            changes: vec![OnceCell::new(); events.len()],
+            show_in_list: false,
+        }
+    }
+
+    pub fn key(&self) -> Option<&PropPath> {
+        self.key.as_ref()
+    }
+
+    /// the index of the last event before this one with the same type, node and key
+    pub fn previous(&self, event_idx: usize) -> Option<usize> {
+        self.previous.get(event_idx).copied().flatten()
+    }
+
+    /// the previous event's index along with the values that changed since it, or none if this is
+    /// the first event with its type, node and key
+    pub fn changes_since_previous(&self, events: &[Event], event_idx: usize) -> Option<(usize, &[DiffLine])> {
+        let previous_idx = self.previous(event_idx)?;
+        let changes = self.changes[event_idx]
+            .get_or_init(|| changed_values(events[previous_idx].event_obj(), events[event_idx].event_obj()));
+        Some((previous_idx, changes.as_slice()))
+    }
+
+    pub fn is_shown_in_list(&self) -> bool {
+        self.show_in_list
+    }
+
+    pub fn toggle_shown_in_list(&mut self) {
+        self.show_in_list = !self.show_in_list;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::history_state::HistoryState;
+    use crate::ds_events::abstract_object::AbstractObject;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::dsl::query_ast::PropPath;
+
+    #[test]
+    fn links_events_to_their_previous_state() {
+        let events = log([
+            ("server1", slot_entry(3).with_prop("slotStatus", "ACCEPTED")),
+            ("server2", slot_entry(3).with_prop("slotStatus", "ACCEPTED")),
+            ("server1", slot_entry(4).with_prop("slotStatus", "ACCEPTED")),
+            ("server1", slot_entry(3).with_prop("slotStatus", "CHOSEN")),
+            ("server1", AbstractObject::new("PaxosSlotEntry")),
+            ("server1", AbstractObject::new("PaxosSlotEntry")),
+        ]);
+
+        let history = HistoryState::build(&events, Some(PropPath { segments: vec!["slotNum".to_string()] }));
+        let previous = (0..events.len()).map(|idx| history.previous(idx)).collect::<Vec<_>>();
+        assert_eq!(previous, vec![None, None, None, Some(0), None, Some(4)]);
+
+        let (previous_idx, changes) = history.changes_since_previous(&events, 3).unwrap();
+        assert_eq!(previous_idx, 0);
+        let changed = changes.iter().map(|line| line.path()).collect::<Vec<_>>();
+        assert_eq!(changed, vec!["slotStatus"]);
+        assert!(history.changes_since_previous(&events, 2).is_none());
+
+        // the changes are only compared the first time they're asked for
+        let (_, changes_again) = history.changes_since_previous(&events, 3).unwrap();
+        assert_eq!(changes_again.as_ptr(), changes.as_ptr());
+
+        // without a key, every entry from a node is the next state of the one before it
+        let history = HistoryState::build(&events, None);
+        assert_eq!(history.previous(2), Some(0));
+        assert_eq!(history.previous(3), Some(2));
+    }
+}
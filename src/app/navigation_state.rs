@@ -1,42 +1,53 @@
-
 #[derive(Debug, Default)]
 pub struct NavigationState {
-    /// the order of elements we should navigate to
-    nav_order: Vec<usize>,
+    /// the order of groups of elements we should navigate to
+    nav_order: Vec<Vec<usize>>,
     /// the index in the nav order we are currently looking at
     current_idx: Option<usize>,
+    /// the index in the current group we are looking at
+    group_member_idx: usize,
 }
 
 impl NavigationState {
-    pub fn load_nav_order(&mut self, order: Vec<usize>) {
+    pub fn load_nav_order(&mut self, order: Vec<Vec<usize>>) {
         self.nav_order = order;
         self.current_idx = None;
+        self.group_member_idx = 0;
     }
     
     pub fn clear_nav_order(&mut self) {
         self.nav_order.clear();
         self.current_idx = None;
+        self.group_member_idx = 0;
+    }
+    
+    /// the group we are currently looking at, if we have started navigating
+    pub fn current_group(&self) -> Option<&[usize]> {
+        self.current_idx
+            .and_then(|idx| self.nav_order.get(idx))
+            .map(|group| group.as_slice())
     }
     
+    /// moves to the first event of the next group
     pub fn next_event(&mut self) -> Option<usize> {
         let next_idx = match self.current_idx {
             None => 0usize,
             Some(existing) => existing + 1,
         };
         
-        match self.nav_order.get(next_idx) {
+        match self.nav_order.get(next_idx).and_then(|group| group.first()) {
             None => None,
             Some(nav_order) => {
                 self.current_idx = Some(next_idx);
+                self.group_member_idx = 0;
                 Some(*nav_order)
             }
         }
     }
     
+    /// moves to the first event of the previous group
     pub fn prev_event(&mut self) -> Option<usize> {
-        let Some(existing) = self.current_idx else {
-            return None
-        };
+        let existing = self.current_idx?;
 
         // if at zero, clear
         let prev_idx = if existing == 0 {
@@ -47,12 +58,28 @@ impl NavigationState {
         };
         
         // otherwise, get
-        match self.nav_order.get(prev_idx) {
+        match self.nav_order.get(prev_idx).and_then(|group| group.first()) {
             None => None,
             Some(nav_order) => {
                 self.current_idx = Some(prev_idx);
+                self.group_member_idx = 0;
                 Some(*nav_order)
             }
         }
     }
+    
+    /// moves to the next event in the current group
+    pub fn next_in_group(&mut self) -> Option<usize> {
+        let next_member = *self.current_group()?.get(self.group_member_idx + 1)?;
+        self.group_member_idx += 1;
+        Some(next_member)
+    }
+    
+    /// moves to the previous event in the current group
+    pub fn prev_in_group(&mut self) -> Option<usize> {
+        let prev_member_idx = self.group_member_idx.checked_sub(1)?;
+        let prev_member = *self.current_group()?.get(prev_member_idx)?;
+        self.group_member_idx = prev_member_idx;
+        Some(prev_member)
+    }
 }
@@ -0,0 +1,31 @@
+#[derive(Debug, Default)]
+pub struct PickerState {
+    /// true if the query picker is shown
+    visible: bool,
+    /// the query the cursor is on
+    cursor: usize,
+}
+
+impl PickerState {
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn next(&mut self, query_count: usize) {
+        if self.cursor + 1 < query_count {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn prev(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+}
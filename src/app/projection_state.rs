@@ -0,0 +1,142 @@
+use std::cmp::Ordering;
+use crate::ds_events::abstract_object::AbstractValue;
+use crate::ds_events::event::Event;
+use crate::dsl::query_ast::PropPath;
+
+#[derive(Debug, Default)]
+pub struct ProjectionState {
+    /// the paths to show as columns in the event list. Empty shows whole events
+    columns: Vec<PropPath>,
+    /// the column we are sorting by, if any
+    sort_column: Option<usize>,
+    /// true if the sort is descending
+    sort_descending: bool,
+    /// the position in the log of the event shown at each row, or empty if events are shown in
+    /// log order
+    row_order: Vec<usize>,
+    /// the row the event at each position in the log is shown at, or empty if events are shown in
+    /// log order
+    event_rows: Vec<usize>,
+}
+
+impl ProjectionState {
+    pub fn columns(&self) -> &[PropPath] {
+        &self.columns
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.columns.is_empty()
+    }
+
+    /// the column we are sorting by, and if it's descending
+    pub fn sort_column(&self) -> Option<(usize, bool)> {
+        self.sort_column.map(|column| (column, self.sort_descending))
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<PropPath>, events: &[Event]) {
+        self.columns = columns;
+        self.sort_column = None;
+        self.sort_descending = false;
+        self.compute_order(events);
+    }
+
+    /// sorts by the next column, or goes back to log order after the last column
+    pub fn cycle_sort_column(&mut self, events: &[Event]) {
+        self.sort_column = match self.sort_column {
+            None if !self.columns.is_empty() => Some(0),
+            Some(column) if column + 1 < self.columns.len() => Some(column + 1),
+            _ => None,
+        };
+        self.compute_order(events);
+    }
+
+    pub fn toggle_sort_direction(&mut self, events: &[Event]) {
+        self.sort_descending = !self.sort_descending;
+        self.compute_order(events);
+    }
+
+    /// the event shown at each row, or empty if events are shown in log order
+    pub fn row_order(&self) -> &[usize] {
+        &self.row_order
+    }
+
+    /// the position in the log of the event shown at the given row
+    pub fn event_at_row(&self, row: usize) -> usize {
+        self.row_order.get(row)
+            .copied()
+            .unwrap_or(row)
+    }
+
+    /// the row the event at the given position in the log is shown at
+    pub fn row_of_event(&self, position: usize) -> usize {
+        self.event_rows.get(position)
+            .copied()
+            .unwrap_or(position)
+    }
+
+    fn compute_order(&mut self, events: &[Event]) {
+        self.row_order.clear();
+        self.event_rows.clear();
+        let Some(column) = self.sort_column.and_then(|column| self.columns.get(column)) else {
+            return;
+        };
+
+        // each event's key is built once, instead of on every comparison
+        let sort_keys = events.iter()
+            .map(|event| column.lookup_value(event).ok().map(AbstractValue::sort_key))
+            .collect::<Vec<_>>();
+        let mut row_order = (0..events.len()).collect::<Vec<_>>();
+        // events missing the column always go last
+        row_order.sort_by(|left, right| {
+            match (&sort_keys[*left], &sort_keys[*right]) {
+                (Some(left), Some(right)) if self.sort_descending => right.cmp(left),
+                (Some(left), Some(right)) => left.cmp(right),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        });
+
+        self.event_rows = vec![0; events.len()];
+        for (row, position) in row_order.iter().enumerate() {
+            self.event_rows[*position] = row;
+        }
+        self.row_order = row_order;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::projection_state::ProjectionState;
+    use crate::ds_events::abstract_object::AbstractObject;
+    use crate::ds_events::fixtures::{event, slot_entry};
+    use crate::dsl::query_ast::PropPath;
+
+    #[test]
+    fn sorts_by_column() {
+        // rows are positions in the log, which aren't always the same as event ids
+        let slots = [3, 1, 2];
+        let mut events = slots.iter()
+            .enumerate()
+            .map(|(position, slot)| event(position + 100, "server1", slot_entry(*slot)))
+            .collect::<Vec<_>>();
+        events.push(event(103, "server1", AbstractObject::new("Other")));
+
+        let mut projection = ProjectionState::default();
+        projection.set_columns(vec![PropPath { segments: vec!["slotNum".to_string()] }], &events);
+        assert_eq!(projection.event_at_row(0), 0);
+
+        projection.cycle_sort_column(&events);
+        let rows = (0..4).map(|row| projection.event_at_row(row)).collect::<Vec<_>>();
+        assert_eq!(rows, vec![1, 2, 0, 3]);
+        assert_eq!(projection.row_of_event(0), 2);
+
+        projection.toggle_sort_direction(&events);
+        let rows = (0..4).map(|row| projection.event_at_row(row)).collect::<Vec<_>>();
+        assert_eq!(rows, vec![0, 2, 1, 3]);
+
+        projection.cycle_sort_column(&events);
+        assert_eq!(projection.sort_column(), None);
+        assert_eq!(projection.event_at_row(0), 0);
+    }
+}
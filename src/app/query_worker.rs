@@ -0,0 +1,171 @@
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use crate::ds_events::event::Event;
+use crate::ds_events::index::EventIndex;
+use crate::dsl::filters::EventFilterError;
+use crate::dsl::query_ast::EventQuery;
+use crate::dsl::results::QueryResults;
+
+/// how many events the worker searches between progress updates
+const PIECE_SIZE: usize = 10_000;
+
+/// what the worker has found so far
+#[derive(Debug)]
+pub enum QueryUpdate {
+    /// another piece of the log was searched
+    Progress {
+        /// the events that matched in the piece
+        matches: BTreeSet<usize>,
+        /// how many events have been searched so far
+        searched: usize,
+    },
+    /// the query finished. The query is handed back so it can become the active filter
+    Finished {
+        query: Box<EventQuery>,
+        results: Result<QueryResults, EventFilterError>,
+    },
+}
+
+/// runs a query on its own thread, so the viewer keeps responding while big logs are searched
+#[derive(Debug)]
+pub struct QueryWorker {
+    /// the text of the query, for showing in the breadcrumb once it finishes
+    label: String,
+    receiver: mpsc::Receiver<QueryUpdate>,
+    /// set to ask the thread to stop searching
+    cancelled: Arc<AtomicBool>,
+    /// how many events are searched: the whole log, or the events being refined
+    total: usize,
+    /// how many events have been searched so far
+    searched: usize,
+    /// how many events have matched so far
+    match_count: usize,
+}
+
+impl QueryWorker {
+    /// starts running the query. If `within` is given, only those events are searched
+    pub fn spawn(label: String, query: EventQuery, events: Arc<[Event]>, index: Arc<EventIndex>, within: Option<BTreeSet<usize>>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let total = within.as_ref().map_or(events.len(), |within| within.len());
+
+        let thread_cancelled = Arc::clone(&cancelled);
+        thread::spawn(move || {
+            let results = query.run_in_pieces(&events, &index, within.as_ref(), PIECE_SIZE, |matches, searched| {
+                let update = QueryUpdate::Progress { matches: matches.clone(), searched };
+                // the viewer hangs up when it no longer wants the results
+                sender.send(update).is_ok() && !thread_cancelled.load(Ordering::Relaxed)
+            });
+
+            let results = match results {
+                Ok(Some(results)) => Ok(results),
+                // the search was cancelled, so nobody is waiting on the results
+                Ok(None) => return,
+                Err(err) => Err(err),
+            };
+
+            if !thread_cancelled.load(Ordering::Relaxed) {
+                // if the viewer already hung up, there's nobody to tell
+                let _ = sender.send(QueryUpdate::Finished { query: Box::new(query), results });
+            }
+        });
+
+        Self {
+            label,
+            receiver,
+            cancelled,
+            total,
+            searched: 0,
+            match_count: 0,
+        }
+    }
+
+    /// asks the thread to stop. Queries that can't be searched in pieces run to the end, but
+    /// their results are dropped
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// every update the thread has sent since the last call, without waiting for more
+    pub fn poll(&mut self) -> Vec<QueryUpdate> {
+        let updates = self.receiver.try_iter().collect::<Vec<_>>();
+        for update in &updates {
+            if let QueryUpdate::Progress { matches, searched } = update {
+                self.searched = *searched;
+                self.match_count += matches.len();
+            }
+        }
+
+        updates
+    }
+
+    /// the fraction of the log searched so far
+    pub fn progress(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        self.searched as f64 / self.total as f64
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn searched(&self) -> usize {
+        self.searched
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.match_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use crate::app::query_worker::{QueryUpdate, QueryWorker};
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::ds_events::index::EventIndex;
+    use crate::ds_events::schema::LogSchema;
+    use crate::dsl::parser::parse_event_query;
+
+    #[test]
+    fn streams_matches_then_finishes() {
+        let events = log((0..25_000i64).map(|id| ("server1", slot_entry(id % 100))));
+        let index = EventIndex::build(&events, &LogSchema::infer(&events));
+        let (_, query) = parse_event_query("find PaxosSlotEntry not(eq(slotNum, 7))").expect("Parsing should succeed");
+
+        let mut worker = QueryWorker::spawn("test".to_string(), query, events.into(), Arc::new(index), None);
+        let started = Instant::now();
+        let mut streamed = 0;
+        let finished = loop {
+            assert!(started.elapsed() < Duration::from_secs(10), "the worker never finished");
+            let mut finished = None;
+            for update in worker.poll() {
+                match update {
+                    QueryUpdate::Progress { matches, .. } => streamed += matches.len(),
+                    QueryUpdate::Finished { results, .. } => finished = Some(results),
+                }
+            }
+
+            if let Some(results) = finished {
+                break results.expect("Eval should succeed");
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+
+        assert_eq!(streamed, 24_750);
+        assert_eq!(finished.matches().len(), streamed);
+        assert_eq!(worker.match_count(), streamed);
+        assert_eq!(worker.searched(), worker.total());
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeSet;
+use crate::ds_events::schema::LogSchema;
+
+/// one row of the schema browser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaRow<'a> {
+    /// an object name, which can be expanded to show its fields
+    Object(&'a str),
+    /// a field of an object, by its dotted path
+    Field { object: &'a str, path: &'a str },
+}
+
+#[derive(Debug, Default)]
+pub struct SchemaState {
+    /// true if the schema browser is shown
+    visible: bool,
+    /// the object names whose fields are shown
+    expanded: BTreeSet<String>,
+    /// the row the cursor is on
+    cursor: usize,
+}
+
+impl SchemaState {
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_expanded(&self, object: &str) -> bool {
+        self.expanded.contains(object)
+    }
+
+    /// every row currently shown, with the fields of expanded objects under them
+    pub fn rows<'a>(&self, schema: &'a LogSchema) -> Vec<SchemaRow<'a>> {
+        let mut rows = Vec::new();
+        for (name, object) in schema.objects() {
+            rows.push(SchemaRow::Object(name));
+            if self.is_expanded(name) {
+                rows.extend(object.fields().keys().map(|path| SchemaRow::Field { object: name, path }));
+            }
+        }
+
+        rows
+    }
+
+    pub fn next_row(&mut self, schema: &LogSchema) {
+        if self.cursor + 1 < self.rows(schema).len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn prev_row(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// shows or hides the fields of the object under the cursor. On a field, collapses the
+    /// object it belongs to and moves the cursor back to it
+    pub fn toggle_expanded(&mut self, schema: &LogSchema) {
+        let rows = self.rows(schema);
+        let Some(row) = rows.get(self.cursor) else {
+            return;
+        };
+
+        let object = match row {
+            SchemaRow::Object(object) | SchemaRow::Field { object, .. } => object.to_string(),
+        };
+
+        if !self.expanded.remove(&object) {
+            self.expanded.insert(object);
+            return;
+        }
+
+        self.cursor = rows.iter()
+            .position(|row| *row == SchemaRow::Object(&object))
+            .unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::schema_state::{SchemaRow, SchemaState};
+    use crate::ds_events::abstract_object::AbstractObject;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::ds_events::schema::LogSchema;
+
+    #[test]
+    fn expands_and_collapses_objects() {
+        let events = log([
+            ("server1", AbstractObject::new("Ballot").with_prop("seqNum", 1i64)),
+            ("server1", slot_entry(1)),
+        ]);
+        let schema = LogSchema::infer(&events);
+        let mut state = SchemaState::default();
+        assert_eq!(state.rows(&schema), vec![SchemaRow::Object("Ballot"), SchemaRow::Object("PaxosSlotEntry")]);
+
+        state.next_row(&schema);
+        state.toggle_expanded(&schema);
+        state.next_row(&schema);
+        assert_eq!(state.rows(&schema)[state.cursor()], SchemaRow::Field { object: "PaxosSlotEntry", path: "slotNum" });
+
+        state.toggle_expanded(&schema);
+        assert_eq!(state.cursor(), 1);
+        assert_eq!(state.rows(&schema).len(), 2);
+    }
+}
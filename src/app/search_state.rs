@@ -0,0 +1,247 @@
+use std::ops::Range;
+use regex::{Regex, RegexBuilder};
+use crate::ds_events::event::Event;
+
+/// the text of an event that searches look through: its node and the object it logged, the same
+/// way the event list shows it
+pub fn searchable_text(event: &Event) -> String {
+    format!("{}: {}", event.originator(), event.event_obj())
+}
+
+/// state for searching the text of events, like `/` in `less`
+#[derive(Debug, Default)]
+pub struct SearchState {
+    /// the text typed into the prompt, while it's open
+    prompt: Option<String>,
+    /// what is being searched for. Updated as the prompt is typed in
+    pattern: Option<Regex>,
+    /// the pattern before the prompt was opened, which comes back if the search is cancelled
+    previous_pattern: Option<Regex>,
+    /// true if the prompt is a regex instead of plain text
+    use_regex: bool,
+    /// true if upper and lower case letters don't match each other
+    case_sensitive: bool,
+    /// why the text in the prompt isn't a valid pattern, if it isn't
+    error: Option<String>,
+    /// the row the cursor was on when the prompt was opened. Searches start from here as you type
+    origin_row: usize,
+    /// true if n/N go through the events the pattern is found in
+    navigating: bool,
+    /// true if the last search didn't find the pattern in any shown event
+    not_found: bool,
+    /// the searchable text of each event, formatted once so typing in the prompt only matches
+    texts: Vec<String>,
+}
+
+impl SearchState {
+    pub fn is_prompt_open(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    pub fn prompt(&self) -> Option<&str> {
+        self.prompt.as_deref()
+    }
+
+    pub fn pattern(&self) -> Option<&Regex> {
+        self.pattern.as_ref()
+    }
+
+    pub fn use_regex(&self) -> bool {
+        self.use_regex
+    }
+
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn origin_row(&self) -> usize {
+        self.origin_row
+    }
+
+    pub fn is_navigating(&self) -> bool {
+        self.navigating && self.pattern.is_some()
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.not_found
+    }
+
+    pub fn set_found(&mut self, found: bool) {
+        self.not_found = !found;
+    }
+
+    /// makes n/N go back to what they went through before the search
+    pub fn stop_navigating(&mut self) {
+        self.navigating = false;
+    }
+
+    /// opens an empty prompt, searching from the given row
+    pub fn open(&mut self, origin_row: usize) {
+        self.prompt = Some(String::new());
+        self.previous_pattern = self.pattern.take();
+        self.error = None;
+        self.not_found = false;
+        self.origin_row = origin_row;
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.push(c);
+            self.compile();
+        }
+    }
+
+    pub fn pop_char(&mut self) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.pop();
+            self.compile();
+        }
+    }
+
+    pub fn toggle_regex(&mut self) {
+        self.use_regex = !self.use_regex;
+        self.compile();
+    }
+
+    pub fn toggle_case_sensitive(&mut self) {
+        self.case_sensitive = !self.case_sensitive;
+        self.compile();
+    }
+
+    /// closes the prompt, keeping what was typed as the pattern. Returns false if nothing valid
+    /// was typed, in which case the earlier pattern is kept instead
+    pub fn commit(&mut self) -> bool {
+        self.prompt = None;
+        self.error = None;
+        if self.pattern.is_none() {
+            self.pattern = self.previous_pattern.take();
+            return false;
+        }
+
+        self.previous_pattern = None;
+        self.navigating = true;
+        true
+    }
+
+    /// closes the prompt, going back to the pattern from before it was opened
+    pub fn cancel(&mut self) {
+        self.prompt = None;
+        self.error = None;
+        self.pattern = self.previous_pattern.take();
+    }
+
+    /// formats the searchable text of every event, unless it already was
+    pub fn load_texts(&mut self, events: &[Event]) {
+        if self.texts.len() != events.len() {
+            self.texts = events.iter().map(searchable_text).collect();
+        }
+    }
+
+    /// true if the pattern is found in the searchable text of the event
+    pub fn is_event_match(&self, events: &[Event], event_idx: usize) -> bool {
+        match self.texts.get(event_idx) {
+            Some(text) => self.is_match(text),
+            None => self.is_match(&searchable_text(&events[event_idx])),
+        }
+    }
+
+    /// true if the pattern is found in the text
+    pub fn is_match(&self, text: &str) -> bool {
+        self.pattern.as_ref().is_some_and(|pattern| pattern.is_match(text))
+    }
+
+    /// where the pattern is found in the text
+    pub fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let Some(pattern) = &self.pattern else {
+            return Vec::new();
+        };
+
+        pattern.find_iter(text)
+            .filter(|found| !found.is_empty())
+            .map(|found| found.range())
+            .collect()
+    }
+
+    /// turns the prompt into a pattern, or explains why it can't be one
+    fn compile(&mut self) {
+        let Some(prompt) = self.prompt.as_deref() else {
+            return;
+        };
+        if prompt.is_empty() {
+            self.pattern = None;
+            self.error = None;
+            return;
+        }
+
+        let source = if self.use_regex {
+            prompt.to_string()
+        } else {
+            regex::escape(prompt)
+        };
+        match RegexBuilder::new(&source).case_insensitive(!self.case_sensitive).build() {
+            Ok(pattern) => {
+                self.pattern = Some(pattern);
+                self.error = None;
+            }
+            Err(_) => {
+                self.pattern = None;
+                self.error = Some("not a valid regex".to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::search_state::SearchState;
+    use crate::ds_events::fixtures::{log, slot_entry};
+
+    fn type_prompt(search: &mut SearchState, text: &str) {
+        for c in text.chars() {
+            search.push_char(c);
+        }
+    }
+
+    #[test]
+    fn searches_text_and_regexes() {
+        let mut search = SearchState::default();
+        search.open(0);
+        type_prompt(&mut search, "slotnum=1");
+        assert!(search.is_match("PaxosSlotEntry(slotNum=12)"));
+        assert_eq!(search.match_ranges("slotNum=1, slotNum=2, SLOTNUM=1"), vec![0..9, 22..31]);
+
+        search.toggle_case_sensitive();
+        assert!(!search.is_match("PaxosSlotEntry(slotNum=12)"));
+        assert!(search.commit());
+        assert!(search.is_navigating());
+
+        // a bad regex keeps the last pattern once the prompt closes
+        search.open(0);
+        search.toggle_regex();
+        type_prompt(&mut search, "slot(");
+        assert_eq!(search.error(), Some("not a valid regex"));
+        assert!(!search.commit());
+        assert!(search.is_match("slotnum=1"));
+
+        search.open(0);
+        type_prompt(&mut search, r"slotNum=\d{2}");
+        assert!(search.is_match("slotNum=12"));
+        search.cancel();
+        assert!(!search.is_match("slotNum=12"));
+    }
+
+    #[test]
+    fn matches_loaded_texts() {
+        let events = log([("server1", slot_entry(12)), ("server2", slot_entry(3))]);
+        let mut search = SearchState::default();
+        search.load_texts(&events);
+        search.open(0);
+        type_prompt(&mut search, "server2");
+        assert!(!search.is_event_match(&events, 0));
+        assert!(search.is_event_match(&events, 1));
+    }
+}
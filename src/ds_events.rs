@@ -8,12 +8,17 @@ use crate::ds_events::event::{Event, EventParser};
 pub mod event;
 pub mod err;
 pub mod abstract_object;
+pub mod diff;
+pub mod schema;
+pub mod index;
+#[cfg(test)]
+pub(crate) mod fixtures;
 
 pub fn parse_event_log(file: File) -> Result<Vec<Event>, AppError> {
     let reader = BufReader::new(file);
     let mut events: Vec<Event> = Vec::new();
     let event_parser = EventParser::new();
-    for (idx, line) in reader.lines().enumerate() {
+    for line in reader.lines() {
         let line = line
             .map_err(|line_err| AppError::new(format!("line with error: {}", line_err)))?;
 
@@ -1,6 +1,7 @@
 pub mod parser;
 pub mod pretty_print;
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter, Write};
 use std::hash::{Hash, Hasher};
@@ -74,6 +75,53 @@ impl Hash for AbstractValue {
     }
 }
 
+/// orders values for display. Numbers are compared numerically and go first, and everything else
+/// is compared by how it is displayed
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SortKey<'a> {
+    Number(i64),
+    Text(Cow<'a, str>),
+}
+
+impl Display for SortKey<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortKey::Number(num) => write!(f, "{}", num),
+            SortKey::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+/// the kinds of values an [`AbstractValue`] can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ValueKind {
+    Number,
+    Symbol,
+    Object,
+    Map,
+    Collection,
+}
+
+impl ValueKind {
+    /// true for numbers and symbols, which can be compared directly
+    pub fn is_scalar(&self) -> bool {
+        matches!(self, ValueKind::Number | ValueKind::Symbol)
+    }
+}
+
+impl Display for ValueKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ValueKind::Number => "numeric",
+            ValueKind::Symbol => "string",
+            ValueKind::Object => "object",
+            ValueKind::Map => "map",
+            ValueKind::Collection => "collection",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 impl Display for AbstractValue {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -99,6 +147,26 @@ impl Display for AbstractValue {
 }
 
 impl AbstractValue {
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            AbstractValue::Number(_) => ValueKind::Number,
+            AbstractValue::Symbol(_) => ValueKind::Symbol,
+            AbstractValue::Object(_) => ValueKind::Object,
+            AbstractValue::Map(_) => ValueKind::Map,
+            AbstractValue::Collection(_) => ValueKind::Collection,
+        }
+    }
+
+    /// the key this value is ordered by for display. Only objects, maps and collections are
+    /// formatted to build it
+    pub fn sort_key(&self) -> SortKey<'_> {
+        match self {
+            AbstractValue::Number(num) => SortKey::Number(*num),
+            AbstractValue::Symbol(symb) => SortKey::Text(Cow::Borrowed(symb)),
+            other => SortKey::Text(Cow::Owned(other.to_string())),
+        }
+    }
+
     pub fn try_eq_raw_str<StrT: AsRef<str>>(&self, val: StrT) -> Option<bool> {
         match self {
             AbstractValue::Number(cmp_to) => {
@@ -132,7 +132,7 @@ mod tests {
         let text = "[1, 2, 3]";
         let (_, items) = parse_collection(text).expect("should parse successfully");
         assert_eq!(items.len(), 3);
-        let AbstractValue::Number(first_item) = items.get(0).expect("first element should be something") else {
+        let AbstractValue::Number(first_item) = items.first().expect("first element should be something") else {
             panic!("element was not a number")
         };
         assert_eq!(first_item, &1)
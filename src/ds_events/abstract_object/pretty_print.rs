@@ -65,7 +65,7 @@ impl<'a> Display for AbstractObjectPrettyPrinter<'a> {
                     write!(f, "{:?}", collect)
                 }
             }?;
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
         write!(f, "{})", base_indent_str)
     }
@@ -0,0 +1,307 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::ds_events::abstract_object::{AbstractObject, AbstractValue};
+
+/// the most pairs of items two collections can have and still be matched up by what they hold.
+/// Bigger collections are compared item by item, since matching them up takes as many steps
+const MAX_MATCHED_PAIRS: usize = 1_000_000;
+
+/// how a value differs between two events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    /// only the second event has the value
+    Added,
+    /// only the first event has the value
+    Removed,
+    /// both events have the value, but it isn't the same
+    Changed,
+}
+
+/// one line of a diff between two objects, lined up so both sides of a value are on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffLine {
+    /// where the value is, like `ballot.roundNum` or `acceptors[1]`. Collection items are found by
+    /// where they are in the second event, or by both positions if only the first has them
+    path: String,
+    /// how many levels down the tree the value is
+    depth: usize,
+    /// the field name or map key the value is under, or where a collection item is in both events,
+    /// like `[2→1]`
+    label: String,
+    change: Change,
+    /// the value in the first event, or a summary of it if it has values inside of it
+    left: Option<String>,
+    /// the value in the second event, or a summary of it if it has values inside of it
+    right: Option<String>,
+}
+
+impl DiffLine {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn change(&self) -> Change {
+        self.change
+    }
+
+    pub fn left(&self) -> Option<&str> {
+        self.left.as_deref()
+    }
+
+    pub fn right(&self) -> Option<&str> {
+        self.right.as_deref()
+    }
+}
+
+/// lines up the fields of two objects, marking what was added, removed and changed from the
+/// first to the second. Values that are the same are one line, even if they have values inside
+/// of them, and collection items are matched up by what they hold instead of where they are
+pub fn diff_objects(left: &AbstractObject, right: &AbstractObject) -> Vec<DiffLine> {
+    let mut lines = Vec::new();
+    diff_props(left.props(), right.props(), None, 0, &mut lines);
+    lines
+}
+
+/// the values that differ between two objects, leaving out the objects, maps and collections
+/// they are inside of
+pub fn changed_values(left: &AbstractObject, right: &AbstractObject) -> Vec<DiffLine> {
+    let lines = diff_objects(left, right);
+    lines.iter()
+        .enumerate()
+        .filter(|(idx, line)| {
+            // a line is only a heading if what changed is in the lines under it. An object whose
+            // name changed but whose values didn't is a change of its own
+            let is_heading = lines[idx + 1..].iter()
+                .take_while(|child| child.depth > line.depth)
+                .any(|child| child.change != Change::Same);
+            line.change != Change::Same && !is_heading
+        })
+        .map(|(_, line)| line.clone())
+        .collect()
+}
+
+/// a value itself for numbers and symbols, and a summary of it for everything else
+fn summarize(value: &AbstractValue) -> String {
+    match value {
+        AbstractValue::Number(_) | AbstractValue::Symbol(_) => value.to_string(),
+        AbstractValue::Object(object) => object.name().to_string(),
+        AbstractValue::Map(map) => format!("{} entries", map.len()),
+        AbstractValue::Collection(items) => format!("{} items", items.len()),
+    }
+}
+
+fn diff_props(left: &HashMap<String, AbstractValue>, right: &HashMap<String, AbstractValue>, parent: Option<&str>, depth: usize, lines: &mut Vec<DiffLine>) {
+    let names = left.keys()
+        .chain(right.keys())
+        .collect::<BTreeSet<_>>();
+    for name in names {
+        let path = match parent {
+            Some(parent) => format!("{}.{}", parent, name),
+            None => name.clone(),
+        };
+        diff_values(left.get(name), right.get(name), path, name.clone(), depth, lines);
+    }
+}
+
+fn diff_values(left: Option<&AbstractValue>, right: Option<&AbstractValue>, path: String, label: String, depth: usize, lines: &mut Vec<DiffLine>) {
+    let change = match (left, right) {
+        (Some(left), Some(right)) if left == right => Change::Same,
+        (Some(_), Some(_)) => Change::Changed,
+        (Some(_), None) => Change::Removed,
+        (None, _) => Change::Added,
+    };
+    lines.push(DiffLine {
+        path: path.clone(),
+        depth,
+        label,
+        change,
+        left: left.map(summarize),
+        right: right.map(summarize),
+    });
+    if change != Change::Changed {
+        return;
+    }
+
+    match (left, right) {
+        (Some(AbstractValue::Object(left)), Some(AbstractValue::Object(right))) => {
+            diff_props(left.props(), right.props(), Some(&path), depth + 1, lines);
+        }
+        (Some(AbstractValue::Map(left)), Some(AbstractValue::Map(right))) => {
+            diff_props(left, right, Some(&path), depth + 1, lines);
+        }
+        (Some(AbstractValue::Collection(left)), Some(AbstractValue::Collection(right))) => {
+            diff_collections(left, right, &path, depth + 1, lines);
+        }
+        _ => {}
+    }
+}
+
+/// matches up the items both collections hold, in order, using their longest common subsequence.
+/// Items between the matches that were removed and added are paired up as changes
+fn diff_collections(left: &[AbstractValue], right: &[AbstractValue], path: &str, depth: usize, lines: &mut Vec<DiffLine>) {
+    if left.len().saturating_mul(right.len()) > MAX_MATCHED_PAIRS {
+        for idx in 0..left.len().max(right.len()) {
+            diff_values(left.get(idx), right.get(idx), format!("{}[{}]", path, idx), format!("[{}]", idx), depth, lines);
+        }
+        return;
+    }
+
+    // common[i][j] is how many items left[i..] and right[j..] have in common
+    let mut common = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            common[i][j] = if left[i] == right[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() || j < right.len() {
+        if i < left.len() && j < right.len() && left[i] == right[j] {
+            push_unmatched_items(left, right, &mut removed, &mut added, path, depth, lines);
+            diff_values(Some(&left[i]), Some(&right[j]), item_path(path, Some(j), Some(i)), item_label(Some(i), Some(j)), depth, lines);
+            i += 1;
+            j += 1;
+        } else if j < right.len() && (i == left.len() || common[i][j + 1] >= common[i + 1][j]) {
+            added.push(j);
+            j += 1;
+        } else {
+            removed.push(i);
+            i += 1;
+        }
+    }
+    push_unmatched_items(left, right, &mut removed, &mut added, path, depth, lines);
+}
+
+/// writes the items between two matched items, pairing removed items with added ones
+fn push_unmatched_items(left: &[AbstractValue], right: &[AbstractValue], removed: &mut Vec<usize>, added: &mut Vec<usize>, path: &str, depth: usize, lines: &mut Vec<DiffLine>) {
+    for idx in 0..removed.len().max(added.len()) {
+        let left_idx = removed.get(idx).copied();
+        let right_idx = added.get(idx).copied();
+        diff_values(left_idx.map(|idx| &left[idx]), right_idx.map(|idx| &right[idx]), item_path(path, right_idx, left_idx), item_label(left_idx, right_idx), depth, lines);
+    }
+    removed.clear();
+    added.clear();
+}
+
+/// the path of a collection item, by where it is in the second event so it names the same value
+/// as the second event's details. Items only the first event has are named by both positions
+fn item_path(collection_path: &str, right_idx: Option<usize>, left_idx: Option<usize>) -> String {
+    match right_idx {
+        Some(right_idx) => format!("{}[{}]", collection_path, right_idx),
+        None => format!("{}{}", collection_path, item_label(left_idx, None)),
+    }
+}
+
+/// where an item is in each event, like `[1]` if it didn't move, `[2→1]` if it did, or `[-→3]` if
+/// the first event doesn't have it
+fn item_label(left_idx: Option<usize>, right_idx: Option<usize>) -> String {
+    let position = |idx: Option<usize>| idx.map_or("-".to_string(), |idx| idx.to_string());
+    if left_idx == right_idx {
+        return format!("[{}]", position(left_idx));
+    }
+
+    format!("[{}→{}]", position(left_idx), position(right_idx))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds_events::abstract_object::{AbstractObject, AbstractValue};
+    use crate::ds_events::diff::{changed_values, diff_objects, Change, DiffLine};
+
+    fn describe(lines: &[DiffLine]) -> Vec<String> {
+        lines.iter()
+            .map(|line| format!("{:?} {} {:?} {:?}", line.change(), line.path(), line.left(), line.right()))
+            .collect()
+    }
+
+    #[test]
+    fn diffs_slot_entries() {
+        let servers = |names: &[&str]| names.iter()
+            .map(|name| AbstractValue::from(*name))
+            .collect::<Vec<_>>();
+        let accepted = AbstractObject::new("PaxosSlotEntry")
+            .with_prop("slotNum", 3i64)
+            .with_prop("slotStatus", "ACCEPTED")
+            .with_prop("ballot", AbstractObject::new("Ballot").with_prop("roundNum", 1i64).with_prop("serverAddress", "server1"))
+            .with_prop("acceptors", servers(&["server1", "server3"]));
+        let chosen = AbstractObject::new("PaxosSlotEntry")
+            .with_prop("slotNum", 3i64)
+            .with_prop("slotStatus", "CHOSEN")
+            .with_prop("ballot", AbstractObject::new("Ballot").with_prop("roundNum", 2i64).with_prop("serverAddress", "server1"))
+            .with_prop("acceptors", servers(&["server1", "server2", "server4"]))
+            .with_prop("isExecuted", "true");
+
+        assert_eq!(describe(&diff_objects(&accepted, &chosen)), vec![
+            r#"Changed acceptors Some("2 items") Some("3 items")"#,
+            r#"Same acceptors[0] Some("server1") Some("server1")"#,
+            r#"Changed acceptors[1] Some("server3") Some("server2")"#,
+            r#"Added acceptors[2] None Some("server4")"#,
+            r#"Changed ballot Some("Ballot") Some("Ballot")"#,
+            r#"Changed ballot.roundNum Some("1") Some("2")"#,
+            r#"Same ballot.serverAddress Some("server1") Some("server1")"#,
+            r#"Added isExecuted None Some("true")"#,
+            r#"Same slotNum Some("3") Some("3")"#,
+            r#"Changed slotStatus Some("ACCEPTED") Some("CHOSEN")"#,
+        ]);
+
+        let changed = changed_values(&accepted, &chosen).into_iter()
+            .map(|line| line.path().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(changed, vec!["acceptors[1]", "acceptors[2]", "ballot.roundNum", "isExecuted", "slotStatus"]);
+
+        // identical objects are one line each, without what's inside of them
+        assert!(diff_objects(&accepted, &accepted).iter().all(|line| line.change() == Change::Same));
+        assert_eq!(diff_objects(&accepted, &accepted).len(), 4);
+    }
+
+    #[test]
+    fn keeps_changed_object_names() {
+        let ballot = |name: &str| AbstractObject::new("Accept")
+            .with_prop("ballot", AbstractObject::new(name).with_prop("roundNum", 1i64));
+        let changed = changed_values(&ballot("Ballot"), &ballot("BallotV2"));
+        assert_eq!(describe(&changed), vec![r#"Changed ballot Some("Ballot") Some("BallotV2")"#]);
+    }
+
+    #[test]
+    fn labels_items_by_both_positions() {
+        let items = |values: &[i64]| values.iter()
+            .map(|value| AbstractValue::from(*value))
+            .collect::<Vec<_>>();
+        let before = AbstractObject::new("Log").with_prop("entries", items(&[1, 2, 3]));
+        let after = AbstractObject::new("Log").with_prop("entries", items(&[0, 1]));
+
+        let labels = diff_objects(&before, &after).into_iter()
+            .skip(1)
+            .map(|line| format!("{:?} {}", line.change(), line.label()))
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["Added [-→0]", "Same [0→1]", "Removed [1→-]", "Removed [2→-]"]);
+        let paths = diff_objects(&before, &after).into_iter()
+            .skip(1)
+            .map(|line| line.path().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["entries[0]", "entries[1]", "entries[1→-]", "entries[2→-]"]);
+
+        // collections too big to match up are compared item by item
+        let count = 1001;
+        let before = AbstractObject::new("Log").with_prop("entries", items(&(0..count).collect::<Vec<_>>()));
+        let after = AbstractObject::new("Log").with_prop("entries", items(&(1..=count).collect::<Vec<_>>()));
+        let lines = diff_objects(&before, &after);
+        assert_eq!(lines.len(), count as usize + 1);
+        assert!(lines[1..].iter().all(|line| line.change() == Change::Changed));
+        assert_eq!(lines[1].path(), "entries[0]");
+    }
+}
@@ -81,19 +81,36 @@ impl Event {
     }
 }
 
-pub struct EventParser<'a> {
+/// the format of timestamps in DSLabs logs, e.g. `2024-03-05 22:59:25`
+const TIMESTAMP_FORMAT: &[FormatItem<'static>] = format_description!("[year]-[month padding:zero repr:numerical]-[day padding:zero] [hour]:[minute padding:zero]:[second padding:zero]");
+
+/// parses a timestamp in the same format that DSLabs uses for its log lines
+pub fn parse_timestamp<StrT: AsRef<str>>(timestamp: StrT) -> Result<PrimitiveDateTime, AppError> {
+    PrimitiveDateTime::parse(timestamp.as_ref(), TIMESTAMP_FORMAT)
+        .map_err(|err| AppError::new(format!("could not parse timestamp: {}", err)))
+}
+
+/// formats a timestamp the same way DSLabs does
+pub fn format_timestamp(timestamp: PrimitiveDateTime) -> String {
+    timestamp.format(TIMESTAMP_FORMAT)
+        .expect("Timestamps should always be formattable")
+}
+
+pub struct EventParser {
     line_regex: Regex,
-    timestamp_format: Vec<FormatItem<'a>>,
     running_id: Cell<usize>,
 }
 
-impl<'a> EventParser<'a> {
+impl Default for EventParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventParser {
     pub fn new() -> Self {
-        let format = format_description!("[year]-[month padding:zero repr:numerical]-[day padding:zero] [hour]:[minute padding:zero]:[second padding:zero]");
-        let owned = format.to_owned();
         Self {
             line_regex: Regex::new(r"^\[(\w+)\s*] \[([^]]+)] \[[^]]+] ([\w\-]+): (.+)$").expect("Regex compilation should not fail"),
-            timestamp_format: owned,
             running_id: Cell::new(0),
         }
     }
@@ -104,8 +121,7 @@ impl<'a> EventParser<'a> {
             let log_level = EventLevel::try_from(log_level)?;
 
             let timestamp = result.get(2).unwrap().as_str();
-            let ts = PrimitiveDateTime::parse(timestamp, &self.timestamp_format)
-                .map_err(|err| AppError::new(format!("could not parse timestamp: {}", err)))?;
+            let ts = parse_timestamp(timestamp)?;
 
             let originator = result.get(3).unwrap().as_str();
 
@@ -125,7 +141,7 @@ impl<'a> EventParser<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::ds_events::event::{EventLevel, EventParser};
+    use crate::ds_events::event::{parse_timestamp, EventLevel, EventParser};
 
     #[test]
     fn event_level_order_correctly() {
@@ -140,4 +156,12 @@ mod tests {
             .parse(event_line)
             .expect("Parsing should not fail");
     }
+    
+    #[test]
+    fn parse_log_timestamp() {
+        let ts = parse_timestamp("2024-03-05 22:59:25").expect("timestamp should parse");
+        assert_eq!(ts.hour(), 22);
+        assert_eq!(ts.second(), 25);
+        assert!(parse_timestamp("2024-03-05").is_err());
+    }
 }
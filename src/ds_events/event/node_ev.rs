@@ -90,7 +90,7 @@ impl CustomEventNode {
     }
     
     pub fn sender(&self) -> Option<&str> {
-        self.sender.as_ref().map(|s| s.as_str())
+        self.sender.as_deref()
     }
 }
 
@@ -93,7 +93,7 @@ fn parse_custom_event_body(input: &str) -> IResult<&str, AbstractValue> {
     let not_quote_slash = is_not("\"\\");
     
     alt((
-            map(parse_abstract_object, |abstract_obj| AbstractValue::Object(abstract_obj)),
+            map(parse_abstract_object, AbstractValue::Object),
             map(not_quote_slash, |msg: &str| AbstractValue::Symbol(msg.to_string()))
     ))(input)
 }
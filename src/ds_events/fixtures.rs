@@ -0,0 +1,25 @@
+use time::macros::datetime;
+use time::PrimitiveDateTime;
+use crate::ds_events::abstract_object::AbstractObject;
+use crate::ds_events::event::{Event, EventLevel};
+
+/// the time test events are logged at, unless a test needs them spread out
+pub(crate) const LOGGED_AT: PrimitiveDateTime = datetime!(2024-03-05 22:59:25);
+
+/// an info event logged at [`LOGGED_AT`]
+pub(crate) fn event<StrT: Into<String>>(id: usize, originator: StrT, object: AbstractObject) -> Event {
+    Event::new(id, EventLevel::Info, LOGGED_AT, originator, object)
+}
+
+/// a log of info events with the given originators and objects, with ids counting up from 0
+pub(crate) fn log<StrT: Into<String>>(entries: impl IntoIterator<Item = (StrT, AbstractObject)>) -> Vec<Event> {
+    entries.into_iter()
+        .enumerate()
+        .map(|(id, (originator, object))| event(id, originator, object))
+        .collect()
+}
+
+/// a `PaxosSlotEntry` for the given slot, for tests to add the rest of its fields to
+pub(crate) fn slot_entry(slot_num: i64) -> AbstractObject {
+    AbstractObject::new("PaxosSlotEntry").with_prop("slotNum", slot_num)
+}
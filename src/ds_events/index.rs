@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use crate::ds_events::abstract_object::AbstractValue;
+use crate::ds_events::event::Event;
+use crate::ds_events::schema::LogSchema;
+
+/// the most paths whose values are indexed. The most common paths in the log are picked
+const MAX_INDEXED_PATHS: usize = 32;
+
+/// the ids of the events with each value at one path
+#[derive(Debug, Default)]
+struct ValueIndex {
+    numbers: HashMap<i64, Vec<usize>>,
+    symbols: HashMap<String, Vec<usize>>,
+    /// every event with a number at the path
+    numeric: Vec<usize>,
+    /// every event with an object, collection or map at the path
+    non_scalar: Vec<usize>,
+}
+
+/// inverted indexes over a log, from things queries commonly ask about to the ids of the events
+/// that have them. Every list of ids is sorted
+#[derive(Debug, Default)]
+pub struct EventIndex {
+    by_name: HashMap<String, Vec<usize>>,
+    by_originator: HashMap<String, Vec<usize>>,
+    /// the values of the most common scalar paths, by their dotted path
+    by_value: HashMap<String, ValueIndex>,
+    /// every id in the log, in order
+    all_ids: Vec<usize>,
+}
+
+impl EventIndex {
+    /// indexes the log, along with the values of the most common paths in its schema
+    pub fn build(events: &[Event], schema: &LogSchema) -> Self {
+        let mut index = Self::default();
+
+        // count how often each scalar path appears across every object
+        let mut path_counts = HashMap::<&str, usize>::new();
+        for object in schema.objects().values() {
+            for (path, field) in object.fields() {
+                let scalar_count = field.kinds().iter()
+                    .filter(|(kind, _)| kind.is_scalar())
+                    .map(|(_, count)| count)
+                    .sum::<usize>();
+                if scalar_count > 0 {
+                    *path_counts.entry(path).or_default() += scalar_count;
+                }
+            }
+        }
+        let mut common_paths = path_counts.into_iter().collect::<Vec<_>>();
+        common_paths.sort_by(|(left_path, left), (right_path, right)| right.cmp(left).then(left_path.cmp(right_path)));
+        let indexed_paths = common_paths.into_iter()
+            .take(MAX_INDEXED_PATHS)
+            .map(|(path, _)| (path.to_string(), path.split('.').collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+
+        for event in events {
+            let id = event.id();
+            index.all_ids.push(id);
+            index.by_name.entry(event.event_obj().name().to_string()).or_default().push(id);
+            index.by_originator.entry(event.originator().to_string()).or_default().push(id);
+
+            for (path, segments) in &indexed_paths {
+                let value_index = index.by_value.entry(path.clone()).or_default();
+                match lookup_segments(event, segments) {
+                    Some(AbstractValue::Number(number)) => {
+                        value_index.numbers.entry(*number).or_default().push(id);
+                        value_index.numeric.push(id);
+                    }
+                    Some(AbstractValue::Symbol(symbol)) => value_index.symbols.entry(symbol.clone()).or_default().push(id),
+                    Some(_) => value_index.non_scalar.push(id),
+                    None => {}
+                }
+            }
+        }
+
+        index
+    }
+
+    /// every id in the log
+    pub fn all_ids(&self) -> &[usize] {
+        &self.all_ids
+    }
+
+    /// the events with the given object name
+    pub fn with_name(&self, name: &str) -> &[usize] {
+        self.by_name.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// the events logged by the given node
+    pub fn with_originator(&self, originator: &str) -> &[usize] {
+        self.by_originator.get(originator).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn is_path_indexed(&self, path: &str) -> bool {
+        self.by_value.contains_key(path)
+    }
+
+    /// the events whose value at the path would compare equal to `comparison` in an `eq`, along
+    /// with the ones whose value can't be compared to it, so the query still counts those. `None`
+    /// if the path isn't indexed
+    pub fn with_value(&self, path: &str, comparison: &str) -> Option<Vec<usize>> {
+        let value_index = self.by_value.get(path)?;
+        let symbols = value_index.symbols.get(comparison).map(Vec::as_slice).unwrap_or_default();
+        let (numbers, mismatched) = match comparison.parse::<i64>() {
+            Ok(number) => {
+                let numbers = value_index.numbers.get(&number).map(Vec::as_slice).unwrap_or_default();
+                (numbers, value_index.non_scalar.clone())
+            }
+            Err(_) => (Default::default(), union(&value_index.numeric, &value_index.non_scalar)),
+        };
+
+        Some(union(&union(symbols, numbers), &mismatched))
+    }
+}
+
+/// the value at a path in the event, the same one `PropPath::lookup_value` finds. Segments after
+/// a collection are item indexes
+fn lookup_segments<'ev>(event: &'ev Event, segments: &[&str]) -> Option<&'ev AbstractValue> {
+    let (first, rest) = segments.split_first()?;
+    let mut value = event.event_obj().props().get(*first)?;
+    for segment in rest {
+        value = match value {
+            AbstractValue::Object(obj) => obj.props().get(*segment)?,
+            AbstractValue::Map(map) => map.get(*segment)?,
+            AbstractValue::Collection(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(value)
+}
+
+/// the part of a sorted list of ids that falls in the range
+pub fn within<'ids>(ids: &'ids [usize], range: &Range<usize>) -> &'ids [usize] {
+    let start = ids.partition_point(|id| *id < range.start);
+    let end = ids.partition_point(|id| *id < range.end);
+    &ids[start..end.max(start)]
+}
+
+/// the ids in both sorted lists
+pub fn intersect(left: &[usize], right: &[usize]) -> Vec<usize> {
+    let mut result = Vec::with_capacity(left.len().min(right.len()));
+    let (mut left_idx, mut right_idx) = (0, 0);
+    while left_idx < left.len() && right_idx < right.len() {
+        match left[left_idx].cmp(&right[right_idx]) {
+            std::cmp::Ordering::Less => left_idx += 1,
+            std::cmp::Ordering::Greater => right_idx += 1,
+            std::cmp::Ordering::Equal => {
+                result.push(left[left_idx]);
+                left_idx += 1;
+                right_idx += 1;
+            }
+        }
+    }
+
+    result
+}
+
+/// the ids in either sorted list
+pub fn union(left: &[usize], right: &[usize]) -> Vec<usize> {
+    let mut result = Vec::with_capacity(left.len() + right.len());
+    let (mut left_idx, mut right_idx) = (0, 0);
+    while left_idx < left.len() && right_idx < right.len() {
+        match left[left_idx].cmp(&right[right_idx]) {
+            std::cmp::Ordering::Less => {
+                result.push(left[left_idx]);
+                left_idx += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                result.push(right[right_idx]);
+                right_idx += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                result.push(left[left_idx]);
+                left_idx += 1;
+                right_idx += 1;
+            }
+        }
+    }
+    result.extend_from_slice(&left[left_idx..]);
+    result.extend_from_slice(&right[right_idx..]);
+
+    result
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use crate::ds_events::abstract_object::{AbstractValue, ValueKind};
+use crate::ds_events::event::Event;
+
+/// the most distinct sample values kept for each field
+const MAX_SAMPLES: usize = 5;
+
+/// what we've seen at one property path across every event of a type
+#[derive(Debug, Default, Clone)]
+pub struct FieldSchema {
+    /// how many events had this path
+    count: usize,
+    /// how many events had each kind of value at this path
+    kinds: BTreeMap<ValueKind, usize>,
+    /// a few distinct values seen at this path
+    samples: Vec<String>,
+}
+
+impl FieldSchema {
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn kinds(&self) -> &BTreeMap<ValueKind, usize> {
+        &self.kinds
+    }
+
+    pub fn samples(&self) -> &[String] {
+        &self.samples
+    }
+
+    fn observe(&mut self, value: &AbstractValue) {
+        self.count += 1;
+        *self.kinds.entry(value.kind()).or_default() += 1;
+
+        // only scalar values make useful samples
+        if matches!(value, AbstractValue::Number(_) | AbstractValue::Symbol(_)) && self.samples.len() < MAX_SAMPLES {
+            let sample = value.to_string();
+            if !self.samples.contains(&sample) {
+                self.samples.push(sample);
+            }
+        }
+    }
+}
+
+/// every property path seen on events with the same object name
+#[derive(Debug, Default, Clone)]
+pub struct ObjectSchema {
+    /// how many events had this object
+    event_count: usize,
+    /// each dotted property path, like `ballot.seqNum`, and what was seen there
+    fields: BTreeMap<String, FieldSchema>,
+}
+
+impl ObjectSchema {
+    pub fn event_count(&self) -> usize {
+        self.event_count
+    }
+
+    pub fn fields(&self) -> &BTreeMap<String, FieldSchema> {
+        &self.fields
+    }
+
+    pub fn field(&self, path: &str) -> Option<&FieldSchema> {
+        self.fields.get(path)
+    }
+
+    fn observe_props<'ev, IterT>(&mut self, prefix: &str, props: IterT)
+        where IterT: IntoIterator<Item = (&'ev String, &'ev AbstractValue)>
+    {
+        for (name, value) in props {
+            let path = if prefix.is_empty() {
+                name.clone()
+            } else {
+                format!("{}.{}", prefix, name)
+            };
+
+            self.observe_value(path, value);
+        }
+    }
+
+    /// records the value at the path, along with everything inside of it. Collection items are
+    /// recorded under their index, like `acceptors.0`
+    fn observe_value(&mut self, path: String, value: &AbstractValue) {
+        self.fields.entry(path.clone()).or_default().observe(value);
+        match value {
+            AbstractValue::Object(obj) => self.observe_props(&path, obj.props()),
+            AbstractValue::Map(map) => self.observe_props(&path, map),
+            AbstractValue::Collection(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    self.observe_value(format!("{}.{}", path, idx), item);
+                }
+            }
+            AbstractValue::Number(_) | AbstractValue::Symbol(_) => {}
+        }
+    }
+}
+
+/// the shape of every object in a log, inferred from the events in it
+#[derive(Debug, Default, Clone)]
+pub struct LogSchema {
+    /// the schema of each object name
+    objects: BTreeMap<String, ObjectSchema>,
+}
+
+impl LogSchema {
+    /// walks every event and records the paths each object name has
+    pub fn infer(events: &[Event]) -> Self {
+        let mut objects = BTreeMap::<String, ObjectSchema>::new();
+        for event in events {
+            let obj = event.event_obj();
+            let schema = objects.entry(obj.name().to_string()).or_default();
+            schema.event_count += 1;
+            schema.observe_props("", obj.props());
+        }
+
+        Self { objects }
+    }
+
+    pub fn objects(&self) -> &BTreeMap<String, ObjectSchema> {
+        &self.objects
+    }
+
+    pub fn object(&self, name: &str) -> Option<&ObjectSchema> {
+        self.objects.get(name)
+    }
+
+    /// the schema of each object name whose events have the path
+    pub fn fields_at<'a>(&'a self, path: &'a str) -> impl Iterator<Item = (&'a str, &'a FieldSchema)> + 'a {
+        self.objects.iter()
+            .filter_map(move |(name, schema)| schema.field(path).map(|field| (name.as_str(), field)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds_events::abstract_object::{AbstractObject, AbstractValue, ValueKind};
+    use crate::ds_events::fixtures::log;
+    use crate::ds_events::schema::LogSchema;
+
+    #[test]
+    fn infers_nested_paths() {
+        let events = log((0..3i64).map(|id| {
+            let ballot = AbstractObject::new("Ballot")
+                .with_prop("seqNum", id);
+            let mut obj = AbstractObject::new("PaxosSlotEntry")
+                .with_prop("ballot", ballot);
+            if id != 1 {
+                obj.insert_prop("slotStatus", "CHOSEN".to_string());
+            }
+            ("server1", obj)
+        }));
+
+        let schema = LogSchema::infer(&events);
+        let slot_schema = schema.object("PaxosSlotEntry").expect("Object should be in the schema");
+        assert_eq!(slot_schema.event_count(), 3);
+        assert_eq!(slot_schema.fields().keys().collect::<Vec<_>>(), vec!["ballot", "ballot.seqNum", "slotStatus"]);
+
+        let seq_num = slot_schema.field("ballot.seqNum").unwrap();
+        assert_eq!(seq_num.kinds().get(&ValueKind::Number), Some(&3));
+        assert_eq!(seq_num.samples(), &["0", "1", "2"]);
+
+        let status = slot_schema.field("slotStatus").unwrap();
+        assert_eq!(status.count(), 2);
+        assert_eq!(status.samples(), &["CHOSEN"]);
+    }
+
+    #[test]
+    fn infers_collection_item_paths() {
+        let events = log([
+            ("server1", AbstractObject::new("Prepare").with_prop("acceptors", AbstractValue::Collection(vec![
+                AbstractValue::Object(AbstractObject::new("Address").with_prop("name", "server1")),
+                AbstractValue::Object(AbstractObject::new("Address").with_prop("name", "server2")),
+            ]))),
+            ("server2", AbstractObject::new("Prepare").with_prop("acceptors", AbstractValue::Collection(Vec::new()))),
+        ]);
+
+        let schema = LogSchema::infer(&events);
+        let prepare = schema.object("Prepare").expect("Object should be in the schema");
+        assert_eq!(prepare.fields().keys().collect::<Vec<_>>(), vec!["acceptors", "acceptors.0", "acceptors.0.name", "acceptors.1", "acceptors.1.name"]);
+        assert_eq!(prepare.field("acceptors.1.name").unwrap().samples(), &["server2"]);
+    }
+}
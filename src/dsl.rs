@@ -1,3 +1,14 @@
 pub mod query_ast;
 pub mod parser;
-pub mod filters;
\ No newline at end of file
+pub mod filters;
+pub mod aggregate;
+pub mod sequence;
+pub mod join;
+pub mod results;
+pub mod rules;
+pub mod diagnostics;
+pub mod issues;
+pub mod completion;
+pub mod macros;
+pub mod explain;
+pub mod planner;
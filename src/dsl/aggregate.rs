@@ -0,0 +1,262 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
+use crate::ds_events::abstract_object::{AbstractValue, SortKey};
+use crate::ds_events::event::Event;
+use crate::dsl::filters::lookup_event;
+use crate::dsl::query_ast::{Aggregation, EventQuery, GroupKey, PropPath};
+
+/// a table of results produced by summarizing queries
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct QueryTable {
+    /// the label of each column
+    headers: Vec<String>,
+    /// each row, with one cell per column
+    rows: Vec<Vec<String>>,
+}
+
+impl QueryTable {
+    pub fn new<StrT: Into<String>>(headers: impl IntoIterator<Item = StrT>) -> Self {
+        Self {
+            headers: headers.into_iter().map(|header| header.into()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push_row<StrT: Into<String>>(&mut self, row: impl IntoIterator<Item = StrT>) {
+        self.rows.push(row.into_iter().map(|cell| cell.into()).collect());
+    }
+
+    pub fn headers(&self) -> &Vec<String> {
+        &self.headers
+    }
+
+    pub fn rows(&self) -> &Vec<Vec<String>> {
+        &self.rows
+    }
+
+    /// the width of the widest cell in each column, including the header
+    pub fn column_widths(&self) -> Vec<usize> {
+        self.headers.iter()
+            .enumerate()
+            .map(|(idx, header)| {
+                self.rows.iter()
+                    .filter_map(|row| row.get(idx))
+                    .map(|cell| cell.chars().count())
+                    .chain([header.chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+}
+
+impl Display for QueryTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let widths = self.column_widths();
+        let write_row = |f: &mut Formatter<'_>, row: &Vec<String>| {
+            let cells = row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", cells.join("  ").trim_end())
+        };
+
+        write_row(f, &self.headers)?;
+        let separator = widths.iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<_>>();
+        write_row(f, &separator)?;
+        for row in &self.rows {
+            write_row(f, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl EventQuery {
+    /// summarizes the matches of this query as a table, or returns none if this query doesn't summarize events.
+    /// `groups` are the chains or tuples the matches came from, if the query matches events together
+    pub fn summarize(&self, matches: &BTreeSet<usize>, groups: &[Vec<usize>], log: &[Event]) -> Option<QueryTable> {
+        match self {
+            // sequences and joins are summarized by their groups instead, see `EventQuery::run`
+            EventQuery::Find { .. } | EventQuery::Pipeline { .. } | EventQuery::Sequence(_) | EventQuery::Join(_) => None,
+            EventQuery::Exists(_) => {
+                let mut table = QueryTable::new(["exists"]);
+                table.push_row([(!matches.is_empty()).to_string()]);
+                Some(table)
+            }
+            EventQuery::Count(source) => {
+                // sequences and joins count the chains or tuples they found, not the events in them
+                let count = match source.as_ref() {
+                    EventQuery::Sequence(_) | EventQuery::Join(_) => groups.len(),
+                    _ => matches.len(),
+                };
+                let mut table = QueryTable::new(["count"]);
+                table.push_row([count.to_string()]);
+                Some(table)
+            }
+            EventQuery::Aggregate { aggregation, .. } => {
+                let matching_events = matches.iter()
+                    .filter_map(|id| lookup_event(log, *id));
+                Some(aggregation.summarize(matching_events))
+            }
+        }
+    }
+}
+
+impl Aggregation {
+    fn summarize<'ev>(&self, events: impl Iterator<Item = &'ev Event>) -> QueryTable {
+        match self {
+            Aggregation::GroupByCount { key, distinct } => {
+                let mut groups = BTreeMap::<SortKey, HashSet<Option<&AbstractValue>>>::new();
+                let mut counts = BTreeMap::<SortKey, usize>::new();
+                for event in events {
+                    let Some(group) = key.group_of(event) else {
+                        continue;
+                    };
+
+                    match distinct {
+                        None => *counts.entry(group).or_default() += 1,
+                        Some(path) => {
+                            let value = path.lookup_value(event).ok();
+                            groups.entry(group).or_default().insert(value);
+                        }
+                    }
+                }
+
+                let count_header = match distinct {
+                    None => "count".to_string(),
+                    Some(path) => format!("count distinct {}", path),
+                };
+                let mut table = QueryTable::new([key.to_string(), count_header]);
+                if distinct.is_some() {
+                    for (group, values) in groups {
+                        let count = values.into_iter().flatten().count();
+                        table.push_row([group.to_string(), count.to_string()]);
+                    }
+                } else {
+                    for (group, count) in counts {
+                        table.push_row([group.to_string(), count.to_string()]);
+                    }
+                }
+
+                table
+            }
+            Aggregation::Distinct(path) => {
+                // ordered the same way the event list sorts a column, so numbers go in numeric order
+                let mut values = BTreeMap::<SortKey, usize>::new();
+                for event in events {
+                    if let Ok(value) = path.lookup_value(event) {
+                        *values.entry(value.sort_key()).or_default() += 1;
+                    }
+                }
+
+                let mut table = QueryTable::new([path.to_string(), "count".to_string()]);
+                for (value, count) in values {
+                    table.push_row([value.to_string(), count.to_string()]);
+                }
+
+                table
+            }
+            Aggregation::Min(path) => summarize_extreme(path, "min", events, |value, best| value < best),
+            Aggregation::Max(path) => summarize_extreme(path, "max", events, |value, best| value > best),
+        }
+    }
+}
+
+/// finds the numeric value of the path that is preferred over all others, along with the event it came from
+fn summarize_extreme<'ev, PreferT>(path: &PropPath, label: &str, events: impl Iterator<Item = &'ev Event>, prefer: PreferT) -> QueryTable
+    where PreferT: Fn(i64, i64) -> bool
+{
+    let mut best: Option<(i64, usize)> = None;
+    for event in events {
+        let Ok(AbstractValue::Number(value)) = path.lookup_value(event) else {
+            continue;
+        };
+
+        if best.is_none_or(|(best_value, _)| prefer(*value, best_value)) {
+            best = Some((*value, event.id()));
+        }
+    }
+
+    let mut table = QueryTable::new([format!("{}({})", label, path), "event".to_string()]);
+    if let Some((value, id)) = best {
+        table.push_row([value.to_string(), id.to_string()]);
+    }
+
+    table
+}
+
+impl Display for GroupKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupKey::Node => write!(f, "node"),
+            GroupKey::Type => write!(f, "type"),
+            GroupKey::Path(path) => write!(f, "{}", path),
+        }
+    }
+}
+
+impl GroupKey {
+    /// the group an event belongs to, or none if the event doesn't have the key. Groups are
+    /// ordered the same way the event list sorts a column
+    fn group_of<'ev>(&self, event: &'ev Event) -> Option<SortKey<'ev>> {
+        match self {
+            GroupKey::Node => Some(SortKey::Text(Cow::Borrowed(event.originator()))),
+            GroupKey::Type => Some(SortKey::Text(Cow::Borrowed(event.event_obj().name()))),
+            GroupKey::Path(path) => path.lookup_value(event)
+                .ok()
+                .map(AbstractValue::sort_key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds_events::event::Event;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::dsl::filters::EventSequenceQuery;
+    use crate::dsl::parser::parse_event_query;
+
+    fn slot_events() -> Vec<Event> {
+        log((0..6i64).map(|id| (format!("server{}", id % 2), slot_entry(id % 3).with_prop("ballot", id))))
+    }
+
+    fn summarize(query_text: &str, events: &[Event]) -> Vec<Vec<String>> {
+        let (_, query) = parse_event_query(query_text).expect("Parsing should succeed");
+        let matches = query.eval(events).expect("Eval should succeed");
+        let table = query.summarize(&matches, &[], events).expect("Query should produce a table");
+        table.rows().clone()
+    }
+
+    #[test]
+    fn count_and_exists() {
+        let events = slot_events();
+        assert_eq!(summarize("count find any eq(slotNum, 1)", &events), vec![vec!["2"]]);
+        assert_eq!(summarize("exists find any eq(slotNum, 7)", &events), vec![vec!["false"]]);
+    }
+
+    #[test]
+    fn group_by_count() {
+        let events = slot_events();
+        assert_eq!(summarize("find any has(slotNum) | group by node count", &events), vec![vec!["server0", "3"], vec!["server1", "3"]]);
+        assert_eq!(summarize("find any server(0) | group by node count distinct slotNum", &events), vec![vec!["server0", "3"]]);
+    }
+
+    #[test]
+    fn distinct_min_max() {
+        let events = slot_events();
+        assert_eq!(summarize("find any server(1) | distinct slotNum", &events), vec![vec!["0", "1"], vec!["1", "1"], vec!["2", "1"]]);
+        assert_eq!(summarize("find any eq(slotNum, 2) | min ballot", &events), vec![vec!["2", "2"]]);
+        assert_eq!(summarize("find any eq(slotNum, 2) | max ballot", &events), vec![vec!["5", "5"]]);
+    }
+
+    #[test]
+    fn orders_numbers_numerically() {
+        let events = log([9, 10, 9, 100].map(|slot| ("server1", slot_entry(slot))));
+        assert_eq!(summarize("find any has(slotNum) | distinct slotNum", &events), vec![vec!["9", "2"], vec!["10", "1"], vec!["100", "1"]]);
+        assert_eq!(summarize("find any has(slotNum) | group by slotNum count", &events), vec![vec!["9", "2"], vec!["10", "1"], vec!["100", "1"]]);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeSet;
+use crate::ds_events::schema::LogSchema;
+use crate::dsl::diagnostics::expected_at_end;
+use crate::dsl::parser::{parse_editor_text, EXPECTED_EVENT_TYPE, EXPECTED_PATH, EXPECTED_VALUE};
+
+/// the words a partially typed word at the cursor could be completed to
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Completions {
+    /// the part of the word already typed before the cursor
+    prefix: String,
+    /// everything that starts with the prefix, in the order they should be offered
+    candidates: Vec<String>,
+}
+
+impl Completions {
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+}
+
+/// the word being typed at the end of the text: an unclosed string, or a run of name and path characters
+fn typed_prefix(before_cursor: &str) -> &str {
+    if before_cursor.matches('"').count() % 2 == 1 {
+        let quote = before_cursor.rfind('"').unwrap_or_default();
+        return &before_cursor[quote..];
+    }
+
+    let start = before_cursor.char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '.')
+        .last()
+        .map_or(before_cursor.len(), |(idx, _)| idx);
+    &before_cursor[start..]
+}
+
+/// works out what could complete the word being typed at the end of `before_cursor`, from what the
+/// query parser expects after the text before that word
+pub fn complete(before_cursor: &str, schema: &LogSchema) -> Completions {
+    let prefix = typed_prefix(before_cursor);
+    let parsed_text = &before_cursor[..before_cursor.len() - prefix.len()];
+
+    let mut candidates = Vec::<String>::new();
+    for expected in expected_at_end(parsed_text, parse_editor_text) {
+        let scope = &expected.scope;
+        let expected_candidates = match expected.expected.as_str() {
+            EXPECTED_EVENT_TYPE => schema.objects().keys().cloned().collect(),
+            EXPECTED_PATH => paths(schema, scope.event_type.as_deref()),
+            EXPECTED_VALUE => scope.eq_path.as_deref()
+                .map(|path| values(schema, scope.event_type.as_deref(), path))
+                .unwrap_or_default(),
+            // keywords are expected in backticks. Punctuation isn't worth offering
+            other => other.strip_prefix('`')
+                .and_then(|other| other.strip_suffix('`'))
+                .filter(|keyword| keyword.starts_with(char::is_alphabetic) && keyword.chars().all(|c| c.is_alphanumeric() || c == '_'))
+                .map(|keyword| vec![keyword.to_string()])
+                .unwrap_or_default(),
+        };
+
+        for candidate in expected_candidates {
+            if candidate.starts_with(prefix) && candidate != prefix && !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+    }
+
+    Completions { prefix: prefix.to_string(), candidates }
+}
+
+/// every path events of the type have, or every path any event has
+fn paths(schema: &LogSchema, event_type: Option<&str>) -> Vec<String> {
+    match event_type {
+        Some(event_type) => schema.object(event_type)
+            .map(|object| object.fields().keys().cloned().collect())
+            .unwrap_or_default(),
+        None => schema.objects().values()
+            .flat_map(|object| object.fields().keys().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// values seen at the path, quoted the way `eq` expects
+fn values(schema: &LogSchema, event_type: Option<&str>, path: &str) -> Vec<String> {
+    let samples = match event_type {
+        Some(event_type) => schema.object(event_type)
+            .and_then(|object| object.field(path))
+            .map(|field| field.samples().to_vec())
+            .unwrap_or_default(),
+        None => schema.fields_at(path)
+            .flat_map(|(_, field)| field.samples().iter().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    };
+
+    samples.into_iter()
+        .map(|sample| if sample.parse::<i64>().is_ok() { sample } else { format!("\"{}\"", sample) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds_events::abstract_object::AbstractObject;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::ds_events::schema::LogSchema;
+    use crate::dsl::completion::complete;
+
+    fn schema() -> LogSchema {
+        let events = log([
+            ("server1", slot_entry(1).with_prop("slotStatus", "CHOSEN")),
+            ("server1", AbstractObject::new("PaxosRequest").with_prop("sequenceNum", 3i64)),
+        ]);
+        LogSchema::infer(&events)
+    }
+
+    fn candidates(before_cursor: &str) -> Vec<String> {
+        complete(before_cursor, &schema()).candidates().to_vec()
+    }
+
+    #[test]
+    fn completes_event_types() {
+        assert_eq!(candidates("find Paxos"), vec!["PaxosRequest", "PaxosSlotEntry"]);
+        assert_eq!(candidates("find any eq(slotNum, 1) union find "), vec!["any", "PaxosRequest", "PaxosSlotEntry"]);
+    }
+
+    #[test]
+    fn completes_operators_and_keywords() {
+        // `as` names the event for a join
+        assert_eq!(candidates("find any a"), vec!["and", "after", "at_or_after", "at_or_before", "as"]);
+        assert_eq!(candidates("find any and { has(slotNum); n"), vec!["not"]);
+        assert_eq!(candidates("find any has(slotNum) u"), vec!["union"]);
+    }
+
+    #[test]
+    fn completes_paths_for_the_type() {
+        assert_eq!(candidates("find PaxosSlotEntry eq(slot"), vec!["slotNum", "slotStatus"]);
+        assert_eq!(candidates("find PaxosRequest not(has("), vec!["sequenceNum"]);
+        assert_eq!(candidates("find any s"), vec!["server"]);
+    }
+
+    #[test]
+    fn completes_observed_values() {
+        assert_eq!(candidates("find PaxosSlotEntry eq(slotStatus, "), vec!["\"CHOSEN\""]);
+        assert_eq!(candidates("find any or { eq(slotNum, 2); eq(slotStatus, \"CH"), vec!["\"CHOSEN\""]);
+    }
+
+    #[test]
+    fn completes_from_what_the_parser_expects() {
+        assert_eq!(candidates(""), vec!["highlight", "select", "count", "exists", "find"]);
+        assert_eq!(candidates("find any has(slotNum) | "), vec!["find", "group", "distinct", "min", "max"]);
+        assert_eq!(candidates("find any has(slotNum) then any has(slotNum) w"), vec!["within", "with"]);
+        assert_eq!(candidates("highlight chosen red = find PaxosSlotEntry eq("), vec!["slotNum", "slotStatus"]);
+        assert_eq!(candidates("find PaxosSlotEntry as a, PaxosRequest eq("), vec!["sequenceNum"]);
+    }
+}
@@ -0,0 +1,368 @@
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use nom::character::complete::multispace0;
+use nom::error::{ContextError, ErrorKind, FromExternalError, ParseError};
+use nom::IResult;
+
+/// the result type of every query parser
+pub type ParseResult<'a, T> = IResult<&'a str, T, SyntaxError<'a>>;
+
+/// a parse error that remembers the furthest point parsing reached, and what could have come next there
+#[derive(Debug, PartialEq, Eq)]
+pub struct SyntaxError<'a> {
+    /// the input that was left when parsing failed
+    input: &'a str,
+    /// descriptions of what could have come next
+    expected: Vec<String>,
+}
+
+impl<'a> SyntaxError<'a> {
+    pub fn expected<StrT: Into<String>>(input: &'a str, expected: StrT) -> Self {
+        let expected = expected.into();
+        if input.is_empty() {
+            note_expected_at_end(&expected);
+        }
+
+        Self {
+            input,
+            expected: vec![expected],
+        }
+    }
+
+    fn describe_kind(kind: ErrorKind) -> Option<&'static str> {
+        match kind {
+            ErrorKind::Alpha | ErrorKind::AlphaNumeric => Some("a name"),
+            ErrorKind::Digit => Some("a number"),
+            ErrorKind::Eof => Some("end of query"),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> ParseError<&'a str> for SyntaxError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        Self {
+            input,
+            expected: Self::describe_kind(kind).into_iter().map(String::from).collect(),
+        }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        Self::expected(input, format!("`{}`", c))
+    }
+
+    /// keeps whichever error got further, or everything both expected if they failed at the same place
+    fn or(mut self, other: Self) -> Self {
+        if self.input.len() < other.input.len() {
+            return self;
+        }
+
+        if other.input.len() < self.input.len() {
+            return other;
+        }
+
+        for expected in other.expected {
+            if !self.expected.contains(&expected) {
+                self.expected.push(expected);
+            }
+        }
+        self
+    }
+}
+
+impl<'a> ContextError<&'a str> for SyntaxError<'a> {
+    /// describes the error with the context, if the error is for the thing the context describes
+    fn add_context(input: &'a str, ctx: &'static str, other: Self) -> Self {
+        if input.len() == other.input.len() {
+            Self::expected(input, ctx)
+        } else {
+            other
+        }
+    }
+}
+
+impl<'a, ErrT> FromExternalError<&'a str, ErrT> for SyntaxError<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _e: ErrT) -> Self {
+        Self::from_error_kind(input, kind)
+    }
+}
+
+/// matches exactly the given token, and says it was expected if it wasn't found. Tokens that are words
+/// only match whole words, so `eq` doesn't match the start of `eqq`
+pub fn token<'a>(expected: &'static str) -> impl Fn(&'a str) -> ParseResult<'a, &'a str> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    move |input: &'a str| {
+        let remaining = input.strip_prefix(expected)
+            .filter(|remaining| {
+                let ends_word = expected.ends_with(is_word_char);
+                !(ends_word && remaining.starts_with(is_word_char))
+            });
+
+        match remaining {
+            Some(remaining) => Ok((remaining, &input[..expected.len()])),
+            None => Err(nom::Err::Error(SyntaxError::expected(input, format!("`{}`", expected)))),
+        }
+    }
+}
+
+/// where in the query the parser was when it expected something, so completions can be narrowed
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParseScope {
+    /// the event type of the find clause being parsed, or none for `any` or outside of one
+    pub event_type: Option<String>,
+    /// the path of the `eq` whose value is being parsed
+    pub eq_path: Option<String>,
+}
+
+/// something the parser expected at the end of its input, like "`find`" or "a field path"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedAtEnd {
+    pub expected: String,
+    pub scope: ParseScope,
+}
+
+/// what has been expected at the end of the input so far, while collecting it for completion
+#[derive(Default)]
+struct EndCollector {
+    scope: ParseScope,
+    expected: Vec<ExpectedAtEnd>,
+}
+
+thread_local! {
+    /// only set inside of `expected_at_end`, so normal parsing doesn't pay for collecting
+    static END_COLLECTOR: RefCell<Option<EndCollector>> = const { RefCell::new(None) };
+}
+
+fn note_expected_at_end(expected: &str) {
+    END_COLLECTOR.with_borrow_mut(|collector| {
+        let Some(collector) = collector else {
+            return;
+        };
+
+        let expected = ExpectedAtEnd { expected: expected.to_string(), scope: collector.scope.clone() };
+        if !collector.expected.contains(&expected) {
+            collector.expected.push(expected);
+        }
+    });
+}
+
+/// runs the parser over the input and returns everything any branch of it expected once it ran out
+/// of input, including branches that `opt` and `alt` threw away. This is what could come next
+pub fn expected_at_end<'a, ParserT, O>(input: &'a str, mut parser: ParserT) -> Vec<ExpectedAtEnd>
+    where ParserT: FnMut(&'a str) -> ParseResult<'a, O>
+{
+    END_COLLECTOR.with_borrow_mut(|collector| *collector = Some(EndCollector::default()));
+    let _ = parser(input);
+    END_COLLECTOR.with_borrow_mut(|collector| collector.take())
+        .map(|collector| collector.expected)
+        .unwrap_or_default()
+}
+
+/// runs `parser` with the scope changed by `update`, so what it expects at the end of the input is
+/// tagged with it. Does nothing unless collecting for `expected_at_end`
+pub fn in_scope<'a, UpdateT, ParserT, O>(update: UpdateT, mut parser: ParserT) -> impl FnMut(&'a str) -> ParseResult<'a, O>
+    where UpdateT: Fn(&mut ParseScope),
+          ParserT: FnMut(&'a str) -> ParseResult<'a, O>
+{
+    move |input: &'a str| {
+        let outer_scope = END_COLLECTOR.with_borrow_mut(|collector| {
+            collector.as_mut().map(|collector| {
+                let outer_scope = collector.scope.clone();
+                update(&mut collector.scope);
+                outer_scope
+            })
+        });
+
+        let result = parser(input);
+        if let Some(outer_scope) = outer_scope {
+            END_COLLECTOR.with_borrow_mut(|collector| {
+                if let Some(collector) = collector {
+                    collector.scope = outer_scope;
+                }
+            });
+        }
+
+        result
+    }
+}
+
+/// a syntax error located in the text that was parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryDiagnostic {
+    /// what went wrong
+    message: String,
+    /// the 1-based line the error is on
+    line: usize,
+    /// the 1-based column, in characters, the error starts at
+    column: usize,
+    /// the byte range of the offending text
+    span: Range<usize>,
+    /// the text of the line the error is on
+    line_text: String,
+}
+
+impl QueryDiagnostic {
+    fn new<StrT: Into<String>>(source: &str, offset: usize, message: StrT) -> Self {
+        // the span covers the rest of the offending token
+        let token_len = source[offset..]
+            .char_indices()
+            .find(|(idx, c)| *idx > 0 && (c.is_whitespace() || "(){};,|".contains(*c)))
+            .map_or(source.len() - offset, |(idx, _)| idx);
+        Self::at(source, offset..offset + token_len, message)
+    }
+
+    /// an error about the given byte range of the source. The span is cut off at the end of the line
+    /// it starts on
+    pub fn at<StrT: Into<String>>(source: &str, span: Range<usize>, message: StrT) -> Self {
+        let offset = span.start;
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        let column = source[line_start..offset].chars().count() + 1;
+        let line_text = source[line_start..]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        let span_len = source[span]
+            .lines()
+            .next()
+            .map_or(0, |spanned| spanned.len());
+
+        Self {
+            message: message.into(),
+            line,
+            column,
+            span: offset..offset + span_len,
+            line_text,
+        }
+    }
+
+    fn from_syntax_error(source: &str, error: SyntaxError) -> Self {
+        let offset = source.len() - error.input.len();
+        let found = match source[offset..].chars().next() {
+            None => "end of query".to_string(),
+            Some(_) => {
+                let found = Self::new(source, offset, "");
+                format!("`{}`", &source[found.span])
+            }
+        };
+
+        let message = match error.expected.as_slice() {
+            [] => format!("unexpected {}", found),
+            [expected] => format!("expected {}, found {}", expected, found),
+            expected => format!("expected one of {}, found {}", expected.join(", "), found),
+        };
+
+        Self::new(source, offset, message)
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn span(&self) -> &Range<usize> {
+        &self.span
+    }
+
+    pub fn line_text(&self) -> &str {
+        &self.line_text
+    }
+
+    /// the byte range of the offending text within the line it is on
+    pub fn line_span(&self) -> Range<usize> {
+        let start = self.line_text.char_indices()
+            .nth(self.column - 1)
+            .map_or(self.line_text.len(), |(idx, _)| idx);
+        let end = (start + self.span.len()).min(self.line_text.len());
+        start..end
+    }
+
+    /// a line with carets under the offending span
+    pub fn caret_line(&self) -> String {
+        let span_width = self.line_text[self.line_span()].chars().count().max(1);
+        format!("{}{}", " ".repeat(self.column - 1), "^".repeat(span_width))
+    }
+}
+
+impl Display for QueryDiagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} at line {} col {}", self.message, self.line, self.column)?;
+        writeln!(f, "{}", self.line_text)?;
+        write!(f, "{}", self.caret_line())
+    }
+}
+
+/// runs the parser over all the input, describing where it failed if it couldn't parse all of it
+pub fn parse_complete<'a, T, ParserT>(input: &'a str, mut parser: ParserT) -> Result<T, QueryDiagnostic>
+    where ParserT: FnMut(&'a str) -> ParseResult<'a, T>
+{
+    match parser(input) {
+        Ok((remaining, parsed)) => {
+            let (remaining, _) = multispace0::<&str, SyntaxError>(remaining)
+                .expect("whitespace parsing never fails");
+            if remaining.is_empty() {
+                Ok(parsed)
+            } else {
+                let error = SyntaxError::expected(remaining, "end of query");
+                Err(QueryDiagnostic::from_syntax_error(input, error))
+            }
+        }
+        Err(nom::Err::Error(error)) | Err(nom::Err::Failure(error)) => {
+            Err(QueryDiagnostic::from_syntax_error(input, error))
+        }
+        Err(nom::Err::Incomplete(_)) => {
+            Err(QueryDiagnostic::new(input, input.len(), "unexpected end of query"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dsl::parser::parse_statement;
+
+    #[test]
+    fn reports_line_and_column() {
+        let query = "find any and {\n    eq(slotNum, 3;\n}";
+        let diagnostic = parse_statement(query).expect_err("Parsing should fail");
+        assert_eq!(diagnostic.line(), 2);
+        assert_eq!(diagnostic.column(), 18);
+        assert_eq!(diagnostic.message(), "expected `)`, found `;`");
+        assert_eq!(diagnostic.caret_line(), "                 ^");
+    }
+
+    #[test]
+    fn reports_trailing_garbage() {
+        let diagnostic = parse_statement("find any has(slotNum) garbage").expect_err("Parsing should fail");
+        assert_eq!(diagnostic.column(), 23);
+        assert_eq!(&"find any has(slotNum) garbage"[diagnostic.span().clone()], "garbage");
+        assert!(diagnostic.message().starts_with("expected"), "{}", diagnostic.message());
+    }
+
+    #[test]
+    fn carets_count_characters() {
+        let diagnostic = parse_statement("find any has(slotNum) évé").expect_err("Parsing should fail");
+        assert_eq!(diagnostic.caret_line(), format!("{}^^^", " ".repeat(22)));
+    }
+
+    #[test]
+    fn reports_bad_operator() {
+        let diagnostic = parse_statement("find any eqq(slotNum, 3)").expect_err("Parsing should fail");
+        assert_eq!(diagnostic.column(), 10);
+        assert!(diagnostic.message().contains("`has`"), "{}", diagnostic.message());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::ds_events::event::{format_timestamp, Event};
+use crate::dsl::filters::{lookup_event, EventFilter, EventFilterError};
+use crate::dsl::query_ast::{EventNameFilter, EventQuery, FindEventNode, Operator};
+
+/// one step of evaluating a query against an event, with the steps it is made of
+#[derive(Debug)]
+pub struct ExplainNode {
+    /// the part of the query this evaluates, like `eq(slotNum, 3)`
+    label: String,
+    /// if this part accepted the event, or why it couldn't tell
+    outcome: Result<bool, EventFilterError>,
+    /// what was looked at to decide, like the value found at a path
+    detail: Option<String>,
+    children: Vec<ExplainNode>,
+}
+
+impl ExplainNode {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn outcome(&self) -> &Result<bool, EventFilterError> {
+        &self.outcome
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    pub fn children(&self) -> &[ExplainNode] {
+        &self.children
+    }
+}
+
+impl EventQuery {
+    /// explains how each find clause of this query evaluates against the event
+    pub fn explain(&self, event: &Event, log: &[Event]) -> Vec<ExplainNode> {
+        self.find_nodes().into_iter()
+            .map(|node| node.explain(event, log))
+            .collect()
+    }
+}
+
+impl FindEventNode {
+    pub fn explain(&self, event: &Event, log: &[Event]) -> ExplainNode {
+        let (type_label, find_label) = match &self.event_type {
+            EventNameFilter::Any => ("any".to_string(), "find any".to_string()),
+            EventNameFilter::Named(name) => (format!("type {}", name), format!("find {}", name)),
+        };
+
+        let type_node = ExplainNode {
+            label: type_label,
+            outcome: self.event_type.test(event, log),
+            detail: Some(format!("event is {}", event.event_obj().name())),
+            children: Vec::new(),
+        };
+
+        ExplainNode {
+            label: find_label,
+            outcome: self.test(event, log),
+            detail: None,
+            children: vec![type_node, self.operator.explain(event, log)],
+        }
+    }
+}
+
+impl Operator {
+    /// evaluates every part of this operator against the event. Unlike [`EventFilter::test`], parts
+    /// after the one that decided the result are still evaluated, so they can be shown too
+    pub fn explain(&self, event: &Event, log: &[Event]) -> ExplainNode {
+        let children = match self {
+            Operator::Not(op) => vec![op.explain(event, log)],
+            Operator::And(ops) | Operator::Or(ops) => ops.iter()
+                .map(|op| op.explain(event, log))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        ExplainNode {
+            label: describe_operator(self),
+            outcome: self.test(event, log),
+            detail: self.explain_detail(event, log),
+            children,
+        }
+    }
+
+    /// what the operator looked at in the event
+    fn explain_detail(&self, event: &Event, log: &[Event]) -> Option<String> {
+        let detail = match self {
+            Operator::Eq { prop_name, .. } | Operator::Has(prop_name) => match prop_name.lookup_value(event) {
+                Ok(value) => format!("{} is {} ({})", prop_name, value, value.kind()),
+                Err(_) => format!("{} not found", prop_name),
+            },
+            Operator::Server(_) => format!("from {}", event.originator()),
+            Operator::After(_) | Operator::Before(_) => format!("event id is {}", event.id()),
+            Operator::AtOrAfter(_) | Operator::AtOrBefore(_) | Operator::BetweenTimes { .. } => {
+                format!("logged at {}", format_timestamp(event.time()))
+            }
+            Operator::Within { anchor, .. } => match lookup_event(log, *anchor) {
+                Some(anchor_event) => format!("{} from event {}", event.time() - anchor_event.time(), anchor),
+                None => format!("event {} is not in the log", anchor),
+            },
+            Operator::Not(_) | Operator::And(_) | Operator::Or(_) => return None,
+        };
+
+        Some(detail)
+    }
+}
+
+/// writes an operator back out the way it would be written in a query. Operators that contain
+/// others are written as just their name
+pub(crate) fn describe_operator(operator: &Operator) -> String {
+    match operator {
+        Operator::Eq { prop_name, comparison } if comparison.parse::<i64>().is_ok() => {
+            format!("eq({}, {})", prop_name, comparison)
+        }
+        Operator::Eq { prop_name, comparison } => format!("eq({}, \"{}\")", prop_name, comparison),
+        Operator::Has(prop_name) => format!("has({})", prop_name),
+        Operator::Server(server_id) => format!("server({})", server_id),
+        Operator::After(after) => format!("after({})", after),
+        Operator::Before(before) => format!("before({})", before),
+        Operator::AtOrAfter(start) => format!("at_or_after(\"{}\")", format_timestamp(*start)),
+        Operator::AtOrBefore(end) => format!("at_or_before(\"{}\")", format_timestamp(*end)),
+        Operator::BetweenTimes { start, end } => {
+            format!("between_times(\"{}\", \"{}\")", format_timestamp(*start), format_timestamp(*end))
+        }
+        Operator::Within { window, anchor } => format!("within({}, of: {})", window, anchor),
+        Operator::Not(_) => "not".to_string(),
+        Operator::And(_) => "and".to_string(),
+        Operator::Or(_) => "or".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::dsl::filters::EventFilterError;
+    use crate::dsl::parser::parse_event_query;
+
+    #[test]
+    fn explains_every_branch() {
+        let events = log([("server1", slot_entry(3))]);
+        let (_, query) = parse_event_query("find PaxosSlotEntry or { eq(slotNum, 4); and { server(1); eq(slotStatus, \"CHOSEN\") } }")
+            .expect("Parsing should succeed");
+
+        let explained = query.explain(&events[0], &events);
+        assert_eq!(explained.len(), 1);
+        let find_node = &explained[0];
+        assert!(matches!(find_node.outcome(), Err(EventFilterError::KeyNotFound)));
+
+        let or_node = &find_node.children()[1];
+        assert_eq!(or_node.label(), "or");
+        let eq_node = &or_node.children()[0];
+        assert_eq!(eq_node.label(), "eq(slotNum, 4)");
+        assert!(matches!(eq_node.outcome(), Ok(false)));
+        assert_eq!(eq_node.detail(), Some("slotNum is 3 (numeric)"));
+
+        let and_node = &or_node.children()[1];
+        assert!(matches!(and_node.children()[0].outcome(), Ok(true)));
+        assert_eq!(and_node.children()[0].detail(), Some("from server1"));
+        assert!(matches!(and_node.children()[1].outcome(), Err(EventFilterError::KeyNotFound)));
+        assert_eq!(and_node.children()[1].detail(), Some("slotStatus not found"));
+    }
+}
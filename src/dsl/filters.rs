@@ -1,16 +1,39 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
 use crate::ds_events::event::Event;
-use crate::dsl::query_ast::{EventNameFilter, FindEventNode, Operator};
+use crate::dsl::issues::MismatchCounts;
+use crate::dsl::join::MAX_JOIN_TUPLES;
+use crate::dsl::query_ast::{EventNameFilter, EventQuery, FindEventNode, Operator, SetCombinator};
 
+#[derive(Debug)]
 pub enum EventFilterError {
     KeyNotFound,
     MismatchTypes,
+    /// a query referenced an event id that is not in the log
+    EventNotFound,
+    /// a join condition referred to an alias that was never bound
+    UnknownAlias,
+    /// a join found more tuples than it keeps, see `MAX_JOIN_TUPLES`
+    TooManyTuples,
+}
+
+impl Display for EventFilterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventFilterError::KeyNotFound => write!(f, "field not found"),
+            EventFilterError::MismatchTypes => write!(f, "value has the wrong type"),
+            EventFilterError::EventNotFound => write!(f, "event id is not in the log"),
+            EventFilterError::UnknownAlias => write!(f, "condition refers to an alias that was never bound"),
+            EventFilterError::TooManyTuples => write!(f, "join found more than {} tuples, add conditions to narrow it", MAX_JOIN_TUPLES),
+        }
+    }
 }
 
 /// determines if we can accept an event
 pub trait EventFilter {
-    /// test an event. Returns true or false if it can be accepted, or an error if the query is bad
-    fn test(&self, event: &Event) -> Result<bool, EventFilterError>;
+    /// test an event. Returns true or false if it can be accepted, or an error if the query is bad.
+    /// `log` is the entire event log, used for filters that refer to other events by id
+    fn test(&self, event: &Event, log: &[Event]) -> Result<bool, EventFilterError>;
 }
 
 pub trait EventSequenceQuery {
@@ -20,7 +43,7 @@ pub trait EventSequenceQuery {
 }
 
 impl EventFilter for Operator {
-    fn test(&self, event: &Event) -> Result<bool, EventFilterError> {
+    fn test(&self, event: &Event, log: &[Event]) -> Result<bool, EventFilterError> {
         match self {
             Operator::Eq { prop_name, comparison } => {
                 prop_name.lookup_value(event)
@@ -35,7 +58,7 @@ impl EventFilter for Operator {
             
             Operator::Server(server_id) => {
                 let server_name = format!("server{}", server_id);
-                Ok(event.originator() == &server_name)
+                Ok(event.originator() == server_name)
             }
             Operator::After(after) => {
                 Ok(event.id() >= *after)
@@ -43,15 +66,30 @@ impl EventFilter for Operator {
             Operator::Before(before) => {
                 Ok(event.id() < *before)
             }
+            Operator::AtOrAfter(start) => {
+                Ok(event.time() >= *start)
+            }
+            Operator::AtOrBefore(end) => {
+                Ok(event.time() <= *end)
+            }
+            Operator::BetweenTimes { start, end } => {
+                Ok(*start <= event.time() && event.time() <= *end)
+            }
+            Operator::Within { window, anchor } => {
+                let anchor_time = lookup_event(log, *anchor)
+                    .ok_or(EventFilterError::EventNotFound)?
+                    .time();
+                Ok((event.time() - anchor_time).abs() <= *window)
+            }
             
             
             Operator::Not(op) => {
-                op.test(event)
+                op.test(event, log)
                     .map(|val| !val)
             }
             Operator::And(ops) => {
                 for op in ops {
-                    if !op.test(event)? {
+                    if !op.test(event, log)? {
                         return Ok(false);
                     }
                 }
@@ -60,7 +98,7 @@ impl EventFilter for Operator {
             }
             Operator::Or(ops) => {
                 for op in ops {
-                    if op.test(event)? {
+                    if op.test(event, log)? {
                         return Ok(true)
                     }
                 }
@@ -71,8 +109,42 @@ impl EventFilter for Operator {
     }
 }
 
+impl Operator {
+    /// the comparison that made [`EventFilter::test`] fail for this event, following the same
+    /// order it tests in. `None` if the test doesn't fail
+    pub(crate) fn failed_comparison(&self, event: &Event, log: &[Event]) -> Option<&Operator> {
+        match self {
+            Operator::Eq { .. } => self.test(event, log).err().map(|_| self),
+            Operator::Not(op) => op.failed_comparison(event, log),
+            Operator::And(ops) => {
+                for op in ops {
+                    match op.test(event, log) {
+                        Ok(true) => {}
+                        Ok(false) => return None,
+                        Err(_) => return op.failed_comparison(event, log),
+                    }
+                }
+
+                None
+            }
+            Operator::Or(ops) => {
+                for op in ops {
+                    match op.test(event, log) {
+                        Ok(false) => {}
+                        Ok(true) => return None,
+                        Err(_) => return op.failed_comparison(event, log),
+                    }
+                }
+
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
 impl EventFilter for EventNameFilter {
-    fn test(&self, event: &Event) -> Result<bool, EventFilterError> {
+    fn test(&self, event: &Event, _log: &[Event]) -> Result<bool, EventFilterError> {
         match self {
             EventNameFilter::Any => Ok(true),
             EventNameFilter::Named(event_name) => Ok(event.event_obj().name() == event_name)
@@ -80,36 +152,175 @@ impl EventFilter for EventNameFilter {
     }
 }
 
-impl EventSequenceQuery for FindEventNode {
-    type ResT = HashSet<usize>;
+impl EventFilter for FindEventNode {
+    fn test(&self, event: &Event, log: &[Event]) -> Result<bool, EventFilterError> {
+        Ok(self.event_type.test(event, log)? && self.operator.test(event, log)?)
+    }
+}
 
-    fn eval(&self, events: &[Event]) -> Result<Self::ResT, EventFilterError> {
+impl FindEventNode {
+    /// finds all events in `candidates` that match this node. `log` is the entire event log. Events
+    /// with values the node couldn't compare are counted in `mismatches`
+    pub fn eval_candidates<'ev, IterT>(&self, candidates: IterT, log: &[Event], mismatches: &mut MismatchCounts) -> Result<HashSet<usize>, EventFilterError>
+        where IterT: IntoIterator<Item = &'ev Event>
+    {
         let mut matches = HashSet::<usize>::new();
-        for event in events {
-            match self.event_type.test(event) {
-                Ok(passes) => {
-                    if !passes {
-                        continue;
-                    }
-                }
-                Err(_) => {
-                    // TODO use this error somehow
-                    continue;
-                }
+        for event in candidates {
+            if !self.event_type.test(event, log)? {
+                continue;
             }
             
-            match self.operator.test(event) {
+            match self.operator.test(event, log) {
                 Ok(passes) => {
                     if passes {
                         matches.insert(event.id());
                     }
                 }
-                Err(_) => {
-                    // TODO use this somehow
+                Err(EventFilterError::KeyNotFound) => {
+                    // events missing the field don't match
                 }
+                Err(EventFilterError::MismatchTypes) => {
+                    // neither do events with a value we can't compare, but they're counted so the
+                    // user can be told about them
+                    if let Some(comparison) = self.operator.failed_comparison(event, log) {
+                        mismatches.record(comparison, event);
+                    }
+                }
+                Err(err) => return Err(err),
             }
         }
         
         Ok(matches)
     }
 }
+
+impl EventSequenceQuery for FindEventNode {
+    type ResT = HashSet<usize>;
+
+    fn eval(&self, events: &[Event]) -> Result<Self::ResT, EventFilterError> {
+        self.eval_candidates(events, events, &mut MismatchCounts::default())
+    }
+}
+
+impl EventQuery {
+    /// finds all events in `candidates` that match this query. `log` is the entire event log. Events
+    /// with values the query couldn't compare are counted in `mismatches`
+    pub fn eval_candidates(&self, candidates: &[&Event], log: &[Event], mismatches: &mut MismatchCounts) -> Result<BTreeSet<usize>, EventFilterError> {
+        match self {
+            EventQuery::Find { queries } => {
+                combine_find_matches(queries, |query| query.eval_candidates(candidates.iter().copied(), log, mismatches))
+            }
+            EventQuery::Pipeline { stages } => {
+                let Some((first_stage, rest)) = stages.split_first() else {
+                    return Ok(BTreeSet::new());
+                };
+                
+                let matches = first_stage.eval_candidates(candidates, log, mismatches)?;
+                eval_later_stages(rest, matches, log, mismatches)
+            }
+            EventQuery::Sequence(sequence) => {
+                let chains = sequence.find_chains(candidates, log)?;
+                Ok(chains.into_iter().flatten().collect())
+            }
+            EventQuery::Join(join) => {
+                let tuples = join.find_tuples(candidates, log, mismatches)?;
+                Ok(tuples.into_iter().flatten().collect())
+            }
+            EventQuery::Exists(source) | EventQuery::Count(source) | EventQuery::Aggregate { source, .. } => {
+                source.eval_candidates(candidates, log, mismatches)
+            }
+        }
+    }
+}
+
+impl EventSequenceQuery for EventQuery {
+    type ResT = BTreeSet<usize>;
+
+    fn eval(&self, events: &[Event]) -> Result<Self::ResT, EventFilterError> {
+        let candidates = events.iter().collect::<Vec<_>>();
+        self.eval_candidates(&candidates, events, &mut MismatchCounts::default())
+    }
+}
+
+/// combines the matches of each find clause with the clause's set combinator
+pub(crate) fn combine_find_matches<EvalT>(queries: &[FindEventNode], mut eval: EvalT) -> Result<BTreeSet<usize>, EventFilterError>
+    where EvalT: FnMut(&FindEventNode) -> Result<HashSet<usize>, EventFilterError>
+{
+    let mut matches = BTreeSet::<usize>::new();
+    for (idx, query) in queries.iter().enumerate() {
+        let query_matches = eval(query)?;
+        if idx == 0 {
+            matches.extend(query_matches);
+            continue;
+        }
+        
+        match query.combinator {
+            SetCombinator::Union => matches.extend(query_matches),
+            SetCombinator::Intersect => matches.retain(|id| query_matches.contains(id)),
+            SetCombinator::Except => matches.retain(|id| !query_matches.contains(id)),
+        }
+    }
+    
+    Ok(matches)
+}
+
+/// runs each stage of a pipeline over the matches of the stage before it
+pub(crate) fn eval_later_stages(stages: &[EventQuery], mut matches: BTreeSet<usize>, log: &[Event], mismatches: &mut MismatchCounts) -> Result<BTreeSet<usize>, EventFilterError> {
+    for stage in stages {
+        let stage_candidates = matches.iter()
+            .filter_map(|id| lookup_event(log, *id))
+            .collect::<Vec<_>>();
+        matches = stage.eval_candidates(&stage_candidates, log, mismatches)?;
+    }
+    
+    Ok(matches)
+}
+
+/// finds the event with the given id in the log
+pub fn lookup_event(log: &[Event], id: usize) -> Option<&Event> {
+    log.binary_search_by_key(&id, |ev| ev.id())
+        .ok()
+        .map(|idx| &log[idx])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds_events::abstract_object::{AbstractObject, AbstractValue};
+    use crate::ds_events::event::Event;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::dsl::filters::EventSequenceQuery;
+    use crate::dsl::parser::parse_event_query;
+
+    fn slot_events() -> Vec<Event> {
+        log((0..6i64).map(|id| (format!("server{}", id % 2), slot_entry(id % 3))))
+    }
+
+    #[test]
+    fn eval_combined_find_clauses() {
+        let events = slot_events();
+        let (_, query) = parse_event_query("find any eq(slotNum, 1) union find any eq(slotNum, 2) except find any server(0)")
+            .expect("Parsing should succeed");
+        let matches = query.eval(&events).expect("Eval should succeed");
+        assert_eq!(matches.into_iter().collect::<Vec<_>>(), vec![1, 5]);
+    }
+    
+    #[test]
+    fn eval_pipeline() {
+        let events = slot_events();
+        let (_, query) = parse_event_query("find any server(1) | find any eq(slotNum, 0) union find any eq(slotNum, 2)")
+            .expect("Parsing should succeed");
+        let matches = query.eval(&events).expect("Eval should succeed");
+        assert_eq!(matches.into_iter().collect::<Vec<_>>(), vec![3, 5]);
+    }
+
+    #[test]
+    fn eval_collection_items() {
+        let events = log([
+            ("server0", AbstractObject::new("Prepare").with_prop("acceptors", vec![AbstractValue::from("server1"), AbstractValue::from("server2")])),
+            ("server1", AbstractObject::new("Prepare").with_prop("acceptors", vec![AbstractValue::from("server2")])),
+        ]);
+        let (_, query) = parse_event_query("find Prepare eq(acceptors.1, \"server2\")").expect("Parsing should succeed");
+        let matches = query.eval(&events).expect("Eval should succeed");
+        assert_eq!(matches.into_iter().collect::<Vec<_>>(), vec![0]);
+    }
+}
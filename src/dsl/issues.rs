@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use crate::ds_events::abstract_object::ValueKind;
+use crate::ds_events::event::Event;
+use crate::ds_events::schema::LogSchema;
+use crate::dsl::explain::describe_operator;
+use crate::dsl::query_ast::{EventNameFilter, EventQuery, FindEventNode, Operator};
+
+/// a likely mistake in a query, found by checking its comparisons against the schema of the log or
+/// while running it. Events that can't be compared never match, so these explain why a query
+/// matched less than expected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryIssue {
+    /// the part of the query with the problem, like `eq(slotNum, "abc")`
+    source: String,
+    /// what is wrong with it
+    message: String,
+}
+
+impl QueryIssue {
+    fn new<SourceT: Into<String>, MsgT: Into<String>>(source: SourceT, message: MsgT) -> Self {
+        Self {
+            source: source.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for QueryIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.source, self.message)
+    }
+}
+
+/// how many events each comparison couldn't compare while a query ran, by the path and the kind of
+/// value the events had there
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MismatchCounts {
+    /// the count for each comparison, like `eq(slotNum, "abc")`, path and kind of value
+    counts: BTreeMap<(String, String, ValueKind), usize>,
+}
+
+impl MismatchCounts {
+    /// counts an event whose value the comparison couldn't compare. Events without the field
+    /// aren't counted: most queries look at fields only some events have, and a field no event
+    /// has is already caught by [`EventQuery::find_issues`]
+    pub(crate) fn record(&mut self, comparison: &Operator, event: &Event) {
+        let Operator::Eq { prop_name, .. } = comparison else {
+            return;
+        };
+        let Ok(value) = prop_name.lookup_value(event) else {
+            return;
+        };
+
+        let key = (describe_operator(comparison), prop_name.to_string(), value.kind());
+        *self.counts.entry(key).or_default() += 1;
+    }
+
+    /// describes each comparison that events couldn't be compared by, like
+    /// `eq(slotNum, "abc"): 1532 events have numeric slotNum`
+    pub fn issues(&self) -> Vec<QueryIssue> {
+        self.counts.iter()
+            .map(|((source, path, kind), count)| QueryIssue::new(source.as_str(), format!("{} events have {} {}", count, kind, path)))
+            .collect()
+    }
+}
+
+impl EventQuery {
+    /// checks each comparison in this query against the schema of the log, and describes the ones
+    /// that point at types or fields no event has. Values of the wrong type are counted while the
+    /// query runs instead, since only the events it compares matter
+    pub fn find_issues(&self, schema: &LogSchema) -> Vec<QueryIssue> {
+        let mut issues = Vec::<QueryIssue>::new();
+        for node in self.find_nodes() {
+            for issue in node.find_issues(schema) {
+                if !issues.contains(&issue) {
+                    issues.push(issue);
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// every find node in this query, including those in sequences and joins
+    pub(crate) fn find_nodes(&self) -> Vec<&FindEventNode> {
+        match self {
+            EventQuery::Find { queries } => queries.iter().collect(),
+            EventQuery::Pipeline { stages } => stages.iter()
+                .flat_map(|stage| stage.find_nodes())
+                .collect(),
+            EventQuery::Sequence(sequence) => [&sequence.first].into_iter()
+                .chain(sequence.steps.iter().map(|step| &step.node))
+                .collect(),
+            EventQuery::Join(join) => join.bindings.iter()
+                .map(|binding| &binding.node)
+                .collect(),
+            EventQuery::Exists(source) | EventQuery::Count(source) | EventQuery::Aggregate { source, .. } => {
+                source.find_nodes()
+            }
+        }
+    }
+}
+
+impl FindEventNode {
+    fn find_issues(&self, schema: &LogSchema) -> Vec<QueryIssue> {
+        if let EventNameFilter::Named(name) = &self.event_type {
+            if schema.object(name).is_none() {
+                return vec![QueryIssue::new(name.as_str(), format!("no events have type {}", name))];
+            }
+        }
+
+        let type_description = match &self.event_type {
+            EventNameFilter::Any => "event".to_string(),
+            EventNameFilter::Named(name) => format!("{} event", name),
+        };
+
+        let mut issues = Vec::new();
+        for comparison in comparisons(&self.operator) {
+            let path = match comparison {
+                Operator::Eq { prop_name, .. } | Operator::Has(prop_name) => prop_name.to_string(),
+                _ => unreachable!("only eq and has are comparisons"),
+            };
+
+            let fields = match &self.event_type {
+                EventNameFilter::Any => schema.fields_at(&path)
+                    .map(|(_, field)| field)
+                    .collect::<Vec<_>>(),
+                EventNameFilter::Named(name) => schema.object(name)
+                    .and_then(|object| object.field(&path))
+                    .into_iter()
+                    .collect(),
+            };
+
+            // a path no event has is most likely a typo, rather than a query with no matches
+            if fields.is_empty() {
+                let source = describe_operator(comparison);
+                issues.push(QueryIssue::new(source.as_str(), format!("no {} has a field {}", type_description, path)));
+            }
+        }
+
+        issues
+    }
+}
+
+/// the comparisons against event fields in an operator
+fn comparisons(operator: &Operator) -> Vec<&Operator> {
+    match operator {
+        Operator::Eq { .. } | Operator::Has(_) => vec![operator],
+        Operator::Not(inner) => comparisons(inner),
+        Operator::And(operators) | Operator::Or(operators) => operators.iter()
+            .flat_map(comparisons)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use crate::ds_events::event::Event;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::ds_events::index::EventIndex;
+    use crate::ds_events::schema::LogSchema;
+    use crate::dsl::parser::parse_event_query;
+    use crate::dsl::results::QueryResults;
+
+    fn slot_events() -> Vec<Event> {
+        log((0..4i64).map(|id| ("server1", slot_entry(id).with_prop("slotStatus", "CHOSEN"))))
+    }
+
+    fn issues_of(query: &str) -> Vec<String> {
+        let (_, query) = parse_event_query(query).expect("Parsing should succeed");
+        query.find_issues(&LogSchema::infer(&slot_events())).iter()
+            .map(|issue| issue.to_string())
+            .collect()
+    }
+
+    fn run_issues_of(results: QueryResults) -> Vec<String> {
+        results.issues().iter()
+            .map(|issue| issue.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reports_type_mismatches() {
+        let events = slot_events();
+        let index = EventIndex::build(&events, &LogSchema::infer(&events));
+        let (_, query) = parse_event_query("find any eq(slotNum, \"abc\")").expect("Parsing should succeed");
+        let expected = vec!["eq(slotNum, \"abc\"): 4 events have numeric slotNum"];
+        assert_eq!(run_issues_of(query.run(&events).expect("Eval should succeed")), expected);
+        // the index can't leave out the events that can't be compared, or they wouldn't be counted
+        assert_eq!(run_issues_of(query.run_indexed(&events, &index).expect("Eval should succeed")), expected);
+        assert!(issues_of("find any eq(slotNum, \"abc\")").is_empty());
+    }
+
+    #[test]
+    fn counts_only_compared_events() {
+        let events = slot_events();
+        let index = EventIndex::build(&events, &LogSchema::infer(&events));
+        let run = |query: &str| {
+            let (_, query) = parse_event_query(query).expect("Parsing should succeed");
+            run_issues_of(query.run_indexed(&events, &index).expect("Eval should succeed"))
+        };
+
+        // later stages only compare the events earlier stages matched
+        assert_eq!(run("find any eq(slotNum, 2) | find any eq(slotNum, \"abc\")"),
+                   vec!["eq(slotNum, \"abc\"): 1 events have numeric slotNum"]);
+        // and stops at the first comparison that fails
+        assert_eq!(run("find any and { eq(slotNum, 1); eq(slotNum, \"abc\") }"),
+                   vec!["eq(slotNum, \"abc\"): 1 events have numeric slotNum"]);
+
+        // refining an earlier query only compares the events it matched
+        let (_, query) = parse_event_query("find any eq(slotNum, \"abc\")").expect("Parsing should succeed");
+        let within = BTreeSet::from([1, 3]);
+        let results = query.run_in_pieces(&events, &index, Some(&within), 1, |_, _| true)
+            .expect("Eval should succeed")
+            .expect("The search wasn't stopped");
+        assert_eq!(run_issues_of(results), vec!["eq(slotNum, \"abc\"): 2 events have numeric slotNum"]);
+    }
+
+    #[test]
+    fn reports_unknown_paths() {
+        assert_eq!(issues_of("find PaxosSlotEntry and { has(slotNum); not(eq(slotNm, 3)) }"),
+                   vec!["eq(slotNm, 3): no PaxosSlotEntry event has a field slotNm"]);
+        assert_eq!(issues_of("find PaxosSlotEntri has(slotNum)"), vec!["PaxosSlotEntri: no events have type PaxosSlotEntri"]);
+    }
+
+    #[test]
+    fn no_issues_for_zero_matches() {
+        assert!(issues_of("find any eq(slotNum, 12) | find any eq(slotStatus, \"PROPOSED\")").is_empty());
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use crate::ds_events::abstract_object::AbstractValue;
+use crate::ds_events::event::Event;
+use crate::dsl::aggregate::QueryTable;
+use crate::dsl::filters::{EventFilterError, EventSequenceQuery};
+use crate::dsl::issues::MismatchCounts;
+use crate::dsl::query_ast::{JoinComparison, JoinCondition, JoinOperand, JoinQuery};
+
+/// the most tuples a join finds before giving up. Every binding can multiply the number of tuples,
+/// so a join with loose conditions fails instead of filling memory
+pub const MAX_JOIN_TUPLES: usize = 100_000;
+
+/// an equality between an operand of the binding being joined and an operand of the bindings before it
+struct JoinKey<'q> {
+    /// refers to the bindings before it
+    bound: &'q JoinOperand,
+    /// refers to the binding being joined
+    joined: &'q JoinOperand,
+}
+
+/// an ordering between the id of the binding being joined and the id of a binding before it, like
+/// `id(a) < id(b)`. Buckets are sorted by id, so these narrow each bucket to a range
+struct IdBound {
+    /// the binding before it
+    bound: usize,
+    /// how the joined id compares to the bound id
+    comparison: JoinComparison,
+}
+
+/// the events that can be bound to one binding, along with how to find the ones that join onto a
+/// partial tuple
+struct BindingIndex<'a> {
+    join_keys: Vec<JoinKey<'a>>,
+    id_bounds: Vec<IdBound>,
+    /// conditions that can first be checked once this binding is bound
+    conditions: Vec<&'a JoinCondition>,
+    /// the events hashed by their join key values, each bucket sorted by id. Without join keys
+    /// every event is in the bucket with the empty key
+    buckets: HashMap<Vec<OperandValue<'a>>, Vec<&'a Event>>,
+}
+
+impl BindingIndex<'_> {
+    /// the range of a bucket whose ids satisfy every id bound, given the events bound so far
+    fn id_range(&self, bucket: &[&Event], tuple: &[&Event]) -> (usize, usize) {
+        let (mut start, mut end) = (0, bucket.len());
+        for id_bound in &self.id_bounds {
+            let bound_id = tuple[id_bound.bound].id();
+            match id_bound.comparison {
+                JoinComparison::Gt => start = start.max(bucket.partition_point(|event| event.id() <= bound_id)),
+                JoinComparison::Ge => start = start.max(bucket.partition_point(|event| event.id() < bound_id)),
+                JoinComparison::Lt => end = end.min(bucket.partition_point(|event| event.id() < bound_id)),
+                JoinComparison::Le => end = end.min(bucket.partition_point(|event| event.id() <= bound_id)),
+                JoinComparison::Eq | JoinComparison::NotEq => {}
+            }
+        }
+
+        (start, end.max(start))
+    }
+}
+
+/// the value of an operand, normalized so that equal values hash the same way
+#[derive(Debug, Hash, PartialEq, Eq)]
+enum OperandValue<'a> {
+    Number(i64),
+    Symbol(&'a str),
+    Value(&'a AbstractValue),
+}
+
+impl<'a> OperandValue<'a> {
+    fn from_value(value: &'a AbstractValue) -> Self {
+        match value {
+            AbstractValue::Number(num) => OperandValue::Number(*num),
+            AbstractValue::Symbol(symb) => OperandValue::Symbol(symb),
+            other => OperandValue::Value(other),
+        }
+    }
+
+    fn from_literal(literal: &'a str) -> Self {
+        literal.parse::<i64>()
+            .map(OperandValue::Number)
+            .unwrap_or(OperandValue::Symbol(literal))
+    }
+}
+
+impl JoinQuery {
+    /// finds every tuple of candidates that satisfies the join. Each tuple holds one event id per
+    /// binding, in the order the bindings were written. Fails if there are more than `MAX_JOIN_TUPLES`
+    pub fn find_tuples(&self, candidates: &[&Event], log: &[Event], mismatches: &mut MismatchCounts) -> Result<Vec<Vec<usize>>, EventFilterError> {
+        let condition_aliases = self.conditions.iter()
+            .map(|condition| self.last_binding_of(condition))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut indexes = Vec::with_capacity(self.bindings.len());
+        for (binding_idx, binding) in self.bindings.iter().enumerate() {
+            let matches = binding.node.eval_candidates(candidates.iter().copied(), log, mismatches)?;
+            let join_keys = self.join_keys(binding_idx)?;
+            let mut buckets = HashMap::<Vec<OperandValue>, Vec<&Event>>::new();
+            for event in candidates.iter().filter(|event| matches.contains(&event.id())) {
+                let key = join_keys.iter()
+                    .map(|key| operand_value(key.joined, event))
+                    .collect::<Option<Vec<_>>>();
+                if let Some(key) = key {
+                    buckets.entry(key).or_default().push(event);
+                }
+            }
+
+            for bucket in buckets.values_mut() {
+                bucket.sort_by_key(|event| event.id());
+            }
+
+            indexes.push(BindingIndex {
+                join_keys,
+                id_bounds: self.id_bounds(binding_idx)?,
+                conditions: self.conditions.iter()
+                    .zip(&condition_aliases)
+                    .filter(|(_, last_binding)| **last_binding == binding_idx)
+                    .map(|(condition, _)| condition)
+                    .collect(),
+                buckets,
+            });
+        }
+
+        let mut tuples = Vec::new();
+        self.extend_tuple(&indexes, &mut Vec::with_capacity(indexes.len()), &mut tuples)?;
+        Ok(tuples)
+    }
+
+    /// a table with one row per tuple, and one column per binding
+    pub fn tuple_table(&self, tuples: &[Vec<usize>]) -> QueryTable {
+        let mut table = QueryTable::new(self.bindings.iter().map(|binding| binding.alias.clone()));
+        for tuple in tuples {
+            table.push_row(tuple.iter().map(|id| id.to_string()));
+        }
+
+        table
+    }
+
+    fn binding_idx(&self, alias: &str) -> Result<usize, EventFilterError> {
+        self.bindings.iter()
+            .position(|binding| binding.alias == alias)
+            .ok_or(EventFilterError::UnknownAlias)
+    }
+
+    /// the binding an operand refers to, or none if it's a literal
+    fn operand_binding(&self, operand: &JoinOperand) -> Result<Option<usize>, EventFilterError> {
+        operand.alias()
+            .map(|alias| self.binding_idx(alias))
+            .transpose()
+    }
+
+    /// the last binding a condition refers to, which is when it can first be checked
+    fn last_binding_of(&self, condition: &JoinCondition) -> Result<usize, EventFilterError> {
+        let left = self.operand_binding(&condition.left)?;
+        let right = self.operand_binding(&condition.right)?;
+        Ok(left.max(right).unwrap_or(0))
+    }
+
+    /// the equalities that can be used to hash join the given binding onto the ones before it
+    fn join_keys(&self, binding_idx: usize) -> Result<Vec<JoinKey<'_>>, EventFilterError> {
+        let mut keys = Vec::new();
+        for condition in &self.conditions {
+            if condition.comparison != JoinComparison::Eq {
+                continue;
+            }
+
+            let left = self.operand_binding(&condition.left)?;
+            let right = self.operand_binding(&condition.right)?;
+            match (left, right) {
+                (Some(left), Some(right)) if left == binding_idx && right < binding_idx => {
+                    keys.push(JoinKey { bound: &condition.right, joined: &condition.left });
+                }
+                (Some(left), Some(right)) if right == binding_idx && left < binding_idx => {
+                    keys.push(JoinKey { bound: &condition.left, joined: &condition.right });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(keys)
+    }
+
+    /// the orderings between the id of the given binding and the ids of the ones before it
+    fn id_bounds(&self, binding_idx: usize) -> Result<Vec<IdBound>, EventFilterError> {
+        let mut bounds = Vec::new();
+        for condition in &self.conditions {
+            let (JoinOperand::Id(left), JoinOperand::Id(right)) = (&condition.left, &condition.right) else {
+                continue;
+            };
+
+            let (left, right) = (self.binding_idx(left)?, self.binding_idx(right)?);
+            if left == binding_idx && right < binding_idx {
+                bounds.push(IdBound { bound: right, comparison: condition.comparison });
+            } else if right == binding_idx && left < binding_idx {
+                bounds.push(IdBound { bound: left, comparison: flip(condition.comparison) });
+            }
+        }
+
+        Ok(bounds)
+    }
+
+    /// binds each event that joins onto the partial tuple in turn, recording every complete tuple
+    fn extend_tuple<'a>(&self, indexes: &[BindingIndex<'a>], tuple: &mut Vec<&'a Event>, tuples: &mut Vec<Vec<usize>>) -> Result<(), EventFilterError> {
+        let Some(index) = indexes.get(tuple.len()) else {
+            if tuples.len() == MAX_JOIN_TUPLES {
+                return Err(EventFilterError::TooManyTuples);
+            }
+
+            tuples.push(tuple.iter().map(|event| event.id()).collect());
+            return Ok(());
+        };
+
+        let key = index.join_keys.iter()
+            .map(|key| self.tuple_operand_value(key.bound, tuple))
+            .collect::<Result<Option<Vec<_>>, _>>()?;
+        let Some(bucket) = key.and_then(|key| index.buckets.get(&key)) else {
+            return Ok(());
+        };
+
+        let (start, end) = index.id_range(bucket, tuple);
+        for event in &bucket[start..end] {
+            // an event is never bound twice in the same tuple
+            if tuple.iter().any(|bound| bound.id() == event.id()) {
+                continue;
+            }
+
+            tuple.push(event);
+            if self.check_conditions(&index.conditions, tuple)? {
+                self.extend_tuple(indexes, tuple, tuples)?;
+            }
+            tuple.pop();
+        }
+
+        Ok(())
+    }
+
+    fn check_conditions(&self, conditions: &[&JoinCondition], tuple: &[&Event]) -> Result<bool, EventFilterError> {
+        for condition in conditions {
+            if !self.check_condition(condition, tuple)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn check_condition(&self, condition: &JoinCondition, tuple: &[&Event]) -> Result<bool, EventFilterError> {
+        // missing fields never satisfy a condition
+        let (Some(left), Some(right)) = (self.tuple_operand_value(&condition.left, tuple)?, self.tuple_operand_value(&condition.right, tuple)?) else {
+            return Ok(false);
+        };
+
+        let satisfied = match condition.comparison {
+            JoinComparison::Eq => left == right,
+            JoinComparison::NotEq => left != right,
+            comparison => {
+                let (OperandValue::Number(left), OperandValue::Number(right)) = (left, right) else {
+                    return Ok(false);
+                };
+
+                match comparison {
+                    JoinComparison::Lt => left < right,
+                    JoinComparison::Le => left <= right,
+                    JoinComparison::Gt => left > right,
+                    _ => left >= right,
+                }
+            }
+        };
+
+        Ok(satisfied)
+    }
+
+    /// the value of an operand for the tuple, or none if the bound event doesn't have the field
+    fn tuple_operand_value<'a>(&self, operand: &'a JoinOperand, tuple: &[&'a Event]) -> Result<Option<OperandValue<'a>>, EventFilterError> {
+        if let JoinOperand::Literal(literal) = operand {
+            return Ok(Some(OperandValue::from_literal(literal)));
+        }
+
+        let Some(binding_idx) = self.operand_binding(operand)? else {
+            return Ok(None);
+        };
+        let event = tuple.get(binding_idx).ok_or(EventFilterError::UnknownAlias)?;
+        Ok(operand_value(operand, event))
+    }
+}
+
+/// the comparison with its sides swapped, so `a < b` becomes `b > a`
+fn flip(comparison: JoinComparison) -> JoinComparison {
+    match comparison {
+        JoinComparison::Lt => JoinComparison::Gt,
+        JoinComparison::Le => JoinComparison::Ge,
+        JoinComparison::Gt => JoinComparison::Lt,
+        JoinComparison::Ge => JoinComparison::Le,
+        other => other,
+    }
+}
+
+/// the value of an operand for the event bound to its alias
+fn operand_value<'a>(operand: &'a JoinOperand, event: &'a Event) -> Option<OperandValue<'a>> {
+    match operand {
+        JoinOperand::Field { path, .. } => path.lookup_value(event)
+            .ok()
+            .map(OperandValue::from_value),
+        JoinOperand::Node(_) => Some(OperandValue::Symbol(event.originator())),
+        JoinOperand::Id(_) => Some(OperandValue::Number(event.id() as i64)),
+        JoinOperand::Literal(literal) => Some(OperandValue::from_literal(literal)),
+    }
+}
+
+impl EventSequenceQuery for JoinQuery {
+    type ResT = Vec<Vec<usize>>;
+
+    fn eval(&self, events: &[Event]) -> Result<Self::ResT, EventFilterError> {
+        let candidates = events.iter().collect::<Vec<_>>();
+        self.find_tuples(&candidates, events, &mut MismatchCounts::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds_events::abstract_object::AbstractObject;
+    use crate::ds_events::event::Event;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::dsl::filters::{EventFilterError, EventSequenceQuery};
+    use crate::dsl::parser::parse_event_query;
+    use crate::dsl::query_ast::EventQuery;
+
+    fn message_events() -> Vec<Event> {
+        let messages = [
+            ("Send", "server1", "server2", "Prepare"),
+            ("Send", "server1", "server3", "Prepare"),
+            ("Recv", "server2", "server2", "Prepare"),
+            ("Recv", "server3", "server3", "Accept"),
+            ("Recv", "server3", "server1", "Prepare"),
+        ];
+
+        log(messages.map(|(name, originator, dest, payload)| {
+            (originator, AbstractObject::new(name).with_prop("dest", dest).with_prop("payload", payload))
+        }))
+    }
+
+    fn tuples(query_text: &str) -> Vec<Vec<usize>> {
+        eval_join(query_text, &message_events()).expect("Eval should succeed")
+    }
+
+    fn eval_join(query_text: &str, events: &[Event]) -> Result<Vec<Vec<usize>>, EventFilterError> {
+        let (remaining, query) = parse_event_query(query_text).expect("Parsing should succeed");
+        assert!(remaining.is_empty(), "unparsed input: {}", remaining);
+        let EventQuery::Join(join) = query else {
+            panic!("Expected a join query");
+        };
+        join.eval(events)
+    }
+
+    #[test]
+    fn hash_join_with_residual_condition() {
+        let query = "find Send as s, Recv as r where r.payload == s.payload and r.dest != s.dest";
+        assert_eq!(tuples(query), vec![vec![0, 4], vec![1, 2], vec![1, 4]]);
+    }
+
+    #[test]
+    fn join_with_literal_and_operator() {
+        let query = "find Send eq(dest, \"server2\") as s, Recv as r where s.payload == r.payload and r.dest == \"server2\"";
+        assert_eq!(tuples(query), vec![vec![0, 2]]);
+    }
+
+    #[test]
+    fn join_never_binds_an_event_twice() {
+        let query = "find Recv as a, Recv as b where a.payload == b.payload";
+        assert_eq!(tuples(query), vec![vec![2, 4], vec![4, 2]]);
+    }
+
+    #[test]
+    fn join_on_node_and_order() {
+        let query = "find Recv as a, Recv as b where node(a) == node(b) and id(a) < id(b)";
+        assert_eq!(tuples(query), vec![vec![3, 4]]);
+    }
+
+    #[test]
+    fn id_orderings_narrow_each_bucket() {
+        let events = log([
+            ("server1", slot_entry(1).with_prop("ballot", 2)),
+            ("server1", slot_entry(2).with_prop("ballot", 1)),
+            ("server2", slot_entry(1).with_prop("ballot", 3)),
+            ("server1", slot_entry(3).with_prop("ballot", 1)),
+            ("server1", slot_entry(4).with_prop("ballot", 0)),
+        ]);
+
+        let query = "find PaxosSlotEntry as a, PaxosSlotEntry as b where node(a) == node(b) and id(a) < id(b) and b.ballot < a.ballot";
+        assert_eq!(eval_join(query, &events).unwrap(), vec![vec![0, 1], vec![0, 3], vec![0, 4], vec![1, 4], vec![3, 4]]);
+
+        // the same ordering written from the other side
+        let query = "find PaxosSlotEntry as a, PaxosSlotEntry as b where node(a) == node(b) and id(b) > id(a) and b.ballot < a.ballot";
+        assert_eq!(eval_join(query, &events).unwrap(), vec![vec![0, 1], vec![0, 3], vec![0, 4], vec![1, 4], vec![3, 4]]);
+
+        let query = "find PaxosSlotEntry as a, PaxosSlotEntry as b where id(b) <= id(a) and b.ballot == a.ballot";
+        assert_eq!(eval_join(query, &events).unwrap(), vec![vec![3, 1]]);
+    }
+
+    #[test]
+    fn join_errors_are_returned() {
+        let query = "find Send within(5s, of: 99) as s, Recv as r where s.payload == r.payload";
+        assert!(matches!(eval_join(query, &message_events()), Err(EventFilterError::EventNotFound)));
+    }
+
+    #[test]
+    fn join_stops_at_the_tuple_limit() {
+        // 400 events pair up into 159600 tuples
+        let events = log((0..400).map(|_| ("server1", AbstractObject::new("Send"))));
+        let result = eval_join("find Send as a, Send as b where node(a) == node(b)", &events);
+        assert!(matches!(result, Err(EventFilterError::TooManyTuples)));
+    }
+}
@@ -0,0 +1,408 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use crate::ds_events::err::AppError;
+use crate::dsl::diagnostics::QueryDiagnostic;
+use crate::dsl::parser::parse_macro_header;
+
+/// the project file looked for in the working directory
+pub const PROJECT_FILE_NAME: &str = ".logviz";
+
+/// how deeply macros can call other macros before we assume one is recursive
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+/// names that are followed by `(` in queries, so macros can't use them
+const RESERVED_NAMES: &[&str] = &[
+    "eq", "has", "not", "server", "before", "after", "at_or_after", "at_or_before",
+    "between_times", "within", "node", "id",
+];
+
+/// a named query, with parameters that are replaced by the arguments it is called with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryMacro {
+    name: String,
+    params: Vec<String>,
+    /// the query text the macro expands to
+    body: String,
+    /// the comment written above the definition, if any
+    description: Option<String>,
+}
+
+impl QueryMacro {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// how the macro is called, like `chosen(s)`
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, self.params.join(", "))
+    }
+
+    fn expand(&self, args: &[&str]) -> Result<String, AppError> {
+        if args.len() != self.params.len() {
+            return Err(AppError::new(format!("{} takes {} argument(s) but was given {}", self.signature(), self.params.len(), args.len())));
+        }
+
+        let mut expanded = self.body.clone();
+        for range in identifiers(&self.body).into_iter().rev() {
+            if let Some(param_idx) = self.params.iter().position(|param| *param == self.body[range.clone()]) {
+                expanded.replace_range(range, args[param_idx]);
+            }
+        }
+
+        Ok(expanded)
+    }
+}
+
+/// where a stretch of expanded text came from in the text that was written
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ExpansionPiece {
+    /// text copied as it was written, starting at `source_start`
+    Written { expanded: Range<usize>, source_start: usize },
+    /// the expansion of the macro call at `call` in the written text
+    Call { expanded: Range<usize>, call: Range<usize> },
+}
+
+/// query text with its macro calls expanded, along with where each part of it was written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    text: String,
+    /// covers all of the expanded text, in order
+    pieces: Vec<ExpansionPiece>,
+}
+
+impl Expansion {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// moves a diagnostic from the expanded text to the text that was written. Errors inside of a
+    /// macro's expansion point at the call, and say what the call expanded to
+    pub fn locate(&self, source: &str, diagnostic: QueryDiagnostic) -> QueryDiagnostic {
+        let span = diagnostic.span().clone();
+        let piece = self.pieces.iter().find(|piece| match piece {
+            ExpansionPiece::Written { expanded, .. } | ExpansionPiece::Call { expanded, .. } => expanded.contains(&span.start),
+        });
+
+        match piece {
+            Some(ExpansionPiece::Written { expanded, source_start }) => {
+                let start = source_start + (span.start - expanded.start);
+                let end = source_start + (span.end.min(expanded.end) - expanded.start);
+                QueryDiagnostic::at(source, start..end, diagnostic.message())
+            }
+            Some(ExpansionPiece::Call { expanded, call }) => {
+                let message = format!("{}, in `{}`, which expands to `{}`", diagnostic.message(), &source[call.clone()], &self.text[expanded.clone()]);
+                QueryDiagnostic::at(source, call.clone(), message)
+            }
+            // errors at the very end of the text
+            None => QueryDiagnostic::at(source, source.len()..source.len(), diagnostic.message()),
+        }
+    }
+}
+
+/// a call to a macro in some query text
+struct MacroCall<'q> {
+    /// from the macro's name to the `)` that closes its arguments
+    range: Range<usize>,
+    query_macro: &'q QueryMacro,
+    /// the body of the macro with the arguments filled in
+    body: String,
+}
+
+/// the named queries shared by a project
+#[derive(Debug, Default, Clone)]
+pub struct QueryLibrary {
+    macros: Vec<QueryMacro>,
+    /// the text of each highlight rule, with the line it starts on. They are parsed once the
+    /// macros they call can be expanded
+    highlights: Vec<(usize, String)>,
+}
+
+impl QueryLibrary {
+    /// the project file to use: the given one, or `.logviz` in the working directory if it exists
+    pub fn find_project_file(explicit: Option<PathBuf>) -> Option<PathBuf> {
+        explicit.or_else(|| {
+            let default_path = PathBuf::from(PROJECT_FILE_NAME);
+            default_path.exists().then_some(default_path)
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| AppError::new(format!("could not read {}: {}", path.display(), err)))?;
+        Self::parse(&text)
+    }
+
+    /// parses a project file. Each definition starts with `define` or `highlight` and runs until
+    /// the next one. Lines starting with `#` are comments, and comments right above a definition
+    /// describe it
+    pub fn parse(text: &str) -> Result<Self, AppError> {
+        let mut definitions = Vec::<(usize, String, Option<String>)>::new();
+        let mut comment = Vec::<&str>::new();
+        for (line_idx, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if let Some(comment_text) = trimmed.strip_prefix('#') {
+                comment.push(comment_text.trim());
+            } else if trimmed.is_empty() {
+                comment.clear();
+            } else if matches!(trimmed.split_whitespace().next(), Some("define") | Some("highlight")) {
+                let description = (!comment.is_empty()).then(|| comment.join(" "));
+                definitions.push((line_idx + 1, line.to_string(), description));
+                comment.clear();
+            } else {
+                let Some((_, definition, _)) = definitions.last_mut() else {
+                    return Err(AppError::new(format!("line {}: expected a definition starting with `define`", line_idx + 1)));
+                };
+                definition.push('\n');
+                definition.push_str(line);
+            }
+        }
+
+        let mut library = Self::default();
+        for (line, definition, description) in definitions {
+            if definition.trim_start().starts_with("highlight") {
+                library.highlights.push((line, definition));
+                continue;
+            }
+            
+            let (body, (name, params)) = parse_macro_header(&definition)
+                .map_err(|_| AppError::new(format!("line {}: expected `define name(params) = query`", line)))?;
+
+            if RESERVED_NAMES.contains(&name) {
+                return Err(AppError::new(format!("line {}: {} is an operator, and can't be used as a query name", line, name)));
+            }
+            if library.get(name).is_some() {
+                return Err(AppError::new(format!("line {}: {} is defined more than once", line, name)));
+            }
+            if body.trim().is_empty() {
+                return Err(AppError::new(format!("line {}: {} has no query", line, name)));
+            }
+
+            library.macros.push(QueryMacro {
+                name: name.to_string(),
+                params: params.into_iter().map(|param| param.to_string()).collect(),
+                body: body.trim().to_string(),
+                description,
+            });
+        }
+
+        Ok(library)
+    }
+
+    pub fn macros(&self) -> &[QueryMacro] {
+        &self.macros
+    }
+
+    /// the text of each highlight rule, with the line it starts on
+    pub fn highlights(&self) -> &[(usize, String)] {
+        &self.highlights
+    }
+
+    pub fn get(&self, name: &str) -> Option<&QueryMacro> {
+        self.macros.iter().find(|query_macro| query_macro.name == name)
+    }
+
+    /// replaces every call to a macro in the text, like `chosen(3)`, with the query it stands for
+    pub fn expand(&self, text: &str) -> Result<Expansion, AppError> {
+        let mut expanded = String::new();
+        let mut pieces = Vec::new();
+        let mut copied_to = 0;
+        for call in self.calls(text)? {
+            let written_start = expanded.len();
+            expanded.push_str(&text[copied_to..call.range.start]);
+            pieces.push(ExpansionPiece::Written { expanded: written_start..expanded.len(), source_start: copied_to });
+
+            let call_start = expanded.len();
+            expanded.push_str(&self.expand_nested(&call.body, 1)?);
+            pieces.push(ExpansionPiece::Call { expanded: call_start..expanded.len(), call: call.range.clone() });
+            copied_to = call.range.end;
+        }
+
+        let written_start = expanded.len();
+        expanded.push_str(&text[copied_to..]);
+        pieces.push(ExpansionPiece::Written { expanded: written_start..expanded.len(), source_start: copied_to });
+        Ok(Expansion { text: expanded, pieces })
+    }
+
+    fn expand_nested(&self, text: &str, depth: usize) -> Result<String, AppError> {
+        let mut expanded = String::new();
+        let mut copied_to = 0;
+        for call in self.calls(text)? {
+            if depth >= MAX_EXPANSION_DEPTH {
+                return Err(AppError::new(format!("{} nests too deeply. Does it call itself?", call.query_macro.name)));
+            }
+
+            expanded.push_str(&text[copied_to..call.range.start]);
+            expanded.push_str(&self.expand_nested(&call.body, depth + 1)?);
+            copied_to = call.range.end;
+        }
+
+        expanded.push_str(&text[copied_to..]);
+        Ok(expanded)
+    }
+
+    /// every call to a macro in the text that isn't inside of another call
+    fn calls(&self, text: &str) -> Result<Vec<MacroCall<'_>>, AppError> {
+        let mut calls = Vec::new();
+        let mut call_end = 0;
+        for range in identifiers(text) {
+            if range.start < call_end {
+                // inside the arguments of a call we already found
+                continue;
+            }
+
+            let Some(query_macro) = self.get(&text[range.clone()]) else {
+                continue;
+            };
+            let Some((args, end)) = call_arguments(text, range.end) else {
+                continue;
+            };
+
+            call_end = end;
+            calls.push(MacroCall { range: range.start..end, query_macro, body: query_macro.expand(&args)? });
+        }
+
+        Ok(calls)
+    }
+}
+
+/// the byte ranges of every identifier or dotted path outside of quoted strings
+fn identifiers(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c == '"' {
+            chars.find(|(_, c)| *c == '"');
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some((idx, c)) = chars.peek().copied() {
+                if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                    break;
+                }
+                end = idx + c.len_utf8();
+                chars.next();
+            }
+            ranges.push(start..end);
+        } else if c.is_alphanumeric() {
+            // skip the rest of a number, so units like the `s` in `5s` aren't identifiers
+            while chars.next_if(|(_, c)| c.is_alphanumeric()).is_some() {}
+        }
+    }
+
+    ranges
+}
+
+/// if a `(` follows `name_end`, the comma separated arguments up to the matching `)`, and where
+/// the call ends
+fn call_arguments(text: &str, name_end: usize) -> Option<(Vec<&str>, usize)> {
+    let open_idx = name_end + text[name_end..].len() - text[name_end..].trim_start().len();
+    if !text[open_idx..].starts_with('(') {
+        return None;
+    }
+
+    let mut args = Vec::new();
+    let mut arg_start = open_idx + 1;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    for (idx, c) in text[open_idx + 1..].char_indices() {
+        let idx = idx + open_idx + 1;
+        match c {
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '(' | '{' => depth += 1,
+            ')' if depth == 0 => {
+                let last_arg = text[arg_start..idx].trim();
+                if !last_arg.is_empty() || !args.is_empty() {
+                    args.push(last_arg);
+                }
+                return Some((args, idx + 1));
+            }
+            ')' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                args.push(text[arg_start..idx].trim());
+                arg_start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+
+    // the call is never closed
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dsl::macros::QueryLibrary;
+    use crate::dsl::parser::parse_statement;
+
+    const PROJECT: &str = "
+# slots that were chosen with the given number
+define chosen(s) = find PaxosSlotEntry and {
+    eq(slotStatus, \"CHOSEN\");
+    eq(slotNum, s)
+}
+
+define chosen_on(server_num, s) = chosen(s) | find any server(server_num)
+define all_chosen() = find PaxosSlotEntry eq(slotStatus, \"CHOSEN\")
+
+highlight chosen_slots yellow = all_chosen()
+";
+
+    #[test]
+    fn parses_definitions() {
+        let library = QueryLibrary::parse(PROJECT).expect("Parsing should succeed");
+        let names = library.macros().iter().map(|query_macro| query_macro.signature()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["chosen(s)", "chosen_on(server_num, s)", "all_chosen()"]);
+        assert_eq!(library.get("chosen").unwrap().description(), Some("slots that were chosen with the given number"));
+        assert_eq!(library.get("chosen_on").unwrap().description(), None);
+        assert_eq!(library.highlights(), &[(11, "highlight chosen_slots yellow = all_chosen()".to_string())]);
+    }
+
+    #[test]
+    fn expands_nested_calls() {
+        let library = QueryLibrary::parse(PROJECT).expect("Parsing should succeed");
+        let expansion = library.expand("chosen_on(2, 15) select slotNum").expect("Expanding should succeed");
+        assert_eq!(expansion.text(), "find PaxosSlotEntry and {\n    eq(slotStatus, \"CHOSEN\");\n    eq(slotNum, 15)\n} | find any server(2) select slotNum");
+        assert_eq!(library.expand("count all_chosen()").unwrap().text(), "count find PaxosSlotEntry eq(slotStatus, \"CHOSEN\")");
+    }
+
+    #[test]
+    fn locates_errors_in_the_written_text() {
+        let library = QueryLibrary::parse("define bad(s) = find any eqq(slotNum, s)\ndefine good() = find any has(slotNum)").expect("Parsing should succeed");
+
+        // errors after a call point where they were written
+        let text = "count good()\n  garbage";
+        let expansion = library.expand(text).unwrap();
+        let diagnostic = expansion.locate(text, parse_statement(expansion.text()).expect_err("Parsing should fail"));
+        assert_eq!((diagnostic.line(), diagnostic.column()), (2, 3));
+        assert_eq!(&text[diagnostic.span().clone()], "garbage");
+
+        // errors inside of a named query point at the call
+        let text = "count bad(3) select slotNum";
+        let expansion = library.expand(text).unwrap();
+        let diagnostic = expansion.locate(text, parse_statement(expansion.text()).expect_err("Parsing should fail"));
+        assert_eq!((diagnostic.line(), diagnostic.column()), (1, 7));
+        assert_eq!(&text[diagnostic.span().clone()], "bad(3)");
+        assert!(diagnostic.message().ends_with("in `bad(3)`, which expands to `find any eqq(slotNum, 3)`"), "{}", diagnostic.message());
+    }
+
+    #[test]
+    fn rejects_bad_calls() {
+        let library = QueryLibrary::parse(PROJECT).expect("Parsing should succeed");
+        assert!(library.expand("chosen(1, 2)").is_err());
+        assert!(QueryLibrary::parse("define loop(x) = loop(x)").unwrap().expand("loop(1)").is_err());
+        assert!(QueryLibrary::parse("define eq(x) = find any has(x)").is_err());
+    }
+}
@@ -1,34 +1,308 @@
 use nom::branch::alt;
-use nom::bytes::complete::{is_not, tag};
-use nom::character::complete::{alpha1, alphanumeric1, char, multispace0};
-use nom::combinator::{map, recognize};
-use nom::error::ParseError;
-use nom::{IResult, Parser};
-use nom::multi::{many0, many0_count, separated_list1};
-use nom::sequence::{delimited, pair, separated_pair};
-use crate::dsl::query_ast::{EventNameFilter, EventQuery, FindEventNode, Operator, PropPath};
-
-pub fn parse_event_query(input: &str) -> IResult<&str, EventQuery> {
+use nom::bytes::complete::is_not;
+use nom::character::complete::{alpha1, alphanumeric1, char, digit1, multispace0, one_of};
+use nom::combinator::{map, map_opt, map_res, opt, recognize, verify};
+use nom::error::{context, ParseError};
+use nom::Parser;
+use nom::multi::{many0, many0_count, many1, separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use time::{Duration, PrimitiveDateTime};
+use crate::ds_events::event::parse_timestamp;
+use crate::dsl::diagnostics::{in_scope, parse_complete, token, ParseResult, ParseScope, QueryDiagnostic, SyntaxError};
+use crate::dsl::query_ast::{Aggregation, EventNameFilter, EventQuery, Expectation, FindEventNode, GroupKey, HighlightRule, JoinBinding, JoinComparison, JoinCondition, JoinOperand, JoinQuery, Operator, PropPath, QueryStatement, Rule, SequenceQuery, SequenceStep, SetCombinator, StepKind};
+
+/// what the parser says it expected where an event type, a field path, or a value to compare
+/// against could have gone. Completion looks for these
+pub(crate) const EXPECTED_EVENT_TYPE: &str = "an event type";
+pub(crate) const EXPECTED_PATH: &str = "a field path";
+pub(crate) const EXPECTED_VALUE: &str = "a string or number";
+
+/// parses any number of rules, like `rule chosen_agree = none find ...`
+pub fn parse_rules(input: &str) -> ParseResult<'_, Vec<Rule>> {
+    terminated(many0(parse_rule), multispace0)(input)
+}
+
+fn parse_rule(input: &str) -> ParseResult<'_, Rule> {
+    let (remaining, name) = preceded(ws(token("rule")), parse_path_segment)(input)?;
+    let (remaining, _) = ws(char('=')).parse(remaining)?;
+    let expectation = alt((
+        map(token("none"), |_| Expectation::None),
+        map(token("some"), |_| Expectation::Some),
+        ));
+    let (remaining, (expectation, query)) = pair(ws(expectation), parse_event_query)(remaining)?;
+    
+    Ok((remaining, Rule { name: name.to_string(), expectation, query }))
+}
+
+/// parses a highlight rule from the query editor or a project file, describing where it went
+/// wrong if it can't
+pub fn parse_highlight(input: &str) -> Result<HighlightRule, QueryDiagnostic> {
+    parse_complete(input, parse_highlight_rule)
+}
+
+/// parses a dotted field path given on the command line, like `ballot.roundNum`
+pub fn parse_field_path(input: &str) -> Result<PropPath, QueryDiagnostic> {
+    parse_complete(input, parse_path)
+}
+
+/// parses a highlight rule, like `highlight chosen yellow = find ...`
+fn parse_highlight_rule(input: &str) -> ParseResult<'_, HighlightRule> {
+    let color = context("color", recognize(pair(opt(char('#')), alphanumeric1)));
+    let (remaining, (name, color)) = preceded(ws(token("highlight")), pair(ws(parse_path_segment), ws(color)))(input)?;
+    let (remaining, _) = ws(char('=')).parse(remaining)?;
+    let (remaining, query) = parse_event_query(remaining)?;
+    
+    Ok((remaining, HighlightRule { name: name.to_string(), color: color.to_string(), query }))
+}
+
+/// parses the head of a macro definition, like `define chosen(s) =`, into its name and parameters
+pub fn parse_macro_header(input: &str) -> ParseResult<'_, (&str, Vec<&str>)> {
+    let params = delimited(
+        ws(char('(')),
+        separated_list0(ws(char(',')), parse_path_segment),
+        ws(char(')'))
+    );
+    let (remaining, (name, params)) = preceded(ws(token("define")), pair(ws(parse_path_segment), opt(params)))(input)?;
+    let (remaining, _) = ws(char('=')).parse(remaining)?;
+    
+    Ok((remaining, (name, params.unwrap_or_default())))
+}
+
+/// parses anything the query editor runs, a highlight rule or a statement, and throws it away. Used
+/// to find out what could come next in the editor
+pub(crate) fn parse_editor_text(input: &str) -> ParseResult<'_, ()> {
+    alt((
+        map(parse_highlight_rule, |_| ()),
+        map(parse_query_statement, |_| ()),
+        ))(input)
+}
+
+/// parses a whole statement from the query editor, describing where it went wrong if it can't
+pub fn parse_statement(input: &str) -> Result<QueryStatement, QueryDiagnostic> {
+    parse_complete(input, parse_query_statement)
+}
+
+/// parses a query with an optional trailing select clause, or a select clause on its own
+pub fn parse_query_statement(input: &str) -> ParseResult<'_, QueryStatement> {
+    alt((
+        map(parse_select_clause, |projection| QueryStatement { query: None, projection: Some(projection) }),
+        map(
+            pair(parse_event_query, opt(parse_select_clause)),
+            |(query, projection)| QueryStatement { query: Some(query), projection }
+        ),
+        ))(input)
+}
+
+/// parses `select a, b.c`, or `select *` to show whole events again
+fn parse_select_clause(input: &str) -> ParseResult<'_, Vec<PropPath>> {
+    let columns = alt((
+        map(ws(char('*')), |_| Vec::new()),
+        separated_list1(ws(char(',')), ws(parse_path)),
+        ));
+    preceded(ws(token("select")), columns)(input)
+}
+
+pub fn parse_event_query(input: &str) -> ParseResult<'_, EventQuery> {
+    alt((
+        map(preceded(ws(token("count")), parse_pipeline_query), |query| EventQuery::Count(Box::new(query))),
+        map(preceded(ws(token("exists")), parse_pipeline_query), |query| EventQuery::Exists(Box::new(query))),
+        parse_pipeline_query,
+        ))(input)
+}
+
+fn parse_pipeline_query(input: &str) -> ParseResult<'_, EventQuery> {
+    let (remaining, mut stages) = separated_list1(ws(char('|')), parse_find_stage)(input)?;
+    let query = if stages.len() == 1 {
+        stages.remove(0)
+    } else {
+        EventQuery::Pipeline { stages }
+    };
+    
+    let (remaining, aggregation) = opt(preceded(ws(char('|')), parse_aggregation))(remaining)?;
+    let query = match aggregation {
+        None => query,
+        Some(aggregation) => EventQuery::Aggregate { source: Box::new(query), aggregation },
+    };
+    
+    Ok((remaining, query))
+}
+
+fn parse_aggregation(input: &str) -> ParseResult<'_, Aggregation> {
+    alt((
+        parse_group_by_aggregation,
+        map(preceded(ws(token("distinct")), parse_path), Aggregation::Distinct),
+        map(preceded(ws(token("min")), parse_path), Aggregation::Min),
+        map(preceded(ws(token("max")), parse_path), Aggregation::Max),
+        ))(input)
+}
+
+fn parse_group_by_aggregation(input: &str) -> ParseResult<'_, Aggregation> {
+    let (remaining, _) = pair(ws(token("group")), ws(token("by")))(input)?;
+    let (remaining, key) = map(parse_path, |path| {
+        match path.segments.as_slice() {
+            [segment] if segment == "node" => GroupKey::Node,
+            [segment] if segment == "type" => GroupKey::Type,
+            _ => GroupKey::Path(path),
+        }
+    })(remaining)?;
+    let (remaining, _) = ws(token("count")).parse(remaining)?;
+    let (remaining, distinct) = opt(preceded(ws(token("distinct")), parse_path))(remaining)?;
+    
+    Ok((remaining, Aggregation::GroupByCount { key, distinct }))
+}
+
+fn parse_find_stage(input: &str) -> ParseResult<'_, EventQuery> {
+    alt((
+        map(parse_join_query, EventQuery::Join),
+        map(parse_sequence_query, EventQuery::Sequence),
+        map(
+            parse_find_events_query,
+            |find_events| EventQuery::Find { queries: find_events }
+        ),
+        ))(input)
+}
+
+/// parses `find Send as s, Recv as r where r.payload == s.payload and r.dest != s.dest`
+fn parse_join_query(input: &str) -> ParseResult<'_, JoinQuery> {
+    let bindings = verify(
+        separated_list1(ws(char(',')), parse_join_binding),
+        |bindings: &Vec<JoinBinding>| bindings.len() >= 2
+    );
+    let (remaining, bindings) = preceded(ws(token("find")), bindings)(input)?;
+    let conditions = preceded(ws(token("where")), separated_list1(ws(token("and")), parse_join_condition));
+    let (remaining, conditions) = opt(conditions)(remaining)?;
+    
+    Ok((remaining, JoinQuery {
+        bindings,
+        conditions: conditions.unwrap_or_default(),
+    }))
+}
+
+fn parse_join_binding(input: &str) -> ParseResult<'_, JoinBinding> {
+    let (remaining, event_type) = parse_event_name_filter(input)?;
+    let (remaining, operator) = opt(in_event_scope(&event_type, parse_operator))(remaining)?;
+    let (remaining, alias) = preceded(ws(token("as")), ws(parse_path_segment)).parse(remaining)?;
+    
+    // a binding without an operator accepts every event of its type
+    let node = FindEventNode {
+        combinator: SetCombinator::default(),
+        event_type,
+        operator: operator.unwrap_or(Operator::And(Vec::new())),
+    };
+    Ok((remaining, JoinBinding { alias: alias.to_string(), node }))
+}
+
+fn parse_join_condition(input: &str) -> ParseResult<'_, JoinCondition> {
+    let comparison = alt((
+        map(token("=="), |_| JoinComparison::Eq),
+        map(token("!="), |_| JoinComparison::NotEq),
+        map(token("<="), |_| JoinComparison::Le),
+        map(token(">="), |_| JoinComparison::Ge),
+        map(token("<"), |_| JoinComparison::Lt),
+        map(token(">"), |_| JoinComparison::Gt),
+        ));
+    map(
+        tuple((parse_join_operand, ws(comparison), parse_join_operand)),
+        |(left, comparison, right)| JoinCondition { left, comparison, right }
+    )(input)
+}
+
+fn parse_join_operand(input: &str) -> ParseResult<'_, JoinOperand> {
+    let field = map_opt(parse_path, |path| {
+        let (alias, segments) = path.segments.split_first()?;
+        if segments.is_empty() {
+            return None;
+        }
+        
+        Some(JoinOperand::Field {
+            alias: alias.clone(),
+            path: PropPath { segments: segments.to_vec() },
+        })
+    });
+    
+    let alias = || delimited(char('('), ws(parse_path_segment), char(')'));
+    alt((
+        map(preceded(token("node"), alias()), |alias| JoinOperand::Node(alias.to_string())),
+        map(preceded(token("id"), alias()), |alias| JoinOperand::Id(alias.to_string())),
+        field,
+        map(parse_eq_value, |value| JoinOperand::Literal(value.to_string())),
+        ))(input)
+}
+
+/// parses `find A then B not followed by C within 50 events on same node with same slotNum`
+fn parse_sequence_query(input: &str) -> ParseResult<'_, SequenceQuery> {
+    let (remaining, first) = preceded(ws(token("find")), parse_find_event_query)(input)?;
+    let (remaining, steps) = many1(parse_sequence_step)(remaining)?;
+    
+    let within = delimited(ws(token("within")), nom::character::complete::u64, ws(token("events")));
+    let (remaining, within) = opt(within)(remaining)?;
+    // checking that nothing follows an event means looking at every later event, unless it's limited
+    let has_not_followed_by = steps.iter().any(|step| step.kind == StepKind::NotFollowedBy);
+    if has_not_followed_by && within.is_none() {
+        return Err(nom::Err::Failure(SyntaxError::expected(remaining, "`within N events`, which `not followed by` needs")));
+    }
+    let same_node = tuple((ws(token("on")), ws(token("same")), ws(token("node"))));
+    let (remaining, same_node) = opt(same_node)(remaining)?;
+    let same_values = preceded(
+        pair(ws(token("with")), ws(token("same"))),
+        separated_list1(ws(char(',')), ws(parse_path))
+    );
+    let (remaining, same_values) = opt(same_values)(remaining)?;
+    
+    Ok((remaining, SequenceQuery {
+        first,
+        steps,
+        within: within.map(|within| within as usize),
+        same_node: same_node.is_some(),
+        same_values: same_values.unwrap_or_default(),
+    }))
+}
+
+fn parse_sequence_step(input: &str) -> ParseResult<'_, SequenceStep> {
+    let step_kind = alt((
+        map(ws(token("then")), |_| StepKind::FollowedBy),
+        map(tuple((ws(token("not")), ws(token("followed")), ws(token("by")))), |_| StepKind::NotFollowedBy),
+        ));
     map(
-        pair(ws(tag("find")), parse_find_events_query),
-        |(_, find_events)| EventQuery::Find { queries: find_events }
+        pair(step_kind, parse_find_event_query),
+        |(kind, node)| SequenceStep { kind, node }
     )(input)
 }
 
-fn parse_find_events_query(input: &str) -> IResult<&str, Vec<FindEventNode>> {
-    map(parse_find_event_query, |ev| vec![ev])(input)
+fn parse_find_events_query(input: &str) -> ParseResult<'_, Vec<FindEventNode>> {
+    let (remaining, first) = preceded(ws(token("find")), parse_find_event_query)(input)?;
+    let combined_query = pair(
+        ws(parse_set_combinator),
+        preceded(ws(token("find")), parse_find_event_query)
+    );
+    let (remaining, rest) = many0(combined_query)(remaining)?;
+    
+    let mut queries = vec![first];
+    queries.extend(rest.into_iter()
+        .map(|(combinator, query)| FindEventNode { combinator, ..query }));
+    
+    Ok((remaining, queries))
+}
+
+fn parse_set_combinator(input: &str) -> ParseResult<'_, SetCombinator> {
+    alt((
+        map(token("union"), |_| SetCombinator::Union),
+        map(token("intersect"), |_| SetCombinator::Intersect),
+        map(token("except"), |_| SetCombinator::Except),
+        ))(input)
 }
 
-fn parse_find_event_query(input: &str) -> IResult<&str, FindEventNode> {
+fn parse_find_event_query(input: &str) -> ParseResult<'_, FindEventNode> {
     let (remaining, filter_name) = parse_event_name_filter(input)?;
-    let (remaining, op) = parse_operator(remaining)?;
+    let (remaining, op) = in_event_scope(&filter_name, parse_operator)(remaining)?;
     Ok((remaining, FindEventNode {
+        combinator: SetCombinator::default(),
         event_type: filter_name,
         operator: op
     }))
 }
 
-fn parse_operator(input: &str) -> IResult<&str, Operator> {
+fn parse_operator(input: &str) -> ParseResult<'_, Operator> {
     alt((
         parse_eq_op,
         parse_has_op,
@@ -38,114 +312,179 @@ fn parse_operator(input: &str) -> IResult<&str, Operator> {
         parse_server_op,
         parse_before_op,
         parse_after_op,
+        parse_at_or_after_op,
+        parse_at_or_before_op,
+        parse_between_times_op,
+        parse_within_op,
         ))(input)
 }
 
-fn parse_or_ops(input: &str) -> IResult<&str, Operator> {
-    let (remaining, _) = tag("or")(input)?;
+fn parse_or_ops(input: &str) -> ParseResult<'_, Operator> {
+    let (remaining, _) = token("or")(input)?;
     let body_parser = separated_list1(ws(char(';')), parse_operator);
     let (remaining, ops) = delimited(ws(char('{')), body_parser, ws(char('}')))(remaining)?;
 
     Ok((remaining, Operator::Or(ops)))
 }
 
-fn parse_and_ops(input: &str) -> IResult<&str, Operator> {
+fn parse_and_ops(input: &str) -> ParseResult<'_, Operator> {
 
-    let (remaining, _) = tag("and")(input)?;
+    let (remaining, _) = token("and")(input)?;
     let body_parser = separated_list1(ws(char(';')), parse_operator);
     let (remaining, ops) = delimited(ws(char('{')), body_parser, ws(char('}')))(remaining)?;
 
     Ok((remaining, Operator::And(ops)))
 }
 
-fn parse_not_op(input: &str) -> IResult<&str, Operator> {
+fn parse_not_op(input: &str) -> ParseResult<'_, Operator> {
     let (remaining, (_, op)) = pair(
-        ws(tag("not")),
+        ws(token("not")),
         delimited(char('('), parse_operator, char(')'))
     )(input)?;
 
     Ok((remaining, Operator::Not(Box::new(op))))
 }
 
-fn parse_eq_op(input: &str) -> IResult<&str, Operator> {
+fn parse_eq_op(input: &str) -> ParseResult<'_, Operator> {
     // TODO this needs to accept numbers vs strings etc.
-    let (remaining, _) = tag("eq")(input)?;
-    let params = separated_pair(parse_path, ws(char(',')), parse_eq_value);
-    let (remaining, (prop_name, value)) = delimited(char('('), params, char(')'))(remaining)?;
+    let (remaining, _) = token("eq")(input)?;
+    let (remaining, prop_name) = preceded(char('('), parse_path)(remaining)?;
+    let value = in_scope(|scope: &mut ParseScope| scope.eq_path = Some(prop_name.to_string()), parse_eq_value);
+    let (remaining, value) = terminated(preceded(ws(char(',')), value), char(')'))(remaining)?;
 
     Ok((remaining, Operator::Eq { prop_name, comparison: value.to_string() }))
 }
 
-fn parse_server_op(input: &str) -> IResult<&str, Operator> {
-    let (remaining, _) = tag("server")(input)?;
+fn parse_server_op(input: &str) -> ParseResult<'_, Operator> {
+    let (remaining, _) = token("server")(input)?;
     let (remaining, server_id) = delimited(char('('), parse_eq_value, char(')'))(remaining)?;
 
     Ok((remaining, Operator::Server(server_id.to_string())))
 }
 
-fn parse_before_op(input: &str) -> IResult<&str, Operator> {
-    let (remaining, _) = tag("before")(input)?;
+fn parse_before_op(input: &str) -> ParseResult<'_, Operator> {
+    let (remaining, _) = token("before")(input)?;
     let (remaining, idx) = delimited(char('('), nom::character::complete::u64, char(')'))(remaining)?;
     Ok((remaining, Operator::Before(idx as usize)))
 }
 
-fn parse_after_op(input: &str) -> IResult<&str, Operator> {
-    let (remaining, _) = tag("after")(input)?;
+fn parse_after_op(input: &str) -> ParseResult<'_, Operator> {
+    let (remaining, _) = token("after")(input)?;
     let (remaining, idx) = delimited(char('('), nom::character::complete::u64, char(')'))(remaining)?;
     Ok((remaining, Operator::After(idx as usize)))
 }
 
-fn parse_eq_value(input: &str) -> IResult<&str, &str> {
-    alt((
+fn parse_at_or_after_op(input: &str) -> ParseResult<'_, Operator> {
+    let (remaining, _) = token("at_or_after")(input)?;
+    let (remaining, time) = delimited(char('('), parse_timestamp_value, char(')'))(remaining)?;
+    Ok((remaining, Operator::AtOrAfter(time)))
+}
+
+fn parse_at_or_before_op(input: &str) -> ParseResult<'_, Operator> {
+    let (remaining, _) = token("at_or_before")(input)?;
+    let (remaining, time) = delimited(char('('), parse_timestamp_value, char(')'))(remaining)?;
+    Ok((remaining, Operator::AtOrBefore(time)))
+}
+
+fn parse_between_times_op(input: &str) -> ParseResult<'_, Operator> {
+    let (remaining, _) = token("between_times")(input)?;
+    let params = separated_pair(parse_timestamp_value, ws(char(',')), parse_timestamp_value);
+    let (remaining, (start, end)) = delimited(char('('), params, char(')'))(remaining)?;
+    Ok((remaining, Operator::BetweenTimes { start, end }))
+}
+
+fn parse_within_op(input: &str) -> ParseResult<'_, Operator> {
+    let (remaining, _) = token("within")(input)?;
+    let anchor = preceded(pair(token("of"), ws(char(':'))), nom::character::complete::u64);
+    let params = separated_pair(parse_duration, ws(char(',')), anchor);
+    let (remaining, (window, anchor)) = delimited(char('('), params, char(')'))(remaining)?;
+    Ok((remaining, Operator::Within { window, anchor: anchor as usize }))
+}
+
+/// parses a timestamp string literal, in the same format as the log file
+fn parse_timestamp_value(input: &str) -> ParseResult<'_, PrimitiveDateTime> {
+    context("a timestamp like \"2024-03-05 22:59:25\"", map_res(parse_str_value, parse_timestamp))(input)
+}
+
+/// parses a duration like `500ms`, `5s`, `2m`, or `1h`
+fn parse_duration(input: &str) -> ParseResult<'_, Duration> {
+    let (remaining, amount) = nom::character::complete::i64(input)?;
+    let (remaining, unit) = alt((token("ms"), token("s"), token("m"), token("h")))(remaining)?;
+    let duration = match unit {
+        "ms" => Duration::milliseconds(amount),
+        "s" => Duration::seconds(amount),
+        "m" => Duration::minutes(amount),
+        _ => Duration::hours(amount),
+    };
+    
+    Ok((remaining, duration))
+}
+
+fn parse_eq_value(input: &str) -> ParseResult<'_, &str> {
+    context(EXPECTED_VALUE, alt((
         parse_str_value,
         recognize(nom::character::complete::i64)
-        ))(input)
+        )))(input)
 }
 
-fn parse_str_value(input: &str) -> IResult<&str, &str> {
+fn parse_str_value(input: &str) -> ParseResult<'_, &str> {
     delimited(char('"'), recognize(many0(is_not("\""))), char('"'))(input)
 }
 
-fn parse_has_op(input: &str) -> IResult<&str, Operator> {
+fn parse_has_op(input: &str) -> ParseResult<'_, Operator> {
 
     let (remaining, (_, ident)) = pair(
-        ws(tag("has")),
+        ws(token("has")),
         delimited(char('('), parse_path, char(')'))
     )(input)?;
 
     Ok((remaining, Operator::Has(ident)))
 }
 
-fn parse_event_name_filter(input: &str) -> IResult<&str, EventNameFilter> {
+fn parse_event_name_filter(input: &str) -> ParseResult<'_, EventNameFilter> {
     alt((
-        map(ws(tag("any")), |_| EventNameFilter::Any),
+        map(ws(token("any")), |_| EventNameFilter::Any),
         map(ws(parse_ident), |name| EventNameFilter::Named(name.to_string()))
         ))(input)
 }
 
-fn parse_ident(input: &str) -> IResult<&str, &str> {
-    recognize(pair(
-        alt((alpha1, tag("_"))),
-        many0_count(alt((alphanumeric1, tag("_"), tag("."))))
-    ))(input)
+fn parse_ident(input: &str) -> ParseResult<'_, &str> {
+    context(EXPECTED_EVENT_TYPE, recognize(pair(
+        alt((alpha1, recognize(char('_')))),
+        many0_count(alt((alphanumeric1, recognize(one_of("_.")))))
+    )))(input)
 }
 
-fn parse_path_segment(input: &str) -> IResult<&str, &str> {
+fn parse_path_segment(input: &str) -> ParseResult<'_, &str> {
     recognize(pair(
-        alt((alpha1, tag("_"))),
-        many0_count(alt((alphanumeric1, tag("_"))))
+        alt((alpha1, recognize(char('_')))),
+        many0_count(alt((alphanumeric1, recognize(char('_')))))
     ))(input)
 }
 
-fn parse_path(input: &str) -> IResult<&str, PropPath> {
-    let (remaining, segments) = separated_list1(char('.'), parse_path_segment)(input)?;
-    let segments = segments.into_iter()
+fn parse_path(input: &str) -> ParseResult<'_, PropPath> {
+    // segments after the first can also be collection item indexes, like `acceptors.0`
+    let later_segment = preceded(char('.'), alt((parse_path_segment, digit1)));
+    let (remaining, (first, rest)) = context(EXPECTED_PATH, pair(parse_path_segment, many0(later_segment)))(input)?;
+    let segments = [first].into_iter()
+        .chain(rest)
         .map(|seg| seg.to_string())
         .collect::<Vec<_>>();
     
     Ok((remaining, PropPath { segments }))
 }
 
+/// runs `parser` in the scope of a find clause for the event type, for completion
+fn in_event_scope<'a, ParserT, O>(event_type: &EventNameFilter, parser: ParserT) -> impl FnMut(&'a str) -> ParseResult<'a, O>
+    where ParserT: FnMut(&'a str) -> ParseResult<'a, O>
+{
+    let event_type = match event_type {
+        EventNameFilter::Any => None,
+        EventNameFilter::Named(name) => Some(name.clone()),
+    };
+    in_scope(move |scope: &mut ParseScope| scope.event_type = event_type.clone(), parser)
+}
+
 /// A combinator that takes a parser `inner` and produces a parser that also consumes both leading and
 /// trailing whitespace, returning the output of `inner`.
 fn ws<'a, F, O, E: ParseError<&'a str>>(inner: F) -> impl Parser<&'a str, O, E>
@@ -161,17 +500,20 @@ fn ws<'a, F, O, E: ParseError<&'a str>>(inner: F) -> impl Parser<&'a str, O, E>
 
 #[cfg(test)]
 mod tests {
-    use crate::dsl::parser::{parse_eq_op, parse_event_query};
-    use crate::dsl::query_ast::{EventNameFilter, EventQuery, Operator};
+    use time::Duration;
+    use crate::dsl::parser::{parse_eq_op, parse_event_query, parse_field_path, parse_highlight, parse_operator, parse_query_statement};
+    use crate::dsl::query_ast::{Aggregation, EventNameFilter, EventQuery, GroupKey, Operator, SetCombinator};
 
     #[test]
     fn find_event_parse() {
         let ev_text = "find any has(slotNum)";
         let (_, query) = parse_event_query(ev_text).expect("Parsing should succeed");
-        let EventQuery::Find { queries } = query;
+        let EventQuery::Find { queries } = query else {
+            panic!("Expected a find query");
+        };
 
         assert_eq!(queries.len(), 1);
-        let first = queries.get(0).expect("should have a first element");
+        let first = queries.first().expect("should have a first element");
         let EventNameFilter::Any = first.event_type else { panic!("Expected query filter to be any") };
         match &first.operator {
             Operator::Has(prop) => assert_eq!(prop.segments[0], "slotNum"),
@@ -200,6 +542,17 @@ mod tests {
         let (_, _query) = parse_event_query(ev_text).expect("Parsing should succeed");
     }
     
+    #[test]
+    fn parse_highlight_rule() {
+        let rule = parse_highlight("highlight chosen #ffcc00 = find PaxosSlotEntry eq(slotStatus, \"CHOSEN\")")
+            .expect("Parsing should succeed");
+        assert_eq!(rule.name, "chosen");
+        assert_eq!(rule.color, "#ffcc00");
+        assert!(matches!(rule.query, EventQuery::Find { .. }));
+        
+        assert!(parse_highlight("highlight chosen = find any").is_err());
+    }
+    
     #[test]
     fn parse_path() {
         let text = "eq(leader.area, 10)";
@@ -209,7 +562,143 @@ mod tests {
         };
         
         assert_eq!(prop_name.segments.len(), 2);
-        assert_eq!(prop_name.segments.get(0).unwrap(), "leader");
+        assert_eq!(prop_name.segments.first().unwrap(), "leader");
         assert_eq!(prop_name.segments.get(1).unwrap(), "area");
+        assert_eq!(comparison, "10");
+    }
+    
+    #[test]
+    fn parse_field_paths() {
+        assert_eq!(parse_field_path("ballot.roundNum").unwrap().to_string(), "ballot.roundNum");
+        assert!(parse_field_path("ballot.").is_err());
+    }
+    
+    #[test]
+    fn parse_time_ops() {
+        let (_, op) = parse_operator("at_or_after(\"2024-03-05 22:59:25\")").expect("Should parse successfully");
+        let Operator::AtOrAfter(time) = op else {
+            panic!("Expected at_or_after, but got {:?}", op);
+        };
+        assert_eq!(time.minute(), 59);
+        
+        let (_, op) = parse_operator("between_times(\"2024-03-05 22:59:25\", \"2024-03-05 23:00:00\")").expect("Should parse successfully");
+        let Operator::BetweenTimes { start, end } = op else {
+            panic!("Expected between_times, but got {:?}", op);
+        };
+        assert!(start < end);
+        
+        assert!(parse_operator("at_or_after(\"not a time\")").is_err());
+    }
+    
+    #[test]
+    fn parse_within_op() {
+        let (_, op) = parse_operator("within(5s, of: 1234)").expect("Should parse successfully");
+        let Operator::Within { window, anchor } = op else {
+            panic!("Expected within, but got {:?}", op);
+        };
+        assert_eq!(window, Duration::seconds(5));
+        assert_eq!(anchor, 1234);
+        
+        let (_, op) = parse_operator("within(250ms, of: 3)").expect("Should parse successfully");
+        let Operator::Within { window, .. } = op else {
+            panic!("Expected within, but got {:?}", op);
+        };
+        assert_eq!(window, Duration::milliseconds(250));
+    }
+    
+    #[test]
+    fn parse_combined_find_clauses() {
+        let ev_text = "find any has(slotNum) intersect find PaxosSlotEntry eq(slotStatus, \"CHOSEN\") except find any server(1)";
+        let (remaining, query) = parse_event_query(ev_text).expect("Parsing should succeed");
+        assert!(remaining.is_empty());
+        let EventQuery::Find { queries } = query else {
+            panic!("Expected a find query");
+        };
+        
+        let combinators = queries.iter()
+            .map(|query| query.combinator)
+            .collect::<Vec<_>>();
+        assert_eq!(combinators, vec![SetCombinator::Union, SetCombinator::Intersect, SetCombinator::Except]);
+    }
+    
+    #[test]
+    fn parse_pipeline() {
+        let ev_text = "find any has(slotNum) union find any has(ballot) | find any server(2)";
+        let (remaining, query) = parse_event_query(ev_text).expect("Parsing should succeed");
+        assert!(remaining.is_empty());
+        let EventQuery::Pipeline { stages } = query else {
+            panic!("Expected a pipeline query");
+        };
+        
+        assert_eq!(stages.len(), 2);
+        let EventQuery::Find { queries } = &stages[0] else {
+            panic!("Expected first stage to be a find query");
+        };
+        assert_eq!(queries.len(), 2);
+    }
+    
+    #[test]
+    fn parse_count_and_exists() {
+        let (remaining, query) = parse_event_query("count find any has(slotNum)").expect("Parsing should succeed");
+        assert!(remaining.is_empty());
+        let EventQuery::Count(source) = query else {
+            panic!("Expected a count query");
+        };
+        let EventQuery::Find { .. } = *source else {
+            panic!("Expected count to wrap a find query");
+        };
+        
+        let (_, query) = parse_event_query("exists find any has(slotNum) | find any server(1)").expect("Parsing should succeed");
+        let EventQuery::Exists(source) = query else {
+            panic!("Expected an exists query");
+        };
+        let EventQuery::Pipeline { .. } = *source else {
+            panic!("Expected exists to wrap a pipeline");
+        };
+    }
+    
+    #[test]
+    fn parse_aggregations() {
+        let (remaining, query) = parse_event_query("find any has(slotNum) | group by node count distinct slotNum").expect("Parsing should succeed");
+        assert!(remaining.is_empty());
+        let EventQuery::Aggregate { aggregation: Aggregation::GroupByCount { key: GroupKey::Node, distinct: Some(path) }, .. } = query else {
+            panic!("Expected a group by node aggregation");
+        };
+        assert_eq!(path.segments, vec!["slotNum"]);
+        
+        let (_, query) = parse_event_query("find any has(slotNum) | group by acceptedBallot.roundNum count").expect("Parsing should succeed");
+        let EventQuery::Aggregate { aggregation: Aggregation::GroupByCount { key: GroupKey::Path(path), distinct: None }, .. } = query else {
+            panic!("Expected a group by path aggregation");
+        };
+        assert_eq!(path.segments.len(), 2);
+        
+        let (_, query) = parse_event_query("find any has(slotNum) | find any server(1) | max slotNum").expect("Parsing should succeed");
+        let EventQuery::Aggregate { source, aggregation: Aggregation::Max(_) } = query else {
+            panic!("Expected a max aggregation");
+        };
+        let EventQuery::Pipeline { stages } = *source else {
+            panic!("Expected a pipeline source");
+        };
+        assert_eq!(stages.len(), 2);
+    }
+    
+    #[test]
+    fn parse_select_statements() {
+        let (remaining, statement) = parse_query_statement("find any has(slotNum) select slotNum, acceptedBallot.roundNum").expect("Parsing should succeed");
+        assert!(remaining.is_empty());
+        assert!(statement.query().is_some());
+        let projection = statement.projection().expect("should have a projection");
+        assert_eq!(projection.len(), 2);
+        assert_eq!(projection[1].segments, vec!["acceptedBallot", "roundNum"]);
+        
+        let (_, statement) = parse_query_statement("select slotNum").expect("Parsing should succeed");
+        assert!(statement.query().is_none());
+        assert_eq!(statement.projection().map(|columns| columns.len()), Some(1));
+        
+        let (_, statement) = parse_query_statement("select *").expect("Parsing should succeed");
+        assert_eq!(statement.projection().map(|columns| columns.len()), Some(0));
+        
+        let (_, statement) = parse_query_statement("find any has(slotNum)").expect("Parsing should succeed");
+        assert!(statement.projection().is_none());
     }
 }
@@ -0,0 +1,238 @@
+use std::collections::{BTreeSet, HashSet};
+use std::ops::Range;
+use crate::ds_events::event::Event;
+use crate::ds_events::index::{intersect, union, within, EventIndex};
+use crate::dsl::filters::{combine_find_matches, eval_later_stages, lookup_event, EventFilterError};
+use crate::dsl::issues::MismatchCounts;
+use crate::dsl::query_ast::{EventNameFilter, EventQuery, FindEventNode, Operator};
+
+/// narrows two candidate lists down to the events in both. `None` is every event
+fn and_plans(left: Option<Vec<usize>>, right: Option<Vec<usize>>) -> Option<Vec<usize>> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(intersect(&left, &right)),
+        (plan, None) | (None, plan) => plan,
+    }
+}
+
+impl Operator {
+    /// the sorted ids in `ids` of the events that could pass this operator, looked up in the index.
+    /// Returns `None` if the index can't narrow things down and every event has to be tested.
+    /// Candidates are still tested, so plans only need to include every match, not exclude every
+    /// non-match
+    fn plan(&self, index: &EventIndex, ids: &Range<usize>) -> Option<Vec<usize>> {
+        match self {
+            Operator::Eq { prop_name, comparison } => index.with_value(&prop_name.to_string(), comparison)
+                .map(|matches| within(&matches, ids).to_vec()),
+            Operator::Server(server_id) => Some(within(index.with_originator(&format!("server{}", server_id)), ids).to_vec()),
+            Operator::After(after) => {
+                let all_ids = within(index.all_ids(), ids);
+                Some(all_ids[all_ids.partition_point(|id| id < after)..].to_vec())
+            }
+            Operator::Before(before) => {
+                let all_ids = within(index.all_ids(), ids);
+                Some(all_ids[..all_ids.partition_point(|id| id < before)].to_vec())
+            }
+            Operator::And(ops) => ops.iter()
+                .map(|op| op.plan(index, ids))
+                .fold(None, and_plans),
+            Operator::Or(ops) => ops.iter()
+                .map(|op| op.plan(index, ids))
+                .try_fold(Vec::new(), |matches, plan| Some(union(&matches, &plan?))),
+            // negations, time ranges, and presence checks need every event
+            _ => None,
+        }
+    }
+}
+
+impl FindEventNode {
+    /// the sorted ids of the events that could match this node, or `None` if every event has to be
+    /// tested
+    pub fn plan(&self, index: &EventIndex) -> Option<Vec<usize>> {
+        self.plan_within(index, &(0..usize::MAX))
+    }
+
+    /// like [`FindEventNode::plan`], but only for the events with ids in `ids`
+    fn plan_within(&self, index: &EventIndex, ids: &Range<usize>) -> Option<Vec<usize>> {
+        let type_plan = match &self.event_type {
+            EventNameFilter::Any => None,
+            EventNameFilter::Named(name) => Some(within(index.with_name(name), ids).to_vec()),
+        };
+
+        and_plans(type_plan, self.operator.plan(index, ids))
+    }
+
+    /// finds all events in the log that match this node, only testing the ones the index says could
+    pub fn eval_indexed(&self, log: &[Event], index: &EventIndex) -> Result<HashSet<usize>, EventFilterError> {
+        self.eval_indexed_within(log, index, log, &mut MismatchCounts::default())
+    }
+
+    /// finds the events in `events`, a contiguous piece of the log, that match this node
+    fn eval_indexed_within(&self, log: &[Event], index: &EventIndex, events: &[Event], mismatches: &mut MismatchCounts) -> Result<HashSet<usize>, EventFilterError> {
+        let (Some(first), Some(last)) = (events.first(), events.last()) else {
+            return Ok(HashSet::new());
+        };
+
+        match self.plan_within(index, &(first.id()..last.id() + 1)) {
+            Some(candidates) => self.eval_candidates(candidates.iter().filter_map(|id| lookup_event(log, *id)), log, mismatches),
+            None => self.eval_candidates(events, log, mismatches),
+        }
+    }
+}
+
+impl EventQuery {
+    /// finds all events in the log that match this query, using the index to skip events that
+    /// can't match the find clauses that start the query
+    pub fn eval_indexed(&self, log: &[Event], index: &EventIndex) -> Result<BTreeSet<usize>, EventFilterError> {
+        self.eval_indexed_within(log, index, log, &mut MismatchCounts::default())
+    }
+
+    /// finds the events in `events`, a contiguous piece of the log, that match this query. Only
+    /// queries that [can be evaluated in pieces](EventQuery::can_eval_in_pieces) only look at
+    /// `events`; the rest search the whole log. Events with values the query couldn't compare are
+    /// counted in `mismatches`
+    pub fn eval_indexed_within(&self, log: &[Event], index: &EventIndex, events: &[Event], mismatches: &mut MismatchCounts) -> Result<BTreeSet<usize>, EventFilterError> {
+        match self {
+            EventQuery::Find { queries } => combine_find_matches(queries, |query| query.eval_indexed_within(log, index, events, mismatches)),
+            EventQuery::Pipeline { stages } => {
+                let Some((first_stage, rest)) = stages.split_first() else {
+                    return Ok(BTreeSet::new());
+                };
+
+                let matches = first_stage.eval_indexed_within(log, index, events, mismatches)?;
+                eval_later_stages(rest, matches, log, mismatches)
+            }
+            EventQuery::Exists(source) | EventQuery::Count(source) | EventQuery::Aggregate { source, .. } => {
+                source.eval_indexed_within(log, index, events, mismatches)
+            }
+            EventQuery::Sequence(_) | EventQuery::Join(_) => {
+                let candidates = log.iter().collect::<Vec<_>>();
+                self.eval_candidates(&candidates, log, mismatches)
+            }
+        }
+    }
+
+    /// true if whether an event matches doesn't depend on the other events that match, so the log
+    /// can be searched a piece at a time. Sequences and joins match events together
+    pub fn can_eval_in_pieces(&self) -> bool {
+        match self {
+            EventQuery::Find { .. } => true,
+            EventQuery::Pipeline { stages } => stages.iter().all(|stage| stage.can_eval_in_pieces()),
+            EventQuery::Exists(source) | EventQuery::Count(source) | EventQuery::Aggregate { source, .. } => {
+                source.can_eval_in_pieces()
+            }
+            EventQuery::Sequence(_) | EventQuery::Join(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use crate::ds_events::abstract_object::{AbstractObject, AbstractValue};
+    use crate::ds_events::event::Event;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::ds_events::index::EventIndex;
+    use crate::ds_events::schema::LogSchema;
+    use crate::dsl::filters::EventSequenceQuery;
+    use crate::dsl::parser::parse_event_query;
+    use crate::dsl::query_ast::EventQuery;
+
+    fn slot_events() -> Vec<Event> {
+        log((0..12i64).map(|id| {
+            let obj = if id % 4 == 3 {
+                AbstractObject::new("PaxosRequest").with_prop("sequenceNum", id)
+            } else {
+                slot_entry(id % 3).with_prop("slotStatus", if id % 2 == 0 { "CHOSEN" } else { "ACCEPTED" })
+            };
+            (format!("server{}", id % 2), obj)
+        }))
+    }
+
+    fn parse(query: &str) -> EventQuery {
+        parse_event_query(query).expect("Parsing should succeed").1
+    }
+
+    #[test]
+    fn plans_and_or_trees() {
+        let events = slot_events();
+        let index = EventIndex::build(&events, &LogSchema::infer(&events));
+
+        let EventQuery::Find { queries } = parse("find PaxosSlotEntry and { eq(slotNum, 1); or { server(0); after(10) } }") else {
+            panic!("Expected a find query");
+        };
+        assert_eq!(queries[0].plan(&index), Some(vec![4, 10]));
+
+        // negations can't be planned, so only the type narrows things down
+        let EventQuery::Find { queries } = parse("find PaxosRequest not(server(1))") else {
+            panic!("Expected a find query");
+        };
+        assert_eq!(queries[0].plan(&index), Some(vec![3, 7, 11]));
+    }
+
+    #[test]
+    fn indexed_matches_scan() {
+        let events = slot_events();
+        let index = EventIndex::build(&events, &LogSchema::infer(&events));
+        let queries = [
+            "find any eq(slotStatus, \"CHOSEN\") union find PaxosRequest has(sequenceNum)",
+            "find PaxosSlotEntry or { eq(slotNum, 2); eq(slotNum, \"0\") } except find any server(1)",
+            "find any and { before(9); not(eq(slotNum, 1)) } | find any eq(slotStatus, \"ACCEPTED\")",
+            "count find any eq(sequenceNum, 7)",
+        ];
+
+        for query in queries {
+            let query = parse(query);
+            let scanned = query.eval(&events).expect("Eval should succeed");
+            let indexed = query.eval_indexed(&events, &index).expect("Eval should succeed");
+            assert_eq!(scanned, indexed);
+        }
+    }
+
+    #[test]
+    fn indexed_collection_items_match_scan() {
+        let events = log([
+            ("server0", AbstractObject::new("Prepare").with_prop("acceptors", vec![AbstractValue::from("server1"), AbstractValue::from("server2")])),
+            ("server1", AbstractObject::new("Prepare").with_prop("acceptors", vec![AbstractValue::from("server2")])),
+        ]);
+        let index = EventIndex::build(&events, &LogSchema::infer(&events));
+        assert!(index.is_path_indexed("acceptors.1"));
+
+        for query in ["find Prepare eq(acceptors.1, \"server2\")", "find any eq(acceptors.0, \"server2\")"] {
+            let query = parse(query);
+            let scanned = query.eval(&events).expect("Eval should succeed");
+            let indexed = query.eval_indexed(&events, &index).expect("Eval should succeed");
+            assert!(!scanned.is_empty());
+            assert_eq!(scanned, indexed);
+        }
+    }
+
+    #[test]
+    fn pieces_match_scan() {
+        let events = slot_events();
+        let index = EventIndex::build(&events, &LogSchema::infer(&events));
+        let query = parse("find PaxosSlotEntry or { eq(slotNum, 2); server(1) } | find any not(after(9))");
+        assert!(query.can_eval_in_pieces());
+
+        let mut searched_counts = Vec::new();
+        let results = query.run_in_pieces(&events, &index, None, 5, |_, searched| {
+            searched_counts.push(searched);
+            true
+        }).expect("Eval should succeed").expect("The search wasn't stopped");
+        assert_eq!(searched_counts, vec![5, 10, 12]);
+        assert_eq!(*results.matches(), query.eval(&events).expect("Eval should succeed"));
+
+        let stopped = query.run_in_pieces(&events, &index, None, 5, |_, _| false).expect("Eval should succeed");
+        assert!(stopped.is_none());
+
+        // refining an earlier query only keeps events that matched it
+        let within = BTreeSet::from([0, 1, 2, 8]);
+        let mut searched_counts = Vec::new();
+        let refined = query.run_in_pieces(&events, &index, Some(&within), 3, |_, searched| {
+            searched_counts.push(searched);
+            true
+        }).expect("Eval should succeed").expect("The search wasn't stopped");
+        assert_eq!(*refined.matches(), BTreeSet::from([1, 2, 8]));
+        // only the events being refined are searched
+        assert_eq!(searched_counts, vec![3, 4]);
+    }
+}
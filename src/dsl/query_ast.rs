@@ -1,18 +1,217 @@
+use std::fmt::{Display, Formatter};
+use time::{Duration, PrimitiveDateTime};
 use crate::ds_events::abstract_object::AbstractValue;
 use crate::ds_events::event::Event;
 use crate::dsl::filters::EventFilterError;
 
+/// a single statement entered into the query editor. At least one of the parts is present
+#[derive(Debug)]
+pub struct QueryStatement {
+    /// the query to run, if any
+    pub(crate) query: Option<EventQuery>,
+    /// which fields to show as columns for each event, if a select clause was given. An empty
+    /// projection shows the whole event
+    pub(crate) projection: Option<Vec<PropPath>>,
+}
+
+impl QueryStatement {
+    pub fn query(&self) -> Option<&EventQuery> {
+        self.query.as_ref()
+    }
+
+    pub fn projection(&self) -> Option<&Vec<PropPath>> {
+        self.projection.as_ref()
+    }
+
+    pub fn into_parts(self) -> (Option<EventQuery>, Option<Vec<PropPath>>) {
+        (self.query, self.projection)
+    }
+}
+
+/// what a rule expects of the events its query matches
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Expectation {
+    /// the query must not match any events
+    None,
+    /// the query must match at least one event
+    Some,
+}
+
+/// a named assertion about a log, e.g. `rule agree = none find ...`
+#[derive(Debug)]
+pub struct Rule {
+    /// the name reported when the rule passes or fails
+    pub(crate) name: String,
+    /// what the rule expects of the query's matches
+    pub(crate) expectation: Expectation,
+    /// the query to check
+    pub(crate) query: EventQuery,
+}
+
+/// a query whose matches are drawn in a color, e.g. `highlight chosen yellow = find ...`
+#[derive(Debug)]
+pub struct HighlightRule {
+    /// the name shown in the legend
+    pub(crate) name: String,
+    /// the name of the color, like `yellow` or `#ff8800`
+    pub(crate) color: String,
+    /// the query whose matches are highlighted
+    pub(crate) query: EventQuery,
+}
+
 /// top level query object
 #[derive(Debug)]
 pub enum EventQuery {
     /// find a query or queries
     Find {
-        /// 1 or more event queries to apply in order
+        /// 1 or more event queries to apply in order. Each query's matches are combined with the
+        /// matches before it using its combinator
         queries: Vec<FindEventNode>
     },
-    // TODO
-    // EXISTS - is there a single event that matches the params
-    // COUNT - just display how many events there are that match this
+    /// a pipeline of queries, where each stage runs over the matches of the stage before it
+    Pipeline {
+        /// 2 or more stages to run in order
+        stages: Vec<EventQuery>
+    },
+    /// finds chains of events that happen one after another
+    Sequence(SequenceQuery),
+    /// finds tuples of events whose fields relate to each other
+    Join(JoinQuery),
+    /// is there a single event that matches the query
+    Exists(Box<EventQuery>),
+    /// just display how many events there are that match the query
+    Count(Box<EventQuery>),
+    /// summarizes the events that match the query
+    Aggregate {
+        /// the query whose matches are summarized
+        source: Box<EventQuery>,
+        /// how to summarize the matches
+        aggregation: Aggregation,
+    },
+}
+
+/// how a step in a sequence relates to the steps before it
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StepKind {
+    /// the step must happen after the previous matched step
+    FollowedBy,
+    /// the step must not happen after the previous matched step
+    NotFollowedBy,
+}
+
+/// a single step in a sequence query
+#[derive(Debug)]
+pub struct SequenceStep {
+    /// how this step relates to the steps before it
+    pub(crate) kind: StepKind,
+    /// the events that satisfy this step
+    pub(crate) node: FindEventNode,
+}
+
+/// finds chains of events, starting with an event that matches `first` and followed by events
+/// matching each step in order
+#[derive(Debug)]
+pub struct SequenceQuery {
+    /// the events that can start a chain
+    pub(crate) first: FindEventNode,
+    /// 1 or more steps that continue the chain
+    pub(crate) steps: Vec<SequenceStep>,
+    /// the most events that can be between one step and the next, if limited
+    pub(crate) within: Option<usize>,
+    /// if true, every event in the chain must come from the same node
+    pub(crate) same_node: bool,
+    /// paths that must have the same value in every event of the chain
+    pub(crate) same_values: Vec<PropPath>,
+}
+
+/// names the events that match a find query, so conditions can refer to them
+#[derive(Debug)]
+pub struct JoinBinding {
+    /// the name conditions use to refer to this event
+    pub(crate) alias: String,
+    /// the events that can be bound to the alias
+    pub(crate) node: FindEventNode,
+}
+
+/// one side of a join condition
+#[derive(Debug)]
+pub enum JoinOperand {
+    /// the value at a path of a bound event, e.g. `s.payload`
+    Field {
+        alias: String,
+        path: PropPath,
+    },
+    /// the node that originated a bound event, e.g. `node(s)`
+    Node(String),
+    /// the id of a bound event, e.g. `id(s)`
+    Id(String),
+    /// a literal value
+    Literal(String),
+}
+
+impl JoinOperand {
+    /// the alias this operand refers to, if it refers to a bound event
+    pub fn alias(&self) -> Option<&str> {
+        match self {
+            JoinOperand::Field { alias, .. } | JoinOperand::Node(alias) | JoinOperand::Id(alias) => Some(alias),
+            JoinOperand::Literal(_) => None,
+        }
+    }
+}
+
+/// how the two sides of a join condition are compared. Ordering comparisons only hold between numbers
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum JoinComparison {
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// a condition that every tuple of a join must satisfy
+#[derive(Debug)]
+pub struct JoinCondition {
+    pub(crate) left: JoinOperand,
+    pub(crate) comparison: JoinComparison,
+    pub(crate) right: JoinOperand,
+}
+
+/// finds tuples of distinct events, one for each binding, that satisfy every condition
+#[derive(Debug)]
+pub struct JoinQuery {
+    /// 2 or more named events
+    pub(crate) bindings: Vec<JoinBinding>,
+    /// conditions relating the bound events
+    pub(crate) conditions: Vec<JoinCondition>,
+}
+
+/// what to group events by
+#[derive(Debug)]
+pub enum GroupKey {
+    /// the node that originated the event
+    Node,
+    /// the name of the event object
+    Type,
+    /// the value at the given path
+    Path(PropPath),
+}
+
+/// different ways we can summarize the events matched by a query
+#[derive(Debug)]
+pub enum Aggregation {
+    /// count events per group, or the number of distinct values of a path per group
+    GroupByCount {
+        key: GroupKey,
+        distinct: Option<PropPath>,
+    },
+    /// every distinct value of a path, with how many events had it
+    Distinct(PropPath),
+    /// the smallest numeric value of a path
+    Min(PropPath),
+    /// the largest numeric value of a path
+    Max(PropPath),
 }
 
 /// used to filter events
@@ -24,39 +223,65 @@ pub enum EventNameFilter {
     Named(String),
 }
 
+/// how the matches of a find query combine with the matches of the queries before it
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum SetCombinator {
+    /// keep events matched by either side
+    #[default]
+    Union,
+    /// keep events matched by both sides
+    Intersect,
+    /// keep events matched by the left side, but not the right
+    Except,
+}
+
 /// finds a
 #[derive(Debug)]
 pub struct FindEventNode {
+    /// how this query combines with the queries before it. Ignored for the first query
+    pub(crate) combinator: SetCombinator,
     /// events to look for
     pub(crate) event_type: EventNameFilter,
     /// predicates to apply to each event
     pub(crate) operator: Operator,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PropPath {
     /// a basic dot separated path  
     pub(crate) segments: Vec<String>
 }
 
+impl Display for PropPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.segments.join("."))
+    }
+}
+
 impl PropPath {
+    /// the value at this path in the event. Segments after a collection are item indexes, like
+    /// `acceptors.0`
     pub fn lookup_value<'ev>(&self, event: &'ev Event) -> Result<&'ev AbstractValue, EventFilterError> {
         let mut current_prop_map = Some(event.event_obj().props());
+        let mut current_items: Option<&Vec<AbstractValue>> = None;
         let mut current_value: Option<&AbstractValue> = None;
         for segment in &self.segments {
             
-            if current_prop_map.is_none() {
+            let next_value = match (current_prop_map, current_items) {
+                (Some(prop_map), _) => prop_map.get(segment),
+                (None, Some(items)) => segment.parse::<usize>().ok().and_then(|idx| items.get(idx)),
                 // we can't keep going...
-                return Err(EventFilterError::KeyNotFound)
-            }
+                (None, None) => return Err(EventFilterError::KeyNotFound),
+            };
             
-            match current_prop_map.unwrap().get(segment) {
+            match next_value {
                 None => {
                     // if this key doesn't exist then we're done
                     return Err(EventFilterError::KeyNotFound)
                 }
                 Some(value) => {
                     current_value = Some(value);
+                    current_items = None;
                     match value {
                         AbstractValue::Object(obj) => {
                             current_prop_map = Some(obj.props());
@@ -64,6 +289,10 @@ impl PropPath {
                         AbstractValue::Map(map) => {
                             current_prop_map = Some(map)
                         }
+                        AbstractValue::Collection(items) => {
+                            current_prop_map = None;
+                            current_items = Some(items);
+                        }
                         _ => current_prop_map = None
                     }
                 }
@@ -90,6 +319,20 @@ pub enum Operator {
     After(usize),
     /// find all events before the given event id
     Before(usize),
+    /// find all events that occurred at or after the given time
+    AtOrAfter(PrimitiveDateTime),
+    /// find all events that occurred at or before the given time
+    AtOrBefore(PrimitiveDateTime),
+    /// find all events that occurred between the two times, inclusive
+    BetweenTimes {
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+    },
+    /// find all events that occurred within a window of time around the given event id
+    Within {
+        window: Duration,
+        anchor: usize,
+    },
 
     /// negates an operator
     Not(Box<Operator>),
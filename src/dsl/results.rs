@@ -0,0 +1,146 @@
+use std::collections::BTreeSet;
+use crate::ds_events::event::Event;
+use crate::ds_events::index::EventIndex;
+use crate::dsl::aggregate::QueryTable;
+use crate::dsl::filters::{lookup_event, EventFilterError};
+use crate::dsl::issues::{MismatchCounts, QueryIssue};
+use crate::dsl::query_ast::EventQuery;
+
+/// everything produced by running a query over the log
+#[derive(Debug, Default)]
+pub struct QueryResults {
+    /// every event that matched the query
+    matches: BTreeSet<usize>,
+    /// events that matched together, like the chains of a sequence or the tuples of a join
+    groups: Vec<Vec<usize>>,
+    /// the summary of the matches, if the query produces one
+    table: Option<QueryTable>,
+    /// the events each comparison couldn't compare
+    mismatches: MismatchCounts,
+}
+
+impl QueryResults {
+    pub fn matches(&self) -> &BTreeSet<usize> {
+        &self.matches
+    }
+
+    pub fn groups(&self) -> &Vec<Vec<usize>> {
+        &self.groups
+    }
+
+    pub fn table(&self) -> Option<&QueryTable> {
+        self.table.as_ref()
+    }
+
+    /// describes the comparisons that events couldn't be compared by while the query ran
+    pub fn issues(&self) -> Vec<QueryIssue> {
+        self.mismatches.issues()
+    }
+
+    pub fn into_parts(self) -> (BTreeSet<usize>, Vec<Vec<usize>>, Option<QueryTable>) {
+        (self.matches, self.groups, self.table)
+    }
+}
+
+impl EventQuery {
+    /// runs this query over the whole log
+    pub fn run(&self, log: &[Event]) -> Result<QueryResults, EventFilterError> {
+        self.run_with_index(log, None, None)
+    }
+
+    /// runs this query over the whole log, using the index to skip events that can't match
+    pub fn run_indexed(&self, log: &[Event], index: &EventIndex) -> Result<QueryResults, EventFilterError> {
+        self.run_with_index(log, Some(index), None)
+    }
+
+    /// runs this query like [`EventQuery::run_indexed`], but searches the log `piece_size` events
+    /// at a time when it can. After each piece, `progress` is given the events that matched in it
+    /// and how many events have been searched so far, and can return false to stop the search, in
+    /// which case `None` is returned. Queries that can't be evaluated in pieces are run all at once.
+    /// If `within` is given, only those events are searched, `piece_size` at a time, so the query
+    /// refines an earlier one
+    pub fn run_in_pieces<ProgressT>(&self, log: &[Event], index: &EventIndex, within: Option<&BTreeSet<usize>>, piece_size: usize, mut progress: ProgressT) -> Result<Option<QueryResults>, EventFilterError>
+        where ProgressT: FnMut(&BTreeSet<usize>, usize) -> bool
+    {
+        if !self.can_eval_in_pieces() {
+            return self.run_with_index(log, Some(index), within).map(Some);
+        }
+
+        let mut matches = BTreeSet::new();
+        let mut mismatches = MismatchCounts::default();
+        let mut searched = 0;
+        let mut add_piece = |piece_matches: BTreeSet<usize>, piece_len: usize| {
+            searched += piece_len;
+            let keep_going = progress(&piece_matches, searched);
+            matches.extend(piece_matches);
+            keep_going
+        };
+
+        let piece_size = piece_size.max(1);
+        match within {
+            Some(within) => {
+                // only the events being refined are searched, so the progress is out of them
+                let candidates = within.iter()
+                    .filter_map(|id| lookup_event(log, *id))
+                    .collect::<Vec<_>>();
+                for piece in candidates.chunks(piece_size) {
+                    if !add_piece(self.eval_candidates(piece, log, &mut mismatches)?, piece.len()) {
+                        return Ok(None);
+                    }
+                }
+            }
+            None => {
+                for piece in log.chunks(piece_size) {
+                    if !add_piece(self.eval_indexed_within(log, index, piece, &mut mismatches)?, piece.len()) {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+
+        let table = self.summarize(&matches, &[], log);
+        Ok(Some(QueryResults { matches, groups: Vec::new(), table, mismatches }))
+    }
+
+    fn run_with_index(&self, log: &[Event], index: Option<&EventIndex>, within: Option<&BTreeSet<usize>>) -> Result<QueryResults, EventFilterError> {
+        let candidates = match within {
+            Some(within) => within.iter()
+                .filter_map(|id| lookup_event(log, *id))
+                .collect::<Vec<_>>(),
+            None => log.iter().collect::<Vec<_>>(),
+        };
+        let mut mismatches = MismatchCounts::default();
+        let Some(groups) = self.find_groups(&candidates, log, &mut mismatches)? else {
+            let matches = match (index, within) {
+                (Some(index), None) => self.eval_indexed_within(log, index, log, &mut mismatches)?,
+                _ => self.eval_candidates(&candidates, log, &mut mismatches)?,
+            };
+            let table = self.summarize(&matches, &[], log);
+            return Ok(QueryResults { matches, groups: Vec::new(), table, mismatches });
+        };
+
+        let matches = groups.iter()
+            .flatten()
+            .copied()
+            .collect();
+        let table = match self {
+            EventQuery::Sequence(sequence) => Some(sequence.chain_table(&groups)),
+            EventQuery::Join(join) => Some(join.tuple_table(&groups)),
+            _ => self.summarize(&matches, &groups, log),
+        };
+        Ok(QueryResults { matches, groups, table, mismatches })
+    }
+
+    /// the events that matched together, for sequences and joins and the queries that summarize
+    /// them, or none if this query matches events one at a time
+    fn find_groups(&self, candidates: &[&Event], log: &[Event], mismatches: &mut MismatchCounts) -> Result<Option<Vec<Vec<usize>>>, EventFilterError> {
+        match self {
+            EventQuery::Sequence(sequence) => sequence.find_chains(candidates, log).map(Some),
+            EventQuery::Join(join) => join.find_tuples(candidates, log, mismatches).map(Some),
+            EventQuery::Exists(source) | EventQuery::Count(source) | EventQuery::Aggregate { source, .. } => {
+                source.find_groups(candidates, log, mismatches)
+            }
+            EventQuery::Find { .. } | EventQuery::Pipeline { .. } => Ok(None),
+        }
+    }
+}
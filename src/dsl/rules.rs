@@ -0,0 +1,108 @@
+use crate::ds_events::err::AppError;
+use crate::ds_events::event::Event;
+use crate::dsl::filters::EventFilterError;
+use crate::dsl::diagnostics::parse_complete;
+use crate::dsl::parser::parse_rules;
+use crate::dsl::query_ast::{Expectation, Rule};
+
+/// the result of checking a rule against a log
+#[derive(Debug)]
+pub struct RuleOutcome {
+    /// true if the log satisfies the rule
+    pub passed: bool,
+    /// the events that broke the rule, grouped the same way the query groups its matches
+    pub counterexamples: Vec<Vec<usize>>,
+}
+
+impl Rule {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn check(&self, log: &[Event]) -> Result<RuleOutcome, EventFilterError> {
+        let results = self.query.run(log)?;
+        let outcome = match self.expectation {
+            Expectation::None => {
+                let counterexamples = if results.groups().is_empty() {
+                    results.matches().iter().map(|id| vec![*id]).collect()
+                } else {
+                    results.groups().clone()
+                };
+
+                RuleOutcome { passed: counterexamples.is_empty(), counterexamples }
+            }
+            Expectation::Some => RuleOutcome { passed: !results.matches().is_empty(), counterexamples: Vec::new() },
+        };
+
+        Ok(outcome)
+    }
+}
+
+/// parses a rules file. Lines starting with `#` are comments
+pub fn load_rules(text: &str) -> Result<Vec<Rule>, AppError> {
+    // blank out comments instead of removing them so errors point at the right line
+    let without_comments = text.lines()
+        .map(|line| if line.trim_start().starts_with('#') { "" } else { line })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    parse_complete(&without_comments, parse_rules)
+        .map_err(|diagnostic| AppError::new(format!("error while parsing rules: {}", diagnostic)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds_events::event::Event;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::dsl::rules::load_rules;
+
+    const RULES: &str = "
+    # no two servers choose different commands for the same slot
+    rule chosen_agree = none find PaxosSlotEntry eq(slotStatus, \"CHOSEN\") as a,
+        PaxosSlotEntry eq(slotStatus, \"CHOSEN\") as b
+        where a.slotNum == b.slotNum and a.command != b.command and id(a) < id(b)
+
+    # ballots never go backwards on a server
+    rule ballots_monotonic = none find PaxosSlotEntry as a, PaxosSlotEntry as b
+        where node(a) == node(b) and id(a) < id(b) and b.ballot < a.ballot
+
+    rule something_chosen = some find any eq(slotStatus, \"CHOSEN\")
+    ";
+
+    fn slot_events() -> Vec<Event> {
+        let entries = [
+            ("server1", 1, "CHOSEN", "put-a", 2),
+            ("server2", 1, "CHOSEN", "put-b", 1),
+            ("server1", 2, "CHOSEN", "put-c", 3),
+        ];
+
+        log(entries.map(|(originator, slot, status, command, ballot)| {
+            let obj = slot_entry(slot)
+                .with_prop("slotStatus", status)
+                .with_prop("command", command)
+                .with_prop("ballot", ballot);
+            (originator, obj)
+        }))
+    }
+
+    #[test]
+    fn checks_rules() {
+        let rules = load_rules(RULES).expect("Rules should parse");
+        assert_eq!(rules.len(), 3);
+
+        let events = slot_events();
+        let outcomes = rules.iter()
+            .map(|rule| rule.check(&events).expect("Rule should evaluate"))
+            .collect::<Vec<_>>();
+
+        assert!(!outcomes[0].passed);
+        assert_eq!(outcomes[0].counterexamples, vec![vec![0, 1]]);
+        assert!(outcomes[1].passed);
+        assert!(outcomes[2].passed);
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        assert!(load_rules("rule a = some find any has(x)\nrule b = sometimes find any has(x)").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::ds_events::event::Event;
+use crate::dsl::aggregate::QueryTable;
+use crate::dsl::filters::{EventFilter, EventFilterError, EventSequenceQuery};
+use crate::dsl::query_ast::{EventNameFilter, FindEventNode, SequenceQuery, StepKind};
+
+impl SequenceQuery {
+    /// finds every chain that starts with one of the candidates. Each chain holds the ids of the events
+    /// that matched the first event and each [`StepKind::FollowedBy`] step, in order
+    pub fn find_chains(&self, candidates: &[&Event], log: &[Event]) -> Result<Vec<Vec<usize>>, EventFilterError> {
+        let mut chains = Vec::new();
+        for (start_pos, start) in candidates.iter().enumerate() {
+            if !self.first.test(start, log)? {
+                continue;
+            }
+
+            // every event in the chain must agree with the starting event on these paths
+            if self.same_values.iter().any(|path| path.lookup_value(start).is_err()) {
+                continue;
+            }
+
+            if let Some(chain) = self.follow_chain(start, start_pos, candidates, log)? {
+                chains.push(chain);
+            }
+        }
+
+        Ok(chains)
+    }
+
+    /// tries to complete the chain starting at the given event. Each step takes the first event that
+    /// satisfies it after the previous step. The parser makes sure `not followed by` steps have a
+    /// `within` limit, so they never scan the rest of the log
+    fn follow_chain(&self, start: &Event, start_pos: usize, candidates: &[&Event], log: &[Event]) -> Result<Option<Vec<usize>>, EventFilterError> {
+        let mut chain = vec![start.id()];
+        let mut last_pos = start_pos;
+        for step in &self.steps {
+            let last_id = candidates[last_pos].id();
+            let mut step_match = None;
+            let later_events = candidates.iter()
+                .enumerate()
+                .skip(last_pos + 1)
+                .take_while(|(_, event)| self.within.is_none_or(|within| event.id() - last_id <= within));
+            for (pos, event) in later_events {
+                if self.step_matches(&step.node, start, event, log)? {
+                    step_match = Some((pos, event));
+                    break;
+                }
+            }
+
+            match (step.kind, step_match) {
+                (StepKind::FollowedBy, Some((pos, event))) => {
+                    chain.push(event.id());
+                    last_pos = pos;
+                }
+                (StepKind::NotFollowedBy, None) => {}
+                _ => return Ok(None),
+            }
+        }
+
+        Ok(Some(chain))
+    }
+
+    fn step_matches(&self, node: &FindEventNode, start: &Event, event: &Event, log: &[Event]) -> Result<bool, EventFilterError> {
+        if self.same_node && event.originator() != start.originator() {
+            return Ok(false);
+        }
+
+        let same_values = self.same_values.iter()
+            .all(|path| {
+                match (path.lookup_value(start), path.lookup_value(event)) {
+                    (Ok(start_value), Ok(value)) => start_value == value,
+                    _ => false,
+                }
+            });
+
+        if !same_values {
+            return Ok(false);
+        }
+        node.test(event, log)
+    }
+
+    /// a table with one row per chain, and one column per event in the chain
+    pub fn chain_table(&self, chains: &[Vec<usize>]) -> QueryTable {
+        let headers = [&self.first].into_iter()
+            .chain(self.steps.iter()
+                .filter(|step| step.kind == StepKind::FollowedBy)
+                .map(|step| &step.node))
+            .enumerate()
+            .map(|(idx, node)| {
+                match &node.event_type {
+                    EventNameFilter::Any => format!("{}: any", idx + 1),
+                    EventNameFilter::Named(name) => format!("{}: {}", idx + 1, name),
+                }
+            });
+
+        let mut table = QueryTable::new(headers);
+        for chain in chains {
+            table.push_row(chain.iter().map(|id| id.to_string()));
+        }
+
+        table
+    }
+}
+
+impl EventSequenceQuery for SequenceQuery {
+    type ResT = Vec<Vec<usize>>;
+
+    fn eval(&self, events: &[Event]) -> Result<Self::ResT, EventFilterError> {
+        let candidates = events.iter().collect::<Vec<_>>();
+        self.find_chains(&candidates, events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds_events::event::Event;
+    use crate::ds_events::fixtures::{log, slot_entry};
+    use crate::dsl::filters::EventSequenceQuery;
+    use crate::dsl::parser::{parse_event_query, parse_statement};
+    use crate::dsl::query_ast::EventQuery;
+
+    /// slots 0 and 1 are accepted by server1, and only slot 0 is ever chosen
+    fn slot_events() -> Vec<Event> {
+        let entries = [
+            ("server1", 0, "ACCEPTED"),
+            ("server1", 1, "ACCEPTED"),
+            ("server2", 1, "CHOSEN"),
+            ("server1", 0, "CHOSEN"),
+        ];
+
+        log(entries.map(|(originator, slot, status)| (originator, slot_entry(slot).with_prop("slotStatus", status))))
+    }
+
+    fn chains(query_text: &str) -> Vec<Vec<usize>> {
+        let (remaining, query) = parse_event_query(query_text).expect("Parsing should succeed");
+        assert!(remaining.is_empty(), "unparsed input: {}", remaining);
+        let EventQuery::Sequence(sequence) = query else {
+            panic!("Expected a sequence query");
+        };
+        sequence.eval(&slot_events()).expect("Eval should succeed")
+    }
+
+    #[test]
+    fn followed_by_with_same_values() {
+        let query = "find any eq(slotStatus, \"ACCEPTED\") then any eq(slotStatus, \"CHOSEN\") with same slotNum";
+        assert_eq!(chains(query), vec![vec![0, 3], vec![1, 2]]);
+    }
+
+    #[test]
+    fn followed_by_on_same_node_within() {
+        let query = "find any eq(slotStatus, \"ACCEPTED\") then any eq(slotStatus, \"CHOSEN\") within 2 events on same node with same slotNum";
+        assert_eq!(chains(query), Vec::<Vec<usize>>::new());
+
+        let query = "find any eq(slotStatus, \"ACCEPTED\") then any eq(slotStatus, \"CHOSEN\") within 3 events on same node with same slotNum";
+        assert_eq!(chains(query), vec![vec![0, 3]]);
+    }
+
+    #[test]
+    fn not_followed_by() {
+        let query = "find any eq(slotStatus, \"ACCEPTED\") not followed by any eq(slotStatus, \"CHOSEN\") within 10 events on same node with same slotNum";
+        assert_eq!(chains(query), vec![vec![1]]);
+
+        // without a limit, every start would scan the rest of the log
+        let query = "find any eq(slotStatus, \"ACCEPTED\") not followed by any eq(slotStatus, \"CHOSEN\")";
+        assert!(parse_statement(query).is_err());
+    }
+
+    #[test]
+    fn counts_chains() {
+        let query = "count find any eq(slotStatus, \"ACCEPTED\") then any eq(slotStatus, \"CHOSEN\") with same slotNum";
+        let (_, query) = parse_event_query(query).expect("Parsing should succeed");
+        let results = query.run(&slot_events()).expect("Query should run");
+        assert_eq!(results.table().expect("count has a table").rows(), &[vec!["2".to_string()]]);
+    }
+}
@@ -1,6 +1,10 @@
 use crate::app::{App, AppResult, FocusedWindow};
+use crate::app::details_state::DetailQuery;
+use crate::app::search_state::SearchState;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use crate::dsl::parser::parse_event_query;
+use tui_textarea::CursorMove;
+use crate::dsl::diagnostics::QueryDiagnostic;
+use crate::dsl::parser::{parse_highlight, parse_statement};
 
 /// Handles the key events and updates the state of [`App`].
 pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
@@ -15,15 +19,54 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
                 app.focused_window = FocusedWindow::EventList;
                 return Ok(())
             }
+            KeyCode::Down => {
+                app.focus_event_details();
+                return Ok(())
+            }
             KeyCode::Char('c') | KeyCode::Char('C') => {
                 app.quit();
                 return Ok(())
             }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                app.toggle_schema_browser();
+                return Ok(())
+            }
+            KeyCode::Char('l') | KeyCode::Char('L') => {
+                app.toggle_query_picker();
+                return Ok(())
+            }
             _ => {}
         }
     }
     
+    // the search prompts take every key while they're open
+    if app.search_state.is_prompt_open() {
+        match search_prompt_key(key_event, &mut app.search_state) {
+            PromptKey::Edited => app.update_search(),
+            PromptKey::Committed => app.commit_search(),
+            PromptKey::Cancelled => app.cancel_search(),
+            PromptKey::Ignored => {}
+        }
+        return Ok(());
+    }
+    if app.details_state.search().is_prompt_open() {
+        return details_search_handle_key_events(key_event, app);
+    }
+    if app.goto_state.is_prompt_open() {
+        return goto_prompt_handle_key_events(key_event, app);
+    }
+    
     match key_event.code {
+        // close the completion popup if it's open
+        KeyCode::Esc if app.focused_window.is_filter_list() && app.completion_state.is_active() => {
+            app.completion_state.close();
+            Ok(())
+        }
+        // stop the query running in the background, if there is one
+        KeyCode::Esc if app.is_query_running() => {
+            app.cancel_query();
+            Ok(())
+        }
         // Exit application on `ESC` or `q`
         KeyCode::Esc => {
             app.quit();
@@ -37,6 +80,15 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
                 FocusedWindow::QueryEditor => {
                     query_window_handle_key_events(key_event, app)
                 }
+                FocusedWindow::SchemaBrowser => {
+                    schema_browser_handle_key_events(key_event, app)
+                }
+                FocusedWindow::QueryPicker => {
+                    query_picker_handle_key_events(key_event, app)
+                }
+                FocusedWindow::EventDetails => {
+                    event_details_handle_key_events(key_event, app)
+                }
             }
         }
     }
@@ -46,31 +98,33 @@ fn events_list_handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResul
     match key_event.code {
         // select up and down the event list
         KeyCode::Down => {
-            app.event_list_state.next_event();
+            app.active_list_state().next_event();
         }
         KeyCode::PageDown => {
-            app.event_list_state.next_page();
+            app.active_list_state().next_page();
         }
         KeyCode::Up => {
-            app.event_list_state.prev_event();
+            app.active_list_state().prev_event();
         }
         KeyCode::PageUp => {
-            app.event_list_state.prev_page();
+            app.active_list_state().prev_page();
         }
         KeyCode::Home => {
-            app.event_list_state.go_home();
+            app.active_list_state().go_home();
         }
         KeyCode::End => {
-            app.event_list_state.go_end();
+            app.active_list_state().go_end();
         }
         KeyCode::Enter => {
-            app.select_event(app.event_list_state.selected_event);
+            if let Some(event_idx) = app.event_at_cursor() {
+                app.select_event(event_idx);
+            }
         }
         KeyCode::Char('c') => {
             app.clear_selected_event()
         }
         KeyCode::Char('n') => {
-            if app.filter_state.has_active_filter() {
+            if app.can_navigate() {
                 // get the next one and focus it
                 app.nav_next();
             } else {
@@ -78,52 +132,286 @@ fn events_list_handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResul
             }
         }
         KeyCode::Char('N') => {
-            if app.filter_state.has_active_filter() {
-                // get the next one and focus it
+            if app.can_navigate() {
+                // get the previous one and focus it
                 app.nav_prev();
             } else {
                 app.message_state.push("No query is active. Start a query to begin navigating")
             }
         }
+        KeyCode::Char(']') => {
+            app.nav_next_in_group();
+        }
+        KeyCode::Char('[') => {
+            app.nav_prev_in_group();
+        }
+        KeyCode::Char('s') => {
+            app.cycle_sort_column();
+        }
+        KeyCode::Char('x') => {
+            app.toggle_explain();
+        }
+        KeyCode::Char('S') => {
+            app.toggle_sort_direction();
+        }
+        KeyCode::Char('u') => {
+            app.undo_filter();
+        }
+        KeyCode::Char('r') => {
+            app.redo_filter();
+        }
+        KeyCode::Char('U') => {
+            app.undo_all_filters();
+        }
+        KeyCode::Char('h') => {
+            app.cycle_nav_highlight();
+        }
+        KeyCode::Char('H') => {
+            app.remove_nav_highlight();
+        }
+        KeyCode::Char('f') => {
+            app.toggle_filter_view();
+        }
+        KeyCode::Char('+') => {
+            app.grow_filter_context();
+        }
+        KeyCode::Char('-') => {
+            app.shrink_filter_context();
+        }
+        KeyCode::Char('/') => {
+            app.open_search();
+        }
+        KeyCode::Char('g') => {
+            app.goto_state.open();
+        }
+        KeyCode::Char('m') => {
+            app.toggle_marked_event();
+        }
+        KeyCode::Char('d') => {
+            app.history_state.toggle_shown_in_list();
+        }
+        KeyCode::Char('?') => {
+            app.help_visible = !app.help_visible;
+        }
         // Other handlers you could add here.
         _ => {}
     }
     Ok(())
 }
 
-fn query_window_handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+/// what a key did to a search prompt
+enum PromptKey {
+    /// the text or how it's matched changed
+    Edited,
+    Committed,
+    Cancelled,
+    Ignored,
+}
+
+/// edits the search prompt with the key. Committing and cancelling are left to the caller
+fn search_prompt_key(key_event: KeyEvent, search: &mut SearchState) -> PromptKey {
     if key_event.modifiers == KeyModifiers::ALT {
         match key_event.code {
-            KeyCode::Enter => {
-                // TODO parse query
-                let lines_buffer = app.query_text_area.lines().join("\n");
-                match parse_event_query(&lines_buffer) {
-                    Ok((_, event)) => {
-                        app.push_new_filter_state(event);
-                    }
-                    Err(err) => {
-                        let msg = format!("error while parsing query: {}", err);
-                        app.message_state.push(msg);
-                    }
-                }
-                return Ok(());
-            },
-            _ => {}
+            KeyCode::Char('r') | KeyCode::Char('R') => search.toggle_regex(),
+            KeyCode::Char('c') | KeyCode::Char('C') => search.toggle_case_sensitive(),
+            _ => return PromptKey::Ignored,
         }
+        return PromptKey::Edited;
     }
     
-    if key_event.modifiers == KeyModifiers::CONTROL {
+    match key_event.code {
+        KeyCode::Enter => PromptKey::Committed,
+        KeyCode::Esc => PromptKey::Cancelled,
+        KeyCode::Backspace => {
+            search.pop_char();
+            PromptKey::Edited
+        }
+        KeyCode::Char(c) => {
+            search.push_char(c);
+            PromptKey::Edited
+        }
+        _ => PromptKey::Ignored,
+    }
+}
+
+fn details_search_handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    let Some(event) = app.selected_event.and_then(|event_idx| app.events.get(event_idx)) else {
+        app.details_state.search_mut().cancel();
+        return Ok(());
+    };
+    
+    let object = event.event_obj();
+    match search_prompt_key(key_event, app.details_state.search_mut()) {
+        PromptKey::Edited => app.details_state.update_search(object),
+        PromptKey::Committed => {
+            app.details_state.search_mut().commit();
+        }
+        PromptKey::Cancelled => app.details_state.cancel_search(object),
+        PromptKey::Ignored => {}
+    }
+    Ok(())
+}
+
+fn event_details_handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    let Some(event) = app.selected_event.and_then(|event_idx| app.events.get(event_idx)) else {
+        return Ok(());
+    };
+    
+    let object = event.event_obj();
+    let details = &mut app.details_state;
+    match key_event.code {
+        KeyCode::Down => details.next_row(object),
+        KeyCode::Up => details.prev_row(object),
+        KeyCode::PageDown => details.next_page(object),
+        KeyCode::PageUp => details.prev_page(object),
+        KeyCode::Home => details.go_home(object),
+        KeyCode::End => details.go_end(object),
+        KeyCode::Enter | KeyCode::Char(' ') => details.toggle_expanded(object),
+        KeyCode::Right => details.expand(object),
+        KeyCode::Left => details.collapse(object),
+        KeyCode::Char('/') => details.open_search(),
+        KeyCode::Char('e') => app.query_from_details(DetailQuery::Eq, false),
+        KeyCode::Char('E') => app.query_from_details(DetailQuery::Eq, true),
+        KeyCode::Char('h') => app.query_from_details(DetailQuery::Has, false),
+        KeyCode::Char('H') => app.query_from_details(DetailQuery::Has, true),
+        KeyCode::Char('n') | KeyCode::Char('N') => {
+            let forward = key_event.code == KeyCode::Char('n');
+            if details.search().pattern().is_none() {
+                app.message_state.push("Press / to search the event's details");
+            } else if !details.search_next(object, forward) {
+                app.message_state.push("The search text isn't found in this event");
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn goto_prompt_handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Enter => {
+            app.commit_goto();
+        }
+        KeyCode::Esc => {
+            app.goto_state.close();
+        }
+        KeyCode::Backspace => {
+            app.goto_state.pop_char();
+        }
+        KeyCode::Char(c) => {
+            app.goto_state.push_char(c);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn schema_browser_handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Down => {
+            app.schema_state.next_row(&app.schema);
+        }
+        KeyCode::Up => {
+            app.schema_state.prev_row();
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            app.schema_state.toggle_expanded(&app.schema);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn query_picker_handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Down => {
+            app.picker_state.next(app.library.macros().len());
+        }
+        KeyCode::Up => {
+            app.picker_state.prev();
+        }
+        KeyCode::Enter => {
+            app.insert_picked_query();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn query_window_handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    if app.completion_state.is_active() {
         match key_event.code {
-            KeyCode::Backspace => {
-                // TODO delete everything
+            KeyCode::Tab => {
+                app.completion_state.accept(&mut app.query_text_area);
+                return Ok(());
             }
-            _ => {}
+            KeyCode::Down => {
+                app.completion_state.next();
+                return Ok(());
+            }
+            KeyCode::Up => {
+                app.completion_state.prev();
+                return Ok(());
+            }
+            _ => app.completion_state.close(),
         }
+    } else if key_event.code == KeyCode::Tab {
+        // ask for completions, and take the only one if there's no choice to make
+        app.completion_state.update(&app.query_text_area, &app.schema);
+        if app.completion_state.candidates().len() == 1 {
+            app.completion_state.accept(&mut app.query_text_area);
+        }
+        return Ok(());
     }
     
-    match key_event.code {
-        _ => app.query_text_area.input(key_event),
-    };
+    if key_event.modifiers == KeyModifiers::ALT && key_event.code == KeyCode::Enter {
+        let lines_buffer = app.query_text_area.lines().join("\n");
+        let expansion = match app.library.expand(&lines_buffer) {
+            Ok(expansion) => expansion,
+            Err(err) => {
+                app.message_state.push(format!("error while expanding named queries: {}", err));
+                return Ok(());
+            }
+        };
+        
+        // highlight rules are run on their own, without touching the active query
+        if expansion.text().split_whitespace().next() == Some("highlight") {
+            match parse_highlight(expansion.text()) {
+                Ok(rule) => {
+                    app.query_error = None;
+                    app.add_highlight(rule);
+                }
+                Err(diagnostic) => show_query_error(app, expansion.locate(&lines_buffer, diagnostic)),
+            }
+            return Ok(());
+        }
+        
+        match parse_statement(expansion.text()) {
+            Ok(statement) => {
+                app.query_error = None;
+                app.apply_statement(statement, &lines_buffer, true);
+            }
+            Err(diagnostic) => show_query_error(app, expansion.locate(&lines_buffer, diagnostic)),
+        }
+        return Ok(());
+    }
+    
+    let modified = app.query_text_area.input(key_event);
+    if modified && matches!(key_event.code, KeyCode::Char(_) | KeyCode::Backspace) {
+        app.completion_state.update(&app.query_text_area, &app.schema);
+    }
     
     Ok(())
 }
+
+/// reports a query that didn't parse, and moves the cursor onto the error. Errors inside of a named
+/// query point at where it was called
+fn show_query_error(app: &mut App, diagnostic: QueryDiagnostic) {
+    let msg = format!("error while parsing query: {} at line {} col {}", diagnostic.message(), diagnostic.line(), diagnostic.column());
+    app.message_state.push(msg);
+    app.query_text_area.move_cursor(CursorMove::Jump(
+        (diagnostic.line() - 1) as u16,
+        (diagnostic.column() - 1) as u16
+    ));
+    app.query_error = Some(diagnostic);
+}
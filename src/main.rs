@@ -1,31 +1,84 @@
-use std::fs::File;
+use std::fs::{self, File};
 use log_viz::app::{App, AppResult};
 use log_viz::event::{Event, EventHandler};
 use log_viz::handler::handle_key_events;
 use log_viz::tui::Tui;
 use std::io;
 use std::path::PathBuf;
-use clap::Parser;
+use std::process::ExitCode;
+use clap::{Parser, Subcommand};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 use log_viz::ds_events::parse_event_log;
+use log_viz::ds_events::err::AppError;
+use log_viz::ds_events::event::Event as LogEvent;
+use log_viz::ds_events::schema::LogSchema;
+use log_viz::dsl::filters::lookup_event;
+use log_viz::dsl::aggregate::QueryTable;
+use log_viz::dsl::parser::{parse_field_path, parse_statement};
+use log_viz::dsl::macros::QueryLibrary;
+use log_viz::dsl::rules::load_rules;
 
 #[derive(Debug, Parser)]
-#[command(author, version, about)]
+#[command(author, version, about, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// the file to visualize
-    filename: PathBuf
+    #[arg(required = true)]
+    filename: Option<PathBuf>,
+    /// run this query, print its results, and exit instead of opening the viewer
+    #[arg(short, long)]
+    query: Option<String>,
+    /// the project file of named queries to load. Defaults to `.logviz` in the working directory
+    #[arg(long)]
+    queries: Option<PathBuf>,
+    /// the field that tells apart what each node logs the state of, like `slotNum`. Each event's
+    /// changes are shown against the last event of its type from its node with the same value
+    #[arg(long)]
+    state_key: Option<String>,
 }
 
-fn main() -> AppResult<()> {
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// check a log against a file of rules, and exit non-zero if any rule fails
+    Check {
+        /// the file to check
+        filename: PathBuf,
+        /// the rules to check the log against
+        #[arg(short, long)]
+        rules: PathBuf,
+    },
+}
+
+fn main() -> AppResult<ExitCode> {
 
     let args = Args::parse();
-    let log_file = File::open(args.filename)?;
-    println!("Parsing logs...");
+    if let Some(Command::Check { filename, rules }) = args.command {
+        let passed = check_rules(filename, rules)?;
+        return Ok(if passed { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+    }
+    
+    let library = match QueryLibrary::find_project_file(args.queries) {
+        Some(project_path) => QueryLibrary::load(&project_path)?,
+        None => QueryLibrary::default(),
+    };
+    
+    let log_file = File::open(args.filename.expect("filename is required without a subcommand"))?;
+    eprintln!("Parsing logs...");
     let events = parse_event_log(log_file)?;
+    
+    if let Some(query_text) = args.query {
+        return print_query_results(&query_text, &library, &events);
+    }
 
     // Create an application.
-    let mut app = App::new(events);
+    let mut app = App::new(events).with_library(library);
+    if let Some(state_key) = args.state_key {
+        let key = parse_field_path(&state_key)
+            .map_err(|diagnostic| AppError::new(format!("invalid state key {}: {}", state_key, diagnostic)))?;
+        app = app.with_state_key(key);
+    }
 
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(io::stderr());
@@ -43,11 +96,111 @@ fn main() -> AppResult<()> {
             Event::Tick => app.tick(),
             Event::Key(key_event) => handle_key_events(key_event, &mut app)?,
             Event::Mouse(_) => {}
-            Event::Resize(_, _) => {}
+            Event::Resize(width, height) => tui.resize(width, height)?,
         }
     }
 
     // Exit the user interface.
     tui.exit()?;
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+/// evaluates a query over the events and prints what it found. A query that doesn't parse exits with 2
+fn print_query_results(query_text: &str, library: &QueryLibrary, events: &[LogEvent]) -> AppResult<ExitCode> {
+    let expansion = library.expand(query_text)?;
+    let statement = match parse_statement(expansion.text()) {
+        Ok(statement) => statement,
+        Err(diagnostic) => {
+            eprintln!("error while parsing query: {}", expansion.locate(query_text, diagnostic));
+            return Ok(ExitCode::from(2));
+        }
+    };
+    
+    let results = statement.query()
+        .map(|query| query.run(events))
+        .transpose()
+        .map_err(|err| AppError::new(format!("error while evaluating query: {}", err)))?;
+    
+    if let Some(query) = statement.query() {
+        let run_issues = results.iter().flat_map(|results| results.issues());
+        for issue in query.find_issues(&LogSchema::infer(events)).into_iter().chain(run_issues) {
+            eprintln!("warning: {}", issue);
+        }
+    }
+    
+    if let Some(table) = results.as_ref().and_then(|results| results.table()) {
+        print!("{}", table);
+        return Ok(ExitCode::SUCCESS);
+    }
+    
+    let matches = match results {
+        Some(results) => results.into_parts().0,
+        None => events.iter().map(|event| event.id()).collect(),
+    };
+    
+    let matching_events = matches.iter().filter_map(|id| lookup_event(events, *id));
+    match statement.projection() {
+        Some(columns) if !columns.is_empty() => {
+            let headers = ["id".to_string(), "node".to_string()].into_iter()
+                .chain(columns.iter().map(|column| column.to_string()));
+            let mut table = QueryTable::new(headers);
+            for event in matching_events {
+                let cells = [event.id().to_string(), event.originator().to_string()].into_iter()
+                    .chain(columns.iter().map(|column| {
+                        column.lookup_value(event)
+                            .map(|value| value.to_string())
+                            .unwrap_or_default()
+                    }));
+                table.push_row(cells);
+            }
+            print!("{}", table);
+        }
+        _ => {
+            for event in matching_events {
+                println!("{} {}: {}", event.id(), event.originator(), event.event_obj());
+            }
+        }
+    }
+    
+    Ok(ExitCode::SUCCESS)
+}
+
+/// the most counterexamples to print for a single failed rule
+const MAX_COUNTEREXAMPLES: usize = 20;
+
+/// checks every rule against the log, printing which ones failed. Returns true if every rule passed
+fn check_rules(log_path: PathBuf, rules_path: PathBuf) -> AppResult<bool> {
+    let rules = load_rules(&fs::read_to_string(rules_path)?)?;
+    let events = parse_event_log(File::open(log_path)?)?;
+    
+    let mut all_passed = true;
+    for rule in &rules {
+        let outcome = rule.check(&events)
+            .map_err(|err| AppError::new(format!("error while evaluating rule {}: {}", rule.name(), err)))?;
+        
+        if outcome.passed {
+            println!("PASS {}", rule.name());
+            continue;
+        }
+        
+        all_passed = false;
+        if outcome.counterexamples.is_empty() {
+            println!("FAIL {}: no events matched", rule.name());
+            continue;
+        }
+        
+        println!("FAIL {}: {} counterexample(s)", rule.name(), outcome.counterexamples.len());
+        for counterexample in outcome.counterexamples.iter().take(MAX_COUNTEREXAMPLES) {
+            let ids = counterexample.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>();
+            println!("  events {}", ids.join(", "));
+        }
+        
+        if outcome.counterexamples.len() > MAX_COUNTEREXAMPLES {
+            println!("  ...and {} more", outcome.counterexamples.len() - MAX_COUNTEREXAMPLES);
+        }
+    }
+    
+    Ok(all_passed)
 }
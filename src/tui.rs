@@ -4,6 +4,7 @@ use crate::ui;
 use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::Backend;
+use ratatui::layout::Rect;
 use ratatui::Terminal;
 use std::io;
 use std::panic;
@@ -55,6 +56,12 @@ impl<B: Backend> Tui<B> {
         Ok(())
     }
 
+    /// Resizes the terminal interface, so the next draw redraws everything at the new size.
+    pub fn resize(&mut self, width: u16, height: u16) -> AppResult<()> {
+        self.terminal.resize(Rect::new(0, 0, width, height))?;
+        Ok(())
+    }
+
     /// Resets the terminal interface.
     ///
     /// This function is also used for the panic hook to revert
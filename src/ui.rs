@@ -2,6 +2,11 @@
 pub mod event_list;
 mod query_window;
 mod event_details;
+mod query_results;
+mod schema_browser;
+mod query_picker;
+mod explain_view;
+mod diff_view;
 
 use ratatui::{
     layout::Alignment,
@@ -10,12 +15,18 @@ use ratatui::{
     Frame,
 };
 use ratatui::layout::{Constraint, Layout};
-use ratatui::widgets::Wrap;
+use ratatui::widgets::{LineGauge, Wrap};
 
 use crate::app::App;
+use crate::app::search_state::SearchState;
+use crate::ui::diff_view::DiffView;
 use crate::ui::event_details::EventDetailsWidget;
-use crate::ui::event_list::EventList;
+use crate::ui::event_list::{truncate, EventList};
+use crate::ui::explain_view::ExplainView;
+use crate::ui::query_results::QueryResultsWidget;
+use crate::ui::query_picker::QueryPicker;
 use crate::ui::query_window::QueryWindow;
+use crate::ui::schema_browser::SchemaBrowser;
 
 /// Renders the user interface widgets.
 pub fn render(app: &mut App, frame: &mut Frame) {
@@ -25,11 +36,8 @@ pub fn render(app: &mut App, frame: &mut Frame) {
     // - https://github.com/ratatui-org/ratatui/tree/master/examples
 
     let starting_paragraph = Paragraph::new(format!("DSLabs Log Insight.\n\
-         Press `Esc` or `Ctrl-C` to stop running.\n\
-         Press Ctrl-Left or Ctrl-Right to focus the event list or query window respectively.\n\
-         Next/prev event: up/down. Next/prev page: page-up/page-down, Next/prev result: n/Shift-n\n\
-         After entering a query, press Alt-Enter to run it.\n\
-         Current selected event: {}", app.event_list_state.selected_event)
+         Press `Esc` or `Ctrl-C` to stop running, `?` to show or hide the keys.\n\
+         Current selected event: {}", app.event_at_cursor().map(|event_idx| event_idx.to_string()).unwrap_or_default())
     )
         .block(
             Block::bordered()
@@ -41,7 +49,7 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         .centered();
 
     let layout = Layout::vertical([
-        Constraint::Length(8),
+        Constraint::Length(5),
         Constraint::Min(0)
     ])
         .spacing(1)
@@ -59,26 +67,126 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         .spacing(1)
         .split(layout[1]);
     
-    // create the optional details view
-    let event_area_constraints = if app.selected_event.is_some() {
-        vec![Constraint::Ratio(1, 2); 2]
-    } else {
-        vec![Constraint::Min(0)]
+    // explain the active query against the selected event, if asked to
+    let explained = match (app.explain_visible, app.selected_event, app.filter_state.active_filter()) {
+        (true, Some(selected_idx), Some(query)) => {
+            let nodes = query.explain(&app.events[selected_idx], &app.events);
+            let matched = app.filter_state.matching_events().contains(&selected_idx);
+            Some((selected_idx, matched, nodes))
+        }
+        _ => None,
     };
     
+    // compare the marked event with the selected one, if they're different events
+    app.diff_state.update(&app.events, app.marked_event, app.selected_event);
+    let diffed = app.diff_state.diff();
+    
+    // create the optional details and results views
+    let event_area_count = 1
+        + app.selected_event.is_some() as u32
+        + diffed.is_some() as u32
+        + explained.is_some() as u32
+        + app.filter_state.result_table().is_some() as u32
+        + app.schema_state.is_visible() as u32
+        + app.picker_state.is_visible() as u32
+        + app.help_visible as u32;
+    let event_area_constraints = vec![Constraint::Ratio(1, event_area_count); event_area_count as usize];
+    
     let event_area_layout = Layout::vertical(event_area_constraints)
         .spacing(1)
         .split(main_area_layout[0]);
+    let mut event_areas = event_area_layout.iter();
     
     // render the event list
-    let event_list = EventList::new(&app.events, app.filter_state.matching_events(), app.focused_window.is_event_list());
-    frame.render_stateful_widget(event_list, event_area_layout[0], &mut app.event_list_state);
+    let event_list = EventList::new(&app.events, app.filter_state.matching_events(), app.focused_window.is_event_list())
+        .with_columns(app.projection_state.columns(), app.projection_state.sort_column())
+        .with_active_group(app.navigation_state.current_group().unwrap_or_default())
+        .with_breadcrumb(filter_breadcrumb(app))
+        .with_highlights(&app.highlight_state)
+        .with_search(&app.search_state)
+        .with_marked_event(app.marked_event)
+        .with_history(&app.history_state);
+    
+    // leave a line under the event list for the search or goto prompt while one is open
+    let prompt = if app.search_state.is_prompt_open() {
+        Some(search_prompt(&app.search_state))
+    } else {
+        app.goto_state.prompt().map(|text| {
+            Paragraph::new(format!("go to: {}  (an event id, a time like 22:59:25, or a percentage like 50%)", text))
+                .style(Style::default().fg(Color::Yellow))
+        })
+    };
+    let mut event_list_area = *event_areas.next().unwrap();
+    if let Some(prompt) = prompt {
+        let [list_area, prompt_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+            .areas(event_list_area);
+        frame.render_widget(prompt, prompt_area);
+        event_list_area = list_area;
+    }
+    
+    if app.filter_view_state.is_enabled() {
+        // only show the matches, and the events around them
+        let title = format!("Events: {} matches, {} around each, {} of {} shown",
+                            app.filter_state.matching_events().len(), app.filter_view_state.context(),
+                            app.filter_view_state.rows().len(), app.events.len());
+        let event_list = event_list.with_shown_rows(app.filter_view_state.rows(), title);
+        frame.render_stateful_widget(event_list, event_list_area, &mut app.filter_list_state);
+    } else {
+        let event_list = event_list.with_row_order(app.projection_state.row_order());
+        frame.render_stateful_widget(event_list, event_list_area, &mut app.event_list_state);
+    }
     
     // optionally show an event details for the given one
     if let Some(selected_idx) = app.selected_event {
         let selected_ev = app.events.get(selected_idx).unwrap();
-        let details_widget = EventDetailsWidget::new(selected_ev);
-        frame.render_widget(details_widget, event_area_layout[1]);
+        app.details_state.show_event(selected_idx);
+        
+        // leave a line under the details for their search prompt while it's open
+        let mut details_area = *event_areas.next().unwrap();
+        if app.details_state.search().is_prompt_open() {
+            let [tree_area, prompt_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+                .areas(details_area);
+            frame.render_widget(search_prompt(app.details_state.search()), prompt_area);
+            details_area = tree_area;
+        }
+        
+        let changes = app.history_state.changes_since_previous(&app.events, selected_idx);
+        let mut details_widget = EventDetailsWidget::new(selected_ev, app.focused_window.is_event_details());
+        if let Some((previous_idx, changes)) = &changes {
+            details_widget = details_widget.with_changes(app.events[*previous_idx].id(), changes);
+        }
+        frame.render_stateful_widget(details_widget, details_area, &mut app.details_state);
+    }
+    
+    if let Some((marked_idx, selected_idx, lines)) = diffed {
+        let diff_view = DiffView::new(&app.events[marked_idx], &app.events[selected_idx], lines);
+        frame.render_widget(diff_view, *event_areas.next().unwrap());
+    }
+    
+    if let Some((event_id, matched, nodes)) = &explained {
+        frame.render_widget(ExplainView::new(*event_id, *matched, nodes), *event_areas.next().unwrap());
+    }
+    
+    // optionally show the summary produced by the active query
+    if let Some(result_table) = app.filter_state.result_table() {
+        frame.render_widget(QueryResultsWidget::new(result_table), *event_areas.next().unwrap());
+    }
+    
+    // optionally show the fields of each kind of object
+    if app.schema_state.is_visible() {
+        let schema_browser = SchemaBrowser::new(&app.schema, &app.schema_state, app.focused_window.is_schema_browser());
+        frame.render_widget(schema_browser, *event_areas.next().unwrap());
+    }
+    
+    // optionally show the named queries to pick from
+    if app.picker_state.is_visible() {
+        let query_picker = QueryPicker::new(&app.library, &app.picker_state, app.focused_window.is_query_picker());
+        frame.render_widget(query_picker, *event_areas.next().unwrap());
+    }
+    
+    // optionally show what each key does
+    if app.help_visible {
+        frame.render_widget(key_help(), *event_areas.next().unwrap());
     }
 
     let right_bar_layout = Layout::vertical([
@@ -89,14 +197,83 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         .split(main_area_layout[1]);
     
     // render the query window and message area
-    frame.render_stateful_widget(QueryWindow::new(app.focused_window.is_filter_list()), right_bar_layout[0], &mut app.query_text_area);
+    let query_window = QueryWindow::new(app.focused_window.is_filter_list())
+        .with_error(app.query_error.as_ref())
+        .with_completions(app.completion_state.candidates(), app.completion_state.selected());
+    frame.render_stateful_widget(query_window, right_bar_layout[0], &mut app.query_text_area);
 
     let message_block = Block::bordered()
         .title("Messages");
 
-    let message_area = message_block.inner(right_bar_layout[1]);
+    let mut message_area = message_block.inner(right_bar_layout[1]);
+    
+    // show how far along the query running in the background is
+    if let Some(worker) = &app.query_worker {
+        let [progress_area, rest] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+            .areas(message_area);
+        let label = format!("{}/{} searched, {} matches (Esc cancels)", worker.searched(), worker.total(), worker.match_count());
+        let progress = LineGauge::default()
+            .ratio(worker.progress().clamp(0.0, 1.0))
+            .label(label)
+            .gauge_style(Style::default().fg(Color::Yellow));
+        frame.render_widget(progress, progress_area);
+        message_area = rest;
+    }
+    
     let para = Paragraph::new(app.message_state.messages().join("\n"))
         .wrap(Wrap::default());
     frame.render_widget(para, message_area);
     frame.render_widget(message_block, right_bar_layout[1]);
 }
+
+/// what each key does, shown while `?` is toggled on
+fn key_help() -> Paragraph<'static> {
+    Paragraph::new("Press Ctrl-Left or Ctrl-Right to focus the event list or query window respectively. Ctrl-S shows the schema, Ctrl-L the named queries.\n\
+         Next/prev event: up/down. Next/prev page: page-up/page-down, Next/prev result: n/Shift-n, Next/prev in result group: ]/[\n\
+         Add `select a, b.c` to a query to show fields as columns. Sort column: s, Flip sort: Shift-s, Explain the query for the selected event: x\n\
+         Only show matches: f, More/less context around matches: +/-, Undo/redo the last query: u/r, Undo every query: Shift-u\n\
+         Run `highlight name color = query` to color its matches. Pick the highlight n/N go through: h, Remove it: Shift-h\n\
+         Search the text of events: /, then Enter to keep it and go through it with n/N. Alt-r toggles regexes, Alt-c case sensitivity\n\
+         Go to an event id, a time, or a percentage of the way through the log: g\n\
+         Ctrl-Down focuses the selected event's details. Expand/collapse: Enter or right/left, Search inside: /, then n/N\n\
+         In the details, query for events with the same value: e (Shift-e runs it), with the same field: h (Shift-h runs it)\n\
+         Mark the event under the cursor to compare it with the selected event: m\n\
+         Show what changed since each event's previous state: d\n\
+         After entering a query, press Alt-Enter to run it, or Esc to cancel it while it runs. Tab completes the word at the cursor.")
+        .block(Block::bordered().title("Keys").border_type(BorderType::Rounded))
+        .style(Style::default().fg(Color::Cyan))
+        .wrap(Wrap { trim: true })
+}
+
+/// the text typed into the search prompt, along with how it's matched
+fn search_prompt(search: &SearchState) -> Paragraph<'static> {
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let status = match search.error() {
+        Some(error) => format!(" ({})", error),
+        None if search.is_not_found() => " (not found)".to_string(),
+        None => String::new(),
+    };
+    Paragraph::new(format!("/{}  [regex {}] [case {}]{}",
+                           search.prompt().unwrap_or_default(), on_off(search.use_regex()), on_off(search.case_sensitive()), status))
+        .style(Style::default().fg(if status.is_empty() { Color::Yellow } else { Color::Red }))
+}
+
+/// the most characters of each query shown in the breadcrumb
+const BREADCRUMB_LABEL_WIDTH: usize = 32;
+
+/// the chain of queries that were applied, each refining the one before it, or none if no query
+/// is active
+fn filter_breadcrumb(app: &App) -> Option<String> {
+    let layers = app.filter_state.breadcrumb();
+    if layers.is_empty() {
+        return None;
+    }
+    
+    let crumbs = layers.into_iter()
+        .map(|(label, match_count)| format!("{} ({})", truncate(label.to_string(), BREADCRUMB_LABEL_WIDTH), match_count));
+    let breadcrumb = std::iter::once(format!("all events ({})", app.events.len()))
+        .chain(crumbs)
+        .collect::<Vec<_>>()
+        .join(" › ");
+    Some(breadcrumb)
+}
@@ -0,0 +1,67 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Row, Table, Widget};
+use crate::ds_events::diff::{Change, DiffLine};
+use crate::ds_events::event::Event;
+
+/// shows the fields of the marked event next to the selected event's, colored by how they changed
+pub struct DiffView<'a> {
+    marked: &'a Event,
+    selected: &'a Event,
+    lines: &'a [DiffLine],
+}
+
+impl<'a> DiffView<'a> {
+    pub fn new(marked: &'a Event, selected: &'a Event, lines: &'a [DiffLine]) -> Self {
+        Self {
+            marked,
+            selected,
+            lines,
+        }
+    }
+}
+
+/// the marker and color of each kind of change
+fn change_style(change: Change) -> (&'static str, Style) {
+    match change {
+        Change::Same => (" ", Style::default().fg(Color::DarkGray)),
+        Change::Added => ("+", Style::default().fg(Color::Green)),
+        Change::Removed => ("-", Style::default().fg(Color::Red)),
+        Change::Changed => ("~", Style::default().fg(Color::Yellow)),
+    }
+}
+
+impl<'a> Widget for DiffView<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let header = Row::new([
+            "field".to_string(),
+            format!("marked event {}", self.marked.id()),
+            format!("selected event {}", self.selected.id()),
+        ])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+
+        let rows = self.lines.iter()
+            .map(|line| {
+                let (marker, style) = change_style(line.change());
+                Row::new([
+                    format!("{} {}{}", marker, "  ".repeat(line.depth()), line.label()),
+                    line.left().unwrap_or_default().to_string(),
+                    line.right().unwrap_or_default().to_string(),
+                ])
+                    .style(style)
+            });
+
+        let changed_count = self.lines.iter()
+            .filter(|line| line.change() != Change::Same)
+            .count();
+        let title = format!("Diff: event {} ({}) → event {} ({}), {} lines differ",
+                            self.marked.id(), self.marked.event_obj().name(),
+                            self.selected.id(), self.selected.event_obj().name(), changed_count);
+        Table::new(rows, [Constraint::Ratio(1, 3), Constraint::Ratio(1, 3), Constraint::Ratio(1, 3)])
+            .header(header)
+            .column_spacing(1)
+            .block(Block::bordered().title(title))
+            .render(area, buf);
+    }
+}
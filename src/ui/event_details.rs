@@ -1,28 +1,152 @@
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
-use ratatui::widgets::{Block, Paragraph, Widget};
-use crate::ds_events::abstract_object::pretty_print::AbstractObjectPrettyPrinter;
-use crate::ds_events::event::Event;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, HighlightSpacing, List, ListItem, ListState, StatefulWidget, Widget};
+use crate::app::details_state::{DetailsRow, DetailsState};
+use crate::ds_events::diff::{Change, DiffLine};
+use crate::ds_events::event::{format_timestamp, Event};
+use crate::ui::event_list::mark_search_matches;
 
+/// shows the selected event's object as a tree, with the values inside objects, maps and
+/// collections under them
 pub struct EventDetailsWidget<'ev> {
     event: &'ev Event,
+    selected: bool,
+    /// the id of the last event with the same type, node and key, and the values changed since it
+    changes: Option<(usize, &'ev [DiffLine])>,
 }
 
 impl<'ev> EventDetailsWidget<'ev> {
-    pub fn new(event: &'ev Event) -> Self {
-        Self { event }
+    pub fn new(event: &'ev Event, selected: bool) -> Self {
+        Self { event, selected, changes: None }
+    }
+
+    pub fn with_changes(mut self, previous_id: usize, changes: &'ev [DiffLine]) -> Self {
+        self.changes = Some((previous_id, changes));
+        self
+    }
+
+    /// what changed about a row's value since the previous event. Collapsed rows say if anything
+    /// inside of them changed, since those changes aren't shown
+    fn change_span(&self, row: &DetailsRow, state: &DetailsState) -> Option<Span<'ev>> {
+        let (_, changes) = self.changes?;
+        if let Some(line) = changes.iter().find(|line| line.path() == row.key()) {
+            return match (line.change(), line.left()) {
+                (Change::Changed, Some(was)) => Some(Span::styled(format!("  (was {})", was), Style::default().fg(Color::Yellow))),
+                _ => Some(Span::styled("  (new)", Style::default().fg(Color::Green))),
+            };
+        }
+
+        let changed_inside = changes.iter()
+            .filter_map(|line| line.path().strip_prefix(row.key()))
+            .any(|rest| rest.starts_with('.') || rest.starts_with('['));
+        (changed_inside && !state.is_expanded(row))
+            .then(|| Span::styled("  (changed inside)", Style::default().fg(Color::Yellow)))
+    }
+
+    fn row_line(&self, row: &DetailsRow, state: &DetailsState) -> Line<'ev> {
+        let marker = match (row.is_expandable(), state.is_expanded(row)) {
+            (true, true) => "▾ ",
+            (true, false) => "▸ ",
+            (false, _) => "  ",
+        };
+        let search = state.search().pattern().map(|_| state.search());
+
+        let mut spans = vec![Span::raw(format!("{}{}", "  ".repeat(row.depth()), marker))];
+        spans.extend(mark_search_matches(row.label().to_string(), Style::default().fg(Color::Yellow), search));
+        spans.push(Span::raw(if row.kind().is_scalar() { " = " } else { ": " }));
+        spans.extend(mark_search_matches(row.summary().to_string(), Style::default(), search));
+        spans.push(Span::styled(format!("  {}", row.kind()), Style::default().fg(Color::DarkGray)));
+        spans.extend(self.change_span(row, state));
+        Line::from(spans)
     }
 }
 
-impl<'ev> Widget for EventDetailsWidget<'ev> {
-    fn render(self, area: Rect, buf: &mut Buffer) where Self: Sized {
-        let pretty_print = AbstractObjectPrettyPrinter::new(self.event.event_obj());
-        let obj_details = format!("{}", pretty_print);
-        Paragraph::new(obj_details)
-            .block(
-                Block::bordered()
-                    .title("Event Details")
-            )
-            .render(area, buf)
+impl<'ev> StatefulWidget for EventDetailsWidget<'ev> {
+    type State = DetailsState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let object = self.event.event_obj();
+        let mut title = format!("Event {} Details: {} from {} at {}",
+                                self.event.id(), object.name(), self.event.originator(), format_timestamp(self.event.time()));
+        if let Some((previous_id, changes)) = self.changes {
+            title.push_str(&format!(", {} changed since event {}", changes.len(), previous_id));
+            // removed values aren't in the tree, so they're named here
+            let removed = changes.iter()
+                .filter(|line| line.change() == Change::Removed)
+                .map(|line| line.path())
+                .collect::<Vec<_>>();
+            if !removed.is_empty() {
+                title.push_str(&format!(", removed {}", removed.join(", ")));
+            }
+        }
+        let block = Block::bordered()
+            .title(title)
+            .border_style(if self.selected {
+                Style::default().fg(Color::Blue)
+            } else {
+                Style::default()
+            });
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        // only the rows that fit are built
+        let rows = state.rows(object);
+        state.set_height(inner_area.height as usize, rows.len());
+        let items = rows.iter()
+            .skip(state.offset())
+            .take(inner_area.height as usize)
+            .map(|row| ListItem::new(self.row_line(row, state)))
+            .collect::<Vec<_>>();
+
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_spacing(HighlightSpacing::Always);
+        let mut list_state = ListState::default()
+            .with_selected((!rows.is_empty()).then(|| state.cursor() - state.offset()));
+        StatefulWidget::render(list, inner_area, buf, &mut list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use crate::app::details_state::DetailsState;
+    use crate::ds_events::abstract_object::AbstractObject;
+    use crate::ds_events::diff::changed_values;
+    use crate::ds_events::fixtures::log;
+    use crate::ui::event_details::EventDetailsWidget;
+
+    #[test]
+    fn marks_what_changed() {
+        let slot = |status: &str, round: i64| AbstractObject::new("Slot")
+            .with_prop("slotNum", 1i64)
+            .with_prop("status", status)
+            .with_prop("ballot", AbstractObject::new("Ballot").with_prop("round", round));
+        let events = log([("server1", slot("ACCEPTED", 1)), ("server1", slot("CHOSEN", 2))]);
+        let changes = changed_values(events[0].event_obj(), events[1].event_obj());
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 6)).expect("The test backend should open");
+        let mut state = DetailsState::default();
+        terminal.draw(|frame| {
+            let details = EventDetailsWidget::new(&events[1], true).with_changes(events[0].id(), &changes);
+            frame.render_stateful_widget(details, frame.size(), &mut state)
+        }).expect("Drawing should succeed");
+
+        let buffer = terminal.backend().buffer();
+        let rows = (1..5)
+            .map(|y| (1..79).map(|x| buffer.get(x, y).symbol()).collect::<String>())
+            .collect::<Vec<_>>();
+        let row_of = |label: &str| rows.iter()
+            .find(|row| row.contains(label))
+            .map(|row| row.trim_end().to_string())
+            .expect("The row should be shown");
+        assert!(row_of("status").ends_with("(was ACCEPTED)"), "{:?}", rows);
+        assert!(row_of("round").ends_with("(was 1)"), "{:?}", rows);
+        // the change inside the ballot is shown on its own row while the ballot is expanded
+        assert!(row_of("ballot").ends_with("object"), "{:?}", rows);
+        assert!(!row_of("slotNum").contains('('), "{:?}", rows);
     }
 }
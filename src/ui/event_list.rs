@@ -1,16 +1,45 @@
 use std::cmp::min;
-use std::collections::{BTreeSet};
+use std::collections::BTreeSet;
+use std::ops::Range;
 use ratatui::buffer::Buffer;
-use ratatui::layout::Rect;
+use ratatui::layout::{Alignment, Constraint, Rect};
 use ratatui::prelude::Color;
 use ratatui::style::{Modifier, Style, Stylize};
-use ratatui::widgets::{Block, List, ListItem, ListState, StatefulWidget, Widget};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::block::{Position, Title};
+use ratatui::widgets::{Block, Cell, HighlightSpacing, List, ListItem, ListState, Row, StatefulWidget, Table, TableState, Widget};
+use crate::app::highlight_state::HighlightState;
+use crate::app::history_state::HistoryState;
+use crate::app::search_state::{searchable_text, SearchState};
 use crate::ds_events::event::Event;
+use crate::dsl::query_ast::PropPath;
 
 pub struct EventList<'events> {
     events: &'events [Event],
     selected: bool,
     matching_events: &'events BTreeSet<usize>,
+    /// the paths to show as columns. If empty, whole events are shown instead
+    columns: &'events [PropPath],
+    /// the column events are sorted by, and if it's descending
+    sort_column: Option<(usize, bool)>,
+    /// the event shown at each row, or empty if events are shown in log order
+    row_order: &'events [usize],
+    /// how many rows there are. Less than the number of events if only some are shown
+    row_count: usize,
+    /// shown in the border
+    title: String,
+    /// the chain of queries that were applied, shown in the bottom border
+    breadcrumb: Option<String>,
+    /// the group of matching events we are currently navigating through
+    active_group: &'events [usize],
+    /// the highlight rules to color rows by
+    highlights: Option<&'events HighlightState>,
+    /// the text being searched for, which is marked where it's found
+    search: Option<&'events SearchState>,
+    /// the event marked for comparing with the selected one
+    marked_event: Option<usize>,
+    /// used to list what changed since each event's previous state, if they're shown
+    history: Option<&'events HistoryState>,
 }
 
 impl<'events> EventList<'events> {
@@ -18,154 +47,261 @@ impl<'events> EventList<'events> {
         Self {
             events,
             selected,
-            matching_events
+            matching_events,
+            columns: &[],
+            sort_column: None,
+            row_order: &[],
+            row_count: events.len(),
+            title: "Events".to_string(),
+            breadcrumb: None,
+            active_group: &[],
+            highlights: None,
+            search: None,
+            marked_event: None,
+            history: None,
         }
     }
+    
+    pub fn with_columns(mut self, columns: &'events [PropPath], sort_column: Option<(usize, bool)>) -> Self {
+        self.columns = columns;
+        self.sort_column = sort_column;
+        self
+    }
+    
+    pub fn with_row_order(mut self, row_order: &'events [usize]) -> Self {
+        self.row_order = row_order;
+        self
+    }
+    
+    /// only shows the given events, in the given order
+    pub fn with_shown_rows(mut self, rows: &'events [usize], title: String) -> Self {
+        self.row_order = rows;
+        self.row_count = rows.len();
+        self.title = title;
+        self
+    }
+    
+    pub fn with_breadcrumb(mut self, breadcrumb: Option<String>) -> Self {
+        self.breadcrumb = breadcrumb;
+        self
+    }
+    
+    pub fn with_active_group(mut self, active_group: &'events [usize]) -> Self {
+        self.active_group = active_group;
+        self
+    }
+    
+    pub fn with_highlights(mut self, highlights: &'events HighlightState) -> Self {
+        self.highlights = Some(highlights);
+        self
+    }
+    
+    pub fn with_search(mut self, search: &'events SearchState) -> Self {
+        self.search = Some(search);
+        self
+    }
+    
+    pub fn with_marked_event(mut self, marked_event: Option<usize>) -> Self {
+        self.marked_event = marked_event;
+        self
+    }
+    
+    /// lists what changed since each event's previous state, if the history says to
+    pub fn with_history(mut self, history: &'events HistoryState) -> Self {
+        self.history = history.is_shown_in_list().then_some(history);
+        self
+    }
+    
+    /// the fields that changed since the event's previous state, like `Δ slotStatus, roundNum`, or
+    /// none if it's the first event about what it's the state of. Collection items are named by
+    /// their whole path, since their index alone doesn't say much
+    fn change_summary(&self, event: &Event) -> Option<String> {
+        let (_, changes) = self.history?.changes_since_previous(self.events, event.id())?;
+        if changes.is_empty() {
+            return Some("Δ none".to_string());
+        }
+        let paths = changes.iter()
+            .map(|line| if line.label().starts_with('[') { line.path() } else { line.label() })
+            .collect::<Vec<_>>();
+        Some(format!("Δ {}", paths.join(", ")))
+    }
+    
+    /// the text of an event's row, with the places the search text is found marked, followed by
+    /// what changed since the event's previous state
+    fn event_line(&self, event: &Event) -> Line<'events> {
+        let mut spans = vec![Span::raw(format!("{} ", event.id()))];
+        spans.extend(mark_search_matches(searchable_text(event), Style::default(), self.search));
+        if let Some(summary) = self.change_summary(event) {
+            spans.push(Span::styled(format!("  {}", summary), Style::default().fg(Color::Magenta)));
+        }
+        Line::from(spans)
+    }
+    
+    /// matching events are underlined, and the group being navigated stands out from other matches.
+    /// Events a highlight rule matches are drawn in the color of the first rule that matches them,
+    /// and the marked event has a background
+    fn event_style(&self, event: &Event) -> Style {
+        let style = self.match_style(event);
+        if self.marked_event == Some(event.id()) {
+            style.bg(Color::DarkGray)
+        } else {
+            style
+        }
+    }
+    
+    fn match_style(&self, event: &Event) -> Style {
+        let highlight_color = self.highlights
+            .and_then(|highlights| highlights.highlight_of(event.id()))
+            .map(|highlight| highlight.color());
+        
+        if self.active_group.contains(&event.id()) {
+            Style::default().fg(Color::Cyan).bold().underlined()
+        } else if self.matching_events.contains(&event.id()) {
+            Style::default().fg(highlight_color.unwrap_or(Color::Yellow)).underlined()
+        } else if let Some(color) = highlight_color {
+            Style::default().fg(color)
+        } else {
+            Style::default()
+        }
+    }
+    
+    /// a swatch and name for each highlight rule, marking the one n/N go through
+    fn legend(highlights: &HighlightState) -> Line<'events> {
+        let spans = highlights.highlights().iter()
+            .enumerate()
+            .flat_map(|(idx, highlight)| {
+                let name_style = if highlights.nav_highlight_idx() == Some(idx) {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                [
+                    Span::styled(" ■ ", Style::default().fg(highlight.color())),
+                    Span::styled(format!("{} ({})", highlight.name(), highlight.matches().len()), name_style),
+                ]
+            })
+            .chain([Span::raw(" ")])
+            .collect::<Vec<_>>();
+        Line::from(spans)
+    }
+    
+    fn event_at_row(&self, row: usize) -> &'events Event {
+        let event_idx = self.row_order.get(row)
+            .copied()
+            .unwrap_or(row);
+        &self.events[event_idx]
+    }
 }
 
+/// the cursor and scroll position of an event list. Only the rows that fit on screen are drawn,
+/// so the list can be as long as the log
 #[derive(Default, Debug)]
 pub struct EventListState {
-    /// NOTE this does not change
-    event_count: usize,
-    /// the last time we checked, how many lines where there
-    last_height: usize,
-    /// how many pages total we have based on line height, last time we checked
-    page_count: usize,
-    /// which page is currently selected
-    current_page: usize,
-    /// which event is selected overall
-    pub(crate) selected_event: usize,
-    /// state for the given page
-    page_state: ListState,
+    /// how many rows the list has
+    row_count: usize,
+    /// the row the cursor is on
+    selected_row: usize,
+    /// the first row on screen
+    offset: usize,
+    /// how many rows fit on screen, the last time the list was drawn
+    height: usize,
 }
 
 impl EventListState {
 
-    pub fn new(event_count: usize, selected: Option<usize>) -> Self {
-        Self {
-            event_count,
-            page_state: ListState::default().with_selected(selected),
+    pub fn new(row_count: usize, selected: Option<usize>) -> Self {
+        let mut state = Self {
+            row_count,
             ..Self::default()
-        }
+        };
+        state.move_cursor(selected.unwrap_or(0));
+        state
+    }
+    
+    pub fn selected_row(&self) -> usize {
+        self.selected_row
     }
 
     pub fn go_home(&mut self) {
-        self.selected_event = 0;
-        self.page_state = ListState::default().with_selected(Some(0));
-        self.current_page = 0;
+        self.move_cursor(0);
     }
     
     pub fn next_event(&mut self) {
-        // do nothing if we are at the end of the event count
-        if self.selected_event >= self.event_count {
-            return;
-        }
-
-        // bump the selected event
-        self.selected_event += 1;
-
-        let mut page_selected_value = self.page_state.selected().unwrap();
-        page_selected_value += 1;
-        // if we have gone beyond, we need to bump pages
-        if page_selected_value >= self.last_height {
-            self.next_page();
-        } else {
-            // otherwise, we can just update the page state
-            *self.page_state.selected_mut() = Some(page_selected_value);
-        }
+        self.move_cursor(self.selected_row + 1);
     }
     
     pub fn prev_event(&mut self) {
-        if self.selected_event == 0 {
-            return;
-        }
-
-        // bump the selected event
-        self.selected_event -= 1;
-
-        let page_selected_value = self.page_state.selected().unwrap();
-        if page_selected_value == 0 {
-            self.prev_page()
-        } else {
-            *self.page_state.selected_mut() = Some(page_selected_value - 1);
-        }
+        self.move_cursor(self.selected_row.saturating_sub(1));
     }
     
+    /// scrolls down a screen, keeping the cursor on the same line of the screen until the end
     pub fn next_page(&mut self) {
-        // do nothing if we are at the end
-        if self.current_page >= self.page_count {
-            return;
-        }
-
-        // update selected event
-        let selected_page_idx = self.page_state.selected().unwrap_or(0);
-        let dist_from_bottom = self.last_height - selected_page_idx;
-        self.selected_event += dist_from_bottom;
-        
-        // update selected page state
-        self.current_page += 1;
-        self.page_state = ListState::default().with_selected(Some(0));
+        let page = self.height.max(1);
+        self.offset = (self.offset + page).min(self.max_offset());
+        self.move_cursor(self.selected_row + page);
     }
     
+    /// scrolls up a screen, keeping the cursor on the same line of the screen until the start
     pub fn prev_page(&mut self) {
-        if self.current_page == 0 {
-            self.selected_event = 0;
-            *self.page_state.selected_mut() = Some(0);
-            return;
-        }
-
-        let last_selected = self.page_state.selected().unwrap_or(0);
-        self.selected_event -= last_selected;
-        self.selected_event -= 1;
-        
-        self.current_page -= 1;
-        self.page_state = ListState::default().with_selected(Some(self.last_height - 1))
-
-        // TODO update selected accordingly
+        let page = self.height.max(1);
+        self.offset = self.offset.saturating_sub(page);
+        self.move_cursor(self.selected_row.saturating_sub(page));
     }
     
     pub fn go_end(&mut self) {
-        self.current_page = self.page_count;
-        self.selected_event = self.event_count - 1;
-        
-        // compute the last item
-        let remaining_items = self.event_count - (self.page_count * self.last_height);
-        
-        self.page_state = ListState::default().with_selected(Some(min(remaining_items - 1, self.last_height - 1)));
-    }
-    
-    fn calculate_pages(&mut self, height: usize) {
-        self.last_height = height;
-        self.page_count = self.event_count / height;
-        if self.page_state.selected().is_some_and(|sel| sel > height) {
-            *self.page_state.selected_mut() = Some(height - 1);
-        }
+        self.move_cursor(self.row_count.saturating_sub(1));
     }
     
-    pub fn focus_event(&mut self, event_idx: usize) {
+    /// moves the cursor to the given row, scrolling as little as possible to show it
+    pub fn focus_event(&mut self, row: usize) {
         // too far
-        if event_idx >= self.event_count {
+        if row >= self.row_count {
             return;
         }
 
-        // figure out which page we need to focus
-        let Some((containing_page, page_offset)) = self.compute_containing_page(event_idx) else {
-            panic!()
-        };
-        
-        // set the selected page and event
-        self.current_page = containing_page;
-        self.selected_event = event_idx;
-        *self.page_state.selected_mut() = Some(page_offset);
+        self.move_cursor(row);
     }
     
-    fn compute_containing_page(&self, event_idx: usize) -> Option<(usize, usize)> {
-        let containing_page = event_idx / self.last_height;
-        assert!(containing_page < self.page_count);
-        
-        let page_offset = event_idx - (containing_page * self.last_height);
-        
-        Some((containing_page, page_offset))
+    /// the list now has `row_count` rows, with the cursor on `selected_row`, like after the rows
+    /// were filtered or more were added. The cursor stays on the same line of the screen if it can
+    pub fn set_rows(&mut self, row_count: usize, selected_row: usize) {
+        let screen_line = self.selected_row.saturating_sub(self.offset);
+        self.row_count = row_count;
+        self.selected_row = selected_row.min(row_count.saturating_sub(1));
+        self.offset = self.selected_row.saturating_sub(screen_line);
+        self.scroll_to_cursor();
+    }
+    
+    /// fits the list to the height it is drawn at, keeping the cursor on screen
+    fn set_height(&mut self, height: usize) {
+        self.height = height;
+        self.scroll_to_cursor();
+    }
+    
+    /// the rows that are on screen
+    fn visible_rows(&self) -> Range<usize> {
+        self.offset..min(self.offset + self.height, self.row_count)
+    }
+    
+    fn move_cursor(&mut self, row: usize) {
+        self.selected_row = row.min(self.row_count.saturating_sub(1));
+        self.scroll_to_cursor();
+    }
+    
+    /// the furthest the list can scroll without leaving blank lines after the last row
+    fn max_offset(&self) -> usize {
+        self.row_count.saturating_sub(self.height)
+    }
+    
+    /// scrolls as little as possible to put the cursor on screen. Until the list is drawn its
+    /// height isn't known, so this is done again once it is
+    fn scroll_to_cursor(&mut self) {
+        if self.selected_row < self.offset {
+            self.offset = self.selected_row;
+        } else if self.height > 0 && self.selected_row >= self.offset + self.height {
+            self.offset = self.selected_row + 1 - self.height;
+        }
+        self.offset = self.offset.min(self.max_offset());
     }
 }
 
@@ -174,50 +310,313 @@ impl<'events> StatefulWidget for EventList<'events> {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         
-        let block = Block::bordered()
-            .title("Events")
+        let mut block = Block::bordered()
+            .title(self.title.as_str())
             .border_style(if self.selected {
                 Style::default().fg(Color::Blue)
             } else {
                 Style::default()
             });
+        if let Some(highlights) = self.highlights.filter(|highlights| !highlights.highlights().is_empty()) {
+            block = block.title(Title::from(Self::legend(highlights)).alignment(Alignment::Right));
+        }
+        if let Some(breadcrumb) = &self.breadcrumb {
+            block = block.title(Title::from(breadcrumb.as_str()).position(Position::Bottom));
+        }
         
         let block_area = block.inner(area);
         
-        let window_height = block_area.height as usize;
+        // leave room for the column headers
+        let header_height = if self.columns.is_empty() { 0 } else { 1 };
+        let window_height = (block_area.height as usize).saturating_sub(header_height);
+        
+        // only the rows that fit are built
+        state.set_height(window_height);
+        let page_events = state.visible_rows()
+            .map(|row| self.event_at_row(row))
+            .collect::<Vec<_>>();
+        let mut page_state = ListState::default()
+            .with_selected((state.row_count > 0).then(|| state.selected_row - state.offset));
+        
+        block.render(area, buf);
         
-        // re-calculate each time based on how many lines we can show
-        state.calculate_pages(window_height);
+        if self.columns.is_empty() {
+            let events_iter = page_events.into_iter()
+                .map(|event| {
+                    ListItem::new(self.event_line(event))
+                        .style(self.event_style(event))
+                });
+            StatefulWidget::render(
+                List::new(events_iter)
+                    .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                    .highlight_symbol(HIGHLIGHT_SYMBOL)
+                    .repeat_highlight_symbol(true),
+                block_area,
+                buf,
+                &mut page_state
+            );
+        } else {
+            let mut table_state = TableState::default().with_selected(page_state.selected());
+            StatefulWidget::render(
+                self.page_to_table(&page_events, block_area.width as usize),
+                block_area,
+                buf,
+                &mut table_state
+            );
+        }
+    }
+}
+
+const HIGHLIGHT_SYMBOL: &str = ">>";
+const SEARCH_MATCH_STYLE: Style = Style::new().fg(Color::Black).bg(Color::LightYellow);
+const COLUMN_SPACING: usize = 1;
 
-        // figure out which chunk to display
-        let events_iter = self.events
-            .chunks(window_height)
-            .nth(state.current_page)
-            .into_iter()
-            .flat_map(|event| {
-                event.into_iter()
-                    .map(|event| event_to_list_item(event, self.matching_events))
+impl<'events> EventList<'events> {
+    /// builds a table with one column per projected path, truncating cells to fit in the given width
+    fn page_to_table(&self, page_events: &[&'events Event], width: usize) -> Table<'events> {
+        let id_width = self.events.len().to_string().len();
+        let node_width = page_events.iter()
+            .map(|event| event.originator().len())
+            .chain(["node".len()])
+            .max()
+            .unwrap_or(0);
+        
+        // split the leftover space evenly between the projected columns, and the changes if they're shown
+        let column_count = self.columns.len() + self.history.is_some() as usize;
+        let fixed_width = HIGHLIGHT_SYMBOL.len() + id_width + node_width + COLUMN_SPACING * (column_count + 1);
+        let column_width = width.saturating_sub(fixed_width) / column_count;
+        
+        let header_cells = ["id".to_string(), "node".to_string()].into_iter()
+            .chain(self.columns.iter()
+                .enumerate()
+                .map(|(idx, column)| {
+                    let label = match self.sort_column {
+                        Some((sort_idx, descending)) if sort_idx == idx => {
+                            format!("{} {}", column, if descending { "▼" } else { "▲" })
+                        }
+                        _ => column.to_string(),
+                    };
+                    truncate(label, column_width)
+                }))
+            .chain(self.history.map(|_| "changed".to_string()));
+        let header = Row::new(header_cells)
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        
+        let rows = page_events.iter()
+            .map(|event| {
+                // the search text can be in a field that isn't shown, so the id is marked instead
+                let id_style = if self.search.is_some_and(|search| search.is_match(&searchable_text(event))) {
+                    SEARCH_MATCH_STYLE
+                } else {
+                    Style::default()
+                };
+                let cells = [Cell::from(event.id().to_string()).style(id_style), Cell::from(event.originator().to_string())].into_iter()
+                    .chain(self.columns.iter()
+                        .map(|column| {
+                            let value = column.lookup_value(event)
+                                .map(|value| value.to_string())
+                                .unwrap_or_default();
+                            Cell::from(truncate(value, column_width))
+                        }))
+                    .chain(self.history.map(|_| {
+                        let summary = self.change_summary(event).unwrap_or_default();
+                        Cell::from(truncate(summary, column_width)).style(Style::default().fg(Color::Magenta))
+                    }));
+                Row::new(cells).style(self.event_style(event))
             });
         
-        block.render(area, buf);
+        let widths = [Constraint::Length(id_width as u16), Constraint::Length(node_width as u16)].into_iter()
+            .chain((0..column_count).map(|_| Constraint::Fill(1)));
         
-        StatefulWidget::render(
-            List::new(events_iter)
-                .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
-                .highlight_symbol(">>")
-                .repeat_highlight_symbol(true),
-            block_area,
-            buf,
-            &mut state.page_state
-        );
+        Table::new(rows, widths)
+            .header(header)
+            .column_spacing(COLUMN_SPACING as u16)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(HIGHLIGHT_SYMBOL)
+            .highlight_spacing(HighlightSpacing::Always)
+    }
+}
+
+/// splits the text into spans in the given style, with the places the search text is found in
+/// it marked
+pub(crate) fn mark_search_matches<'a>(text: String, style: Style, search: Option<&SearchState>) -> Vec<Span<'a>> {
+    let found = search
+        .map(|search| search.match_ranges(&text))
+        .unwrap_or_default();
+    if found.is_empty() {
+        return vec![Span::styled(text, style)];
     }
+    
+    let mut spans = Vec::new();
+    let mut last_end = 0;
+    for range in found {
+        spans.push(Span::styled(text[last_end..range.start].to_string(), style));
+        spans.push(Span::styled(text[range.clone()].to_string(), SEARCH_MATCH_STYLE));
+        last_end = range.end;
+    }
+    spans.push(Span::styled(text[last_end..].to_string(), style));
+    spans
 }
 
-fn event_to_list_item<'ev>(event: &'ev Event, matching_events: &'ev BTreeSet<usize>) -> ListItem<'ev> {
-    let mut item = ListItem::new(format!("{} {}: {}", event.id(), event.originator(), event.event_obj()));
-    if matching_events.contains(&event.id()) {
-        item = item.style(Style::default().fg(Color::Yellow).underlined());
+/// shortens text to fit in the given width, marking that it was cut off with an ellipsis
+pub(crate) fn truncate(text: String, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text;
     }
     
-    item
+    let mut truncated = text.chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use crate::ds_events::abstract_object::AbstractObject;
+    use crate::ds_events::event::Event;
+    use ratatui::style::Color;
+    use crate::app::history_state::HistoryState;
+    use crate::ds_events::fixtures::log;
+    use crate::dsl::query_ast::PropPath;
+    use crate::ui::event_list::{EventList, EventListState};
+
+    fn events(count: usize) -> Vec<Event> {
+        log((0..count).map(|_| ("server1", AbstractObject::new("Ping"))))
+    }
+
+    /// draws the list in a terminal with room for `height` rows, returning the row the cursor is on
+    /// and the ids of the events on screen
+    fn draw(events: &[Event], state: &mut EventListState, height: u16) -> (String, Vec<String>) {
+        let mut terminal = Terminal::new(TestBackend::new(40, height + 2)).expect("The test backend should open");
+        let matching_events = BTreeSet::new();
+        terminal.draw(|frame| frame.render_stateful_widget(EventList::new(events, &matching_events, true), frame.size(), state))
+            .expect("Drawing should succeed");
+        
+        let buffer = terminal.backend().buffer();
+        let lines = (1..=height)
+            .map(|y| (1..39).map(|x| buffer.get(x, y).symbol()).collect::<String>())
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>();
+        let cursor = lines.iter()
+            .find_map(|line| line.strip_prefix(">>"))
+            .map(|line| line.split_whitespace().next().unwrap_or_default().to_string())
+            .unwrap_or_default();
+        let ids = lines.iter()
+            .map(|line| line.trim_start_matches(">>").split_whitespace().next().unwrap_or_default().to_string())
+            .collect();
+        (cursor, ids)
+    }
+
+    #[test]
+    fn scrolls_to_the_ends() {
+        let events = events(25);
+        let mut state = EventListState::new(events.len(), Some(0));
+        draw(&events, &mut state, 10);
+        
+        state.go_end();
+        let (cursor, ids) = draw(&events, &mut state, 10);
+        assert_eq!(cursor, "24");
+        assert_eq!(ids.first().map(String::as_str), Some("15"));
+        
+        // moving past the end stays on the last event
+        state.next_event();
+        state.next_page();
+        assert_eq!(draw(&events, &mut state, 10).0, "24");
+        
+        state.prev_page();
+        let (cursor, ids) = draw(&events, &mut state, 10);
+        assert_eq!(cursor, "14");
+        assert_eq!(ids.first().map(String::as_str), Some("5"));
+        
+        state.prev_page();
+        state.prev_page();
+        assert_eq!(draw(&events, &mut state, 10).0, "0");
+        
+        // an empty list has nowhere to go
+        let mut empty_state = EventListState::new(0, None);
+        empty_state.go_end();
+        empty_state.next_page();
+        empty_state.prev_event();
+        assert_eq!(draw(&[], &mut empty_state, 10), (String::new(), Vec::new()));
+    }
+
+    #[test]
+    fn keeps_the_cursor_across_resizes() {
+        let events = events(25);
+        
+        // focusing before the list is drawn scrolls once its height is known
+        let mut state = EventListState::new(events.len(), Some(0));
+        state.focus_event(24);
+        let (cursor, ids) = draw(&events, &mut state, 10);
+        assert_eq!(cursor, "24");
+        assert_eq!(ids.len(), 10);
+        
+        state.focus_event(12);
+        assert_eq!(draw(&events, &mut state, 10).0, "12");
+        assert_eq!(draw(&events, &mut state, 3).0, "12");
+        let (cursor, ids) = draw(&events, &mut state, 20);
+        assert_eq!(cursor, "12");
+        assert_eq!(ids.len(), 20);
+        
+        // a list too short to show anything keeps its place, and can still be focused
+        draw(&events, &mut state, 0);
+        assert_eq!(draw(&events, &mut state, 10).0, "12");
+        draw(&events, &mut state, 0);
+        state.focus_event(5);
+        assert_eq!(draw(&events, &mut state, 10).0, "5");
+        
+        // rows past the end of the list are ignored
+        state.focus_event(25);
+        assert_eq!(draw(&events, &mut state, 10).0, "5");
+    }
+
+    #[test]
+    fn keeps_the_cursor_line_when_rows_change() {
+        let events = events(25);
+        let mut state = EventListState::new(events.len(), Some(0));
+        state.focus_event(13);
+        let (_, ids) = draw(&events, &mut state, 10);
+        let screen_line = ids.iter().position(|id| id == "13");
+        
+        // more rows were added, and the cursor's event moved down two rows
+        state.set_rows(events.len(), 15);
+        let (cursor, ids) = draw(&events, &mut state, 10);
+        assert_eq!(cursor, "15");
+        assert_eq!(ids.iter().position(|id| id == "15"), screen_line);
+        
+        // rows past the cursor were filtered out
+        state.set_rows(3, 15);
+        assert_eq!(draw(&events[..3], &mut state, 10).0, "2");
+    }
+
+    #[test]
+    fn lists_changes_after_each_row() {
+        let slot = |status: &str| AbstractObject::new("Slot").with_prop("slotNum", 1i64).with_prop("status", status);
+        let events = log([("server1", slot("ACCEPTED")), ("server1", slot("CHOSEN"))]);
+        let mut history = HistoryState::build(&events, Some(PropPath { segments: vec!["slotNum".to_string()] }));
+        history.toggle_shown_in_list();
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 4)).expect("The test backend should open");
+        let matching_events = BTreeSet::new();
+        let mut state = EventListState::new(events.len(), Some(0));
+        terminal.draw(|frame| {
+            let event_list = EventList::new(&events, &matching_events, true).with_history(&history);
+            frame.render_stateful_widget(event_list, frame.size(), &mut state)
+        }).expect("Drawing should succeed");
+
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| (1..79).map(|x| buffer.get(x, y).symbol()).collect::<String>();
+        // the first state of the slot has nothing to compare with
+        assert!(!row(1).contains('Δ'));
+        let changed_row = row(2);
+        assert!(changed_row.trim_end().ends_with("Δ status"), "{}", changed_row);
+        let summary_start = changed_row.chars().position(|c| c == 'Δ').expect("The summary should be shown");
+        assert!(changed_row.chars().position(|c| c == ')').is_some_and(|text_end| text_end < summary_start));
+        assert_eq!(buffer.get(summary_start as u16 + 1, 2).fg, Color::Magenta);
+    }
 }
@@ -0,0 +1,61 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Widget};
+use crate::dsl::explain::ExplainNode;
+
+/// shows how each part of the active query evaluated against the selected event
+pub struct ExplainView<'a> {
+    event_id: usize,
+    matched: bool,
+    nodes: &'a [ExplainNode],
+}
+
+impl<'a> ExplainView<'a> {
+    pub fn new(event_id: usize, matched: bool, nodes: &'a [ExplainNode]) -> Self {
+        Self {
+            event_id,
+            matched,
+            nodes,
+        }
+    }
+}
+
+/// writes the node and everything under it, indenting each level
+fn push_lines<'a>(node: &'a ExplainNode, depth: usize, lines: &mut Vec<Line<'a>>) {
+    let (marker, marker_style) = match node.outcome() {
+        Ok(true) => ("✓".to_string(), Style::default().fg(Color::Green)),
+        Ok(false) => ("✗".to_string(), Style::default().fg(Color::Red)),
+        Err(err) => (format!("! {}", err), Style::default().fg(Color::Yellow)),
+    };
+
+    let mut spans = vec![
+        Span::raw("  ".repeat(depth)),
+        Span::styled(marker, marker_style),
+        Span::raw(" "),
+        Span::raw(node.label()),
+    ];
+    if let Some(detail) = node.detail() {
+        spans.push(Span::styled(format!("  {}", detail), Style::default().fg(Color::DarkGray)));
+    }
+    lines.push(Line::from(spans));
+
+    for child in node.children() {
+        push_lines(child, depth + 1, lines);
+    }
+}
+
+impl<'a> Widget for ExplainView<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut lines = Vec::new();
+        for node in self.nodes {
+            push_lines(node, 0, &mut lines);
+        }
+
+        let title = format!("Explain event {}: {}", self.event_id, if self.matched { "matched" } else { "did not match" });
+        Paragraph::new(lines)
+            .block(Block::bordered().title(title))
+            .render(area, buf);
+    }
+}
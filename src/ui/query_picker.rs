@@ -0,0 +1,65 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget, Widget};
+use crate::app::picker_state::PickerState;
+use crate::dsl::macros::{QueryLibrary, PROJECT_FILE_NAME};
+
+/// lists the named queries from the project file
+pub struct QueryPicker<'a> {
+    library: &'a QueryLibrary,
+    state: &'a PickerState,
+    selected: bool,
+}
+
+impl<'a> QueryPicker<'a> {
+    pub fn new(library: &'a QueryLibrary, state: &'a PickerState, selected: bool) -> Self {
+        Self {
+            library,
+            state,
+            selected,
+        }
+    }
+}
+
+impl<'a> Widget for QueryPicker<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let items = self.library.macros().iter()
+            .map(|query_macro| {
+                let mut title = vec![Span::styled(query_macro.signature(), Style::default().add_modifier(Modifier::BOLD))];
+                if let Some(description) = query_macro.description() {
+                    title.push(Span::raw(format!("  {}", description)));
+                }
+
+                let body = query_macro.body().lines()
+                    .next()
+                    .unwrap_or_default();
+                let body_line = Line::styled(format!("    {}", body), Style::default().fg(Color::DarkGray));
+                ListItem::new(Text::from(vec![Line::from(title), body_line]))
+            })
+            .collect::<Vec<_>>();
+
+        let block = Block::bordered()
+            .border_style(if self.selected {
+                Style::default().fg(Color::Blue)
+            } else {
+                Style::default()
+            })
+            .title("Named Queries");
+
+        if items.is_empty() {
+            let hint = format!("No named queries. Add `define name(params) = query` lines to a {} file", PROJECT_FILE_NAME);
+            Widget::render(Paragraph::new(hint).block(block), area, buf);
+            return;
+        }
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_spacing(HighlightSpacing::Always);
+
+        let mut list_state = ListState::default().with_selected(Some(self.state.cursor()));
+        StatefulWidget::render(list, area, buf, &mut list_state);
+    }
+}
@@ -0,0 +1,40 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Row, Table, Widget};
+use crate::dsl::aggregate::QueryTable;
+
+pub struct QueryResultsWidget<'table> {
+    table: &'table QueryTable,
+}
+
+impl<'table> QueryResultsWidget<'table> {
+    pub fn new(table: &'table QueryTable) -> Self {
+        Self { table }
+    }
+}
+
+impl<'table> Widget for QueryResultsWidget<'table> {
+    fn render(self, area: Rect, buf: &mut Buffer) where Self: Sized {
+        let widths = self.table.column_widths()
+            .into_iter()
+            .map(|width| Constraint::Min(width as u16))
+            .collect::<Vec<_>>();
+        
+        let header = Row::new(self.table.headers().clone())
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        
+        let rows = self.table.rows()
+            .iter()
+            .map(|row| Row::new(row.clone()));
+        
+        Table::new(rows, widths)
+            .header(header)
+            .column_spacing(2)
+            .block(
+                Block::bordered()
+                    .title("Query Results")
+            )
+            .render(area, buf)
+    }
+}
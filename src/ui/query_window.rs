@@ -2,25 +2,114 @@ use std::marker::PhantomData;
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::prelude::StatefulWidget;
-use ratatui::style::{Color, Style};
-use ratatui::widgets::{Block, Widget};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, HighlightSpacing, List, ListState, Paragraph, Widget, Wrap};
 use tui_textarea::TextArea;
+use crate::dsl::diagnostics::QueryDiagnostic;
 
-pub struct QueryWindow<'a> {
+pub struct QueryWindow<'a, 'b> {
     text_editor: PhantomData<TextArea<'a>>,
     selected: bool,
+    /// the syntax error to show under the editor, if any
+    error: Option<&'b QueryDiagnostic>,
+    /// the completions to show in a popup at the cursor, and which one is highlighted
+    completions: Option<(&'b [String], usize)>,
 }
 
-impl<'a> QueryWindow<'a> {
+impl<'a, 'b> QueryWindow<'a, 'b> {
     pub fn new(selected: bool) -> Self {
         Self {
             text_editor: Default::default(),
-            selected
+            selected,
+            error: None,
+            completions: None,
         }
     }
+
+    pub fn with_error(mut self, error: Option<&'b QueryDiagnostic>) -> Self {
+        self.error = error;
+        self
+    }
+
+    pub fn with_completions(mut self, candidates: &'b [String], selected: usize) -> Self {
+        if !candidates.is_empty() {
+            self.completions = Some((candidates, selected));
+        }
+        self
+    }
+}
+
+/// renders the offending line with the bad span highlighted, carets underneath, and the message
+fn error_lines(error: &QueryDiagnostic) -> Vec<Line<'_>> {
+    let line_text = error.line_text();
+    let span = error.line_span();
+    let error_style = Style::default().fg(Color::Red);
+
+    let source_line = Line::from(vec![
+        Span::raw(&line_text[..span.start]),
+        Span::styled(&line_text[span.clone()], error_style.add_modifier(Modifier::UNDERLINED | Modifier::BOLD)),
+        Span::raw(&line_text[span.end..]),
+    ]);
+
+    vec![
+        source_line,
+        Line::styled(error.caret_line(), error_style),
+        Line::styled(format!("line {} col {}: {}", error.line(), error.column(), error.message()), error_style),
+    ]
+}
+
+/// the most completions shown in the popup at once
+const MAX_POPUP_ROWS: u16 = 8;
+
+/// where the editor drew its cursor. tui-textarea keeps how far it has scrolled to itself, so this
+/// looks for the cell drawn with the cursor style
+fn drawn_cursor(editor_area: Rect, cursor_style: Style, buf: &Buffer) -> Option<(u16, u16)> {
+    for y in editor_area.top()..editor_area.bottom() {
+        for x in editor_area.left()..editor_area.right() {
+            let cell = buf.get(x, y);
+            let matches_colors = cursor_style.fg.is_none_or(|fg| cell.fg == fg) && cursor_style.bg.is_none_or(|bg| cell.bg == bg);
+            if matches_colors && cell.modifier.contains(cursor_style.add_modifier) {
+                return Some((x, y));
+            }
+        }
+    }
+
+    None
 }
 
-impl<'a> StatefulWidget for QueryWindow<'a> {
+/// draws the completion popup just under the cursor, which is at `(x, y)` on the screen, keeping it
+/// inside the editor
+fn render_completions(candidates: &[String], selected: usize, cursor: (u16, u16), editor_area: Rect, buf: &mut Buffer) {
+    let width = candidates.iter()
+        .map(|candidate| candidate.chars().count() as u16)
+        .max()
+        .unwrap_or_default()
+        .saturating_add(4)
+        .min(editor_area.width);
+    let height = (candidates.len() as u16).min(MAX_POPUP_ROWS).saturating_add(2).min(editor_area.height);
+
+    let (cursor_x, cursor_y) = cursor;
+    let x = cursor_x.min(editor_area.right().saturating_sub(width));
+    let below = cursor_y + 1;
+    let y = if below + height <= editor_area.bottom() {
+        below
+    } else {
+        cursor_y.saturating_sub(height).max(editor_area.y)
+    };
+    let popup_area = Rect::new(x, y, width, height);
+
+    let list = List::new(candidates.iter().map(|candidate| candidate.as_str()))
+        .block(Block::bordered().border_style(Style::default().fg(Color::Blue)))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_spacing(HighlightSpacing::Never);
+    let mut list_state = ListState::default().with_selected(Some(selected));
+
+    Clear.render(popup_area, buf);
+    StatefulWidget::render(list, popup_area, buf, &mut list_state);
+}
+
+impl<'a, 'b> StatefulWidget for QueryWindow<'a, 'b> {
     type State = TextArea<'a>;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
@@ -31,9 +120,58 @@ impl<'a> StatefulWidget for QueryWindow<'a> {
                 Style::default()
             })
             .title("Query Editor");
-        
+
         let block_area = block.inner(area);
-        Widget::render(state.widget(), block_area, buf);
+        let editor_area = match self.error {
+            Some(error) => {
+                let [editor_area, error_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(4)])
+                    .areas(block_area);
+
+                Paragraph::new(error_lines(error))
+                    .block(Block::default().borders(Borders::TOP).border_style(Style::default().fg(Color::Red)))
+                    .wrap(Wrap { trim: false })
+                    .render(error_area, buf);
+                editor_area
+            }
+            None => block_area,
+        };
+        Widget::render(state.widget(), editor_area, buf);
         block.render(area, buf);
+
+        let cursor = drawn_cursor(editor_area, state.cursor_style(), buf);
+        if let (Some((candidates, selected)), Some(cursor)) = (self.completions, cursor) {
+            render_completions(candidates, selected, cursor, editor_area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use tui_textarea::{CursorMove, TextArea};
+    use crate::ui::query_window::QueryWindow;
+
+    #[test]
+    fn completions_follow_the_scrolled_cursor() {
+        let lines = (0..10).map(|idx| format!("line {}", idx)).collect::<Vec<_>>();
+        let mut text_area = TextArea::new(lines);
+        text_area.move_cursor(CursorMove::Bottom);
+        text_area.move_cursor(CursorMove::End);
+        let candidates = ["union".to_string()];
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 8)).unwrap();
+        terminal.draw(|frame| {
+            let window = QueryWindow::new(true).with_completions(&candidates, 0);
+            frame.render_stateful_widget(window, frame.size(), &mut text_area);
+        }).unwrap();
+
+        // the editor shows 6 lines, so it has scrolled to put the last one on the bottom row. There's
+        // no room under it, so the popup goes above it
+        let rows = (0..8)
+            .map(|y| (0..20).map(|x| terminal.backend().buffer().get(x, y).symbol()).collect::<String>())
+            .collect::<Vec<_>>();
+        assert_eq!(rows[6], "│line 9            │");
+        assert!(rows[4].contains("union"), "{:?}", rows);
     }
 }
@@ -0,0 +1,96 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, HighlightSpacing, List, ListItem, ListState, StatefulWidget, Widget};
+use crate::app::schema_state::{SchemaRow, SchemaState};
+use crate::ds_events::schema::{FieldSchema, LogSchema};
+
+/// lists every object name in the log, with the fields of expanded objects under them
+pub struct SchemaBrowser<'a> {
+    schema: &'a LogSchema,
+    state: &'a SchemaState,
+    selected: bool,
+}
+
+impl<'a> SchemaBrowser<'a> {
+    pub fn new(schema: &'a LogSchema, state: &'a SchemaState, selected: bool) -> Self {
+        Self {
+            schema,
+            state,
+            selected,
+        }
+    }
+
+    fn object_line(&self, name: &'a str) -> Line<'a> {
+        let marker = if self.state.is_expanded(name) { "▾ " } else { "▸ " };
+        let event_count = self.schema.object(name)
+            .map(|object| object.event_count())
+            .unwrap_or_default();
+
+        Line::from(vec![
+            Span::raw(marker),
+            Span::styled(name, Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(format!("  {} events", event_count), Style::default().fg(Color::DarkGray)),
+        ])
+    }
+
+    fn field_line(&self, object: &'a str, path: &'a str) -> Line<'a> {
+        let Some(object_schema) = self.schema.object(object) else {
+            return Line::raw(path);
+        };
+        let Some(field) = object_schema.field(path) else {
+            return Line::raw(path);
+        };
+
+        let depth = path.matches('.').count();
+        let name = path.rsplit('.').next().unwrap_or(path);
+        let frequency = field.count() * 100 / object_schema.event_count().max(1);
+
+        let mut spans = vec![
+            Span::raw("    ".repeat(depth + 1)),
+            Span::styled(name, Style::default().fg(Color::Yellow)),
+            Span::raw(format!("  {} {}%", describe_kinds(field), frequency)),
+        ];
+        if !field.samples().is_empty() {
+            spans.push(Span::styled(format!("  e.g. {}", field.samples().join(", ")), Style::default().fg(Color::DarkGray)));
+        }
+
+        Line::from(spans)
+    }
+}
+
+/// the kinds of values seen for a field, like `numeric` or `numeric|string`
+fn describe_kinds(field: &FieldSchema) -> String {
+    field.kinds().keys()
+        .map(|kind| kind.to_string())
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+impl<'a> Widget for SchemaBrowser<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let items = self.state.rows(self.schema).into_iter()
+            .map(|row| match row {
+                SchemaRow::Object(name) => ListItem::new(self.object_line(name)),
+                SchemaRow::Field { object, path } => ListItem::new(self.field_line(object, path)),
+            })
+            .collect::<Vec<_>>();
+
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .border_style(if self.selected {
+                        Style::default().fg(Color::Blue)
+                    } else {
+                        Style::default()
+                    })
+                    .title("Schema")
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_spacing(HighlightSpacing::Always);
+
+        let mut list_state = ListState::default().with_selected(Some(self.state.cursor()));
+        StatefulWidget::render(list, area, buf, &mut list_state);
+    }
+}